@@ -12,29 +12,56 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use core::dur_hitbox::DurHitbox;
+use core::dur_hitbox::{DurHbVel, DurHitbox};
 use core::events::{EventKey, EventKeysMap, EventManager, InternalEvent};
-use core::grid::Grid;
-use core::{HbGroup, HbId, HbProfile, HbVel, Hitbox, HIGH_TIME};
-use fnv::FnvHashMap;
-use geom::PlacedShape;
+use core::grid::{Grid, GridStats, GroupSet};
+use core::{
+    is_discrete_pair, is_same_team, ColliderError, HbGroup, HbId, HbProfile, HbVel, Hitbox,
+    HIGH_TIME,
+};
+use float::{consts, n64, Float};
+use fnv::{FnvHashMap, FnvHashSet};
+use geom::shape::PlacedBounds;
+use geom::{v2, Aabb, Card, CardMask, DirVec2, PlacedShape, Shape, ShapeKind, Vec2};
+use std::cmp::Reverse;
+use std::collections::VecDeque;
 use std::mem;
-use util::TightSet;
+use util::{quad_root_ascending, TightSet};
 
 // TODO check that floating point values are within a good range when adding/updating hitboxes
 
+// ids assigned to the parts of compound hitboxes start from here
+const COMPOUND_PART_ID_START: HbId = 1 << 63;
+
 /// A structure that tracks hitboxes and returns collide/separate events.
 ///
 /// Collider manages events using a "simulation time" that the user updates as
 /// necessary. This time starts at `0.0`.
 pub struct Collider<P: HbProfile> {
     hitboxes: FnvHashMap<HbId, HitboxInfo<P>>,
-    time: f64,
+    time: Float,
     grid: Grid,
-    padding: f64,
+    padding: Float,
+    separation: Separation,
     events: EventManager,
+    collide_callbacks: FnvHashMap<HbId, CollideCallback<P>>,
+    overlap_start_times: FnvHashMap<(HbId, HbId), Float>,
+    interact_cache: InteractCache,
+    expiry_events: bool,
+    invalidation_events: bool,
+    bounds: Option<PlacedShape>,
+    compounds: FnvHashMap<HbId, Vec<HbId>>,
+    next_part_id: HbId,
+    // the time given by `self.time()` while frozen, during which `self.time`
+    // stays at the time that the collider was frozen
+    frozen: Option<Float>,
+    history: VecDeque<ColliderState<P>>,
+    history_frames: usize,
 }
 
+type CollideCallback<P> = Box<dyn FnMut(P, P) + Send>;
+type InteractCache = FnvHashMap<((u64, Option<HbGroup>), (u64, Option<HbGroup>)), bool>;
+
 impl<P: HbProfile> Collider<P> {
     /// # Constructs a new `Collider` instance.
     ///
@@ -43,7 +70,11 @@ impl<P: HbProfile> Collider<P> {
     /// the cells used in that grid. If your game has a similar grid concept,
     /// then it is usually a good choice to use the same cell width as that
     /// grid. Otherwise, a good choice is to use a width that is slightly larger
-    /// than most of the hitboxes.
+    /// than most of the hitboxes. Groups whose hitboxes are much smaller or
+    /// larger than the rest may be given their own cell width using
+    /// `set_group_cell_width`. Use `ColliderBuilder::cell_dims` for cells that
+    /// are not square, such as in scenes that are much wider than they are
+    /// tall.
     ///
     /// Collider generates both `Collide` and `Separate` events. However, due to
     /// numerical error, it is important that two hitboxes be a certain small
@@ -55,22 +86,35 @@ impl<P: HbProfile> Collider<P> {
     /// the user, perhaps a fraction of a "pixel."
     ///
     /// Another restriction introduced by `padding` is that hitboxes are not
-    /// allowed to have a width or height smaller than `padding`.
-    pub fn new(cell_width: f64, padding: f64) -> Collider<P> {
-        assert!(cell_width > padding, "requires cell_width > padding");
-        assert!(padding > 0.0, "requires padding > 0.0");
-        Collider {
-            hitboxes: FnvHashMap::default(),
-            time: 0.0,
-            grid: Grid::new(cell_width),
-            padding,
-            events: EventManager::new(),
-        }
+    /// allowed to have a width or height smaller than `padding`. Use
+    /// `ColliderBuilder` to configure the separation distance independently
+    /// of the minimum hitbox size.
+    pub fn new(cell_width: Float, padding: Float) -> Collider<P> {
+        ColliderBuilder::new()
+            .cell_width(cell_width)
+            .padding(padding)
+            .build()
+    }
+
+    /// Reserves capacity for at least `hitboxes` more hitboxes to be added.
+    ///
+    /// This is only a performance hint, which avoids repeatedly growing the
+    /// internal hash maps when loading a large number of hitboxes at once. The
+    /// capacity of the grid is estimated from the number of hitboxes.
+    pub fn reserve(&mut self, hitboxes: usize) {
+        self.hitboxes.reserve(hitboxes);
+        self.grid.reserve(hitboxes);
     }
 
     /// Returns the current simulation time.
-    pub fn time(&self) -> f64 {
-        self.time
+    pub fn time(&self) -> Float {
+        self.frozen.unwrap_or(self.time)
+    }
+
+    // the time that has passed since the collider was frozen, which is added to
+    // the absolute times given to the user while frozen
+    fn frozen_elapsed(&self) -> Float {
+        self.frozen.map_or(0.0, |time| time - self.time)
     }
 
     /// Returns the time at which `self.next()` needs to be called again.
@@ -82,23 +126,418 @@ impl<P: HbProfile> Collider<P> {
     /// `self.time()` again.
     ///
     /// This is a fast constant-time operation.  The result may be infinity.
-    pub fn next_time(&self) -> f64 {
-        self.events.peek_time()
+    pub fn next_time(&self) -> Float {
+        self.events.peek_time() + self.frozen_elapsed()
+    }
+
+    /// Returns the event that will be processed next, along with the time at
+    /// which it will occur, without processing it.
+    ///
+    /// Returns `None` if there are no events, or if the next event is an
+    /// internal event that will not be returned by `next`. Processing that
+    /// internal event may change which events follow it. The ids are given in
+    /// increasing order. For an event involving a compound hitbox (see
+    /// `add_compound`), the id of the compound is given, and an event between
+    /// parts that is not returned for the compound as a whole (such as a second
+    /// part starting to overlap the same hitbox) is treated as internal.
+    pub fn peek_event(&self) -> Option<(HbEvent, HbId, HbId, Float)> {
+        let (time, event) = self.events.peek()?;
+        let (event, id_1, id_2) = self.user_event(event)?;
+        if self.is_duplicate_compound_event(event, id_1, id_2) {
+            return None;
+        }
+        let (id_1, id_2) = (self.parent_id(id_1), self.parent_id(id_2));
+        Some((
+            event,
+            id_1.min(id_2),
+            id_1.max(id_2),
+            time + self.frozen_elapsed(),
+        ))
+    }
+
+    // returns the user event that an internal event will be processed into,
+    // if any, before compound duplicates are removed
+    fn user_event(&self, event: InternalEvent) -> Option<(HbEvent, HbId, HbId)> {
+        let result = match event {
+            InternalEvent::Collide(id_1, id_2) => new_event(HbEvent::Collide, id_1, id_2),
+            InternalEvent::Separate(id_1, id_2) => new_event(HbEvent::Separate, id_1, id_2),
+            InternalEvent::Expire(id) => (HbEvent::Expire, id, id),
+            InternalEvent::ExitBounds(id) => (HbEvent::ExitBounds, id, id),
+            InternalEvent::PanicSmallHitbox(id) if self.invalidation_events => {
+                (HbEvent::Invalidated(InvalidationReason::TooSmall), id, id)
+            }
+            InternalEvent::PanicDurationPassed(id) if self.invalidation_events => (
+                HbEvent::Invalidated(InvalidationReason::DurationPassed),
+                id,
+                id,
+            ),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// Returns true if the event at `self.next_time()` is an internal event
+    /// that will not be returned by `next`.
+    ///
+    /// Returns false if there are no events. When this returns true, calling
+    /// `self.next()` at `self.next_time()` will return `None` (unless another
+    /// event is scheduled for the same time), although processing the internal
+    /// event may schedule new events.
+    pub fn next_is_internal(&self) -> bool {
+        self.events.peek().is_some() && self.peek_event().is_none()
+    }
+
+    /// Returns the events that `self.next()` would return between
+    /// `self.time()` and `self.time() + horizon`, assuming that no hitboxes are
+    /// changed in the meantime, along with the time of each event.
+    ///
+    /// The events are found by running the simulation forward on a copy of
+    /// this collider, so `self` is not modified and no `on_collide` callbacks
+    /// are invoked. The cost is comparable to advancing the collider by
+    /// `horizon`. If a hitbox would become invalid (see
+    /// `set_invalidation_events`) while invalidation events are disabled, then
+    /// only the events before that point are returned. Returns no events while
+    /// the collider is frozen. Panics if `horizon` is negative or not finite.
+    pub fn events_within(&self, horizon: Float) -> Vec<(HbEvent, HbId, HbId, Float)> {
+        assert!(
+            horizon >= 0.0 && horizon.is_finite(),
+            "horizon must be non-negative and finite"
+        );
+        let mut result = Vec::new();
+        if self.frozen.is_some() {
+            return result;
+        }
+        let mut preview = Collider::from_state(self.snapshot());
+        preview.interact_cache = self.interact_cache.clone();
+        // avoids panicking in the preview; these events are not returned unless
+        // the user has enabled them
+        preview.invalidation_events = true;
+        let end_time = self.time + horizon;
+        while preview.next_time() <= end_time {
+            let time = preview.next_time();
+            preview.set_time(time);
+            while let Some((event, profile_1, profile_2)) = preview.next() {
+                if let HbEvent::Invalidated(_) = event {
+                    if !self.invalidation_events {
+                        return result;
+                    }
+                }
+                result.push((event, profile_1.id(), profile_2.id(), time));
+            }
+        }
+        result
     }
 
     /// Advances the simulation time to the given value.
     ///
     /// The positions of all hitboxes will be updated based on the velocities of
     /// the hitboxes. Will panic if `time` exceeds `self.next_time()`. Will also
-    /// panic if `time` is less than `self.time()` (i.e. cannot rewind time).
+    /// panic if `time` is less than `self.time()` (i.e. cannot rewind time), or
+    /// if `time` is not below `1e50` (`1e8` with the `f32` feature).
     ///
     /// The hitboxes are updated implicitly, and this is actually a
-    /// fast constant-time operation.
-    pub fn set_time(&mut self, time: f64) {
-        assert!(time >= self.time, "cannot rewind time");
-        assert!(time <= self.next_time(), "time must not exceed next_time()");
+    /// fast constant-time operation, unless history is enabled (see
+    /// `enable_history`). While frozen (see `set_frozen`), `time` may exceed
+    /// `self.next_time()`.
+    pub fn set_time(&mut self, time: Float) {
+        assert!(time >= self.time(), "cannot rewind time");
+        assert!(
+            self.frozen.is_some() || time <= self.next_time(),
+            "time must not exceed next_time()"
+        );
         assert!(time < HIGH_TIME, "time must not exceed {}", HIGH_TIME);
-        self.time = time;
+        if self.history_frames > 0 && time > self.time() {
+            if self.history.len() == self.history_frames {
+                self.history.pop_front();
+            }
+            let snapshot = self.snapshot();
+            self.history.push_back(snapshot);
+        }
+        if self.frozen.is_some() {
+            self.frozen = Some(time);
+        } else {
+            self.time = time;
+        }
+    }
+
+    /// Freezes or thaws the motion of all hitboxes.
+    ///
+    /// While frozen, `set_time` still advances `self.time()`, but every hitbox
+    /// keeps its current position and no new events come due. Absolute times
+    /// given by the collider while frozen (such as `self.next_time()` and the
+    /// `end_time` of hitbox velocities) are shifted forward by the time spent
+    /// frozen, and absolute times given to the collider are shifted back, so
+    /// the relative timing of everything is unchanged. When thawing, all
+    /// absolute times stored by the collider are shifted forward once: the
+    /// time at which each hitbox was last updated (from which its motion is
+    /// measured), the `end_time` of its velocity, the start times of overlaps,
+    /// and the times of all scheduled events. The simulation then resumes
+    /// exactly where it left off. The user must likewise shift any absolute
+    /// times they hold by the time spent frozen, including the `end_time` of
+    /// velocities given while frozen.
+    ///
+    /// Events that were already due when the collider was frozen are still
+    /// returned by `self.next()`. Hitboxes may be added, updated, and removed
+    /// while frozen as usual. Calls to `set_time` while frozen take constant
+    /// time, while thawing takes time proportional to the number of hitboxes
+    /// and scheduled events.
+    pub fn set_frozen(&mut self, frozen: bool) {
+        if frozen {
+            if self.frozen.is_none() {
+                self.frozen = Some(self.time);
+            }
+        } else if let Some(time) = self.frozen.take() {
+            let offset = self.time - time;
+            self.shift_times(offset);
+            self.time = time;
+        }
+    }
+
+    /// Returns true if the collider is frozen (see `set_frozen`).
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.is_some()
+    }
+
+    /// Shifts the simulation time back to `0.0`, preserving the relative timing
+    /// of all hitboxes and scheduled events.
+    ///
+    /// `set_time` may not exceed an upper bound of `1e50`, or just `1e8` with
+    /// the `f32` feature, so long-running simulations should call this
+    /// periodically. All absolute times stored by
+    /// the collider, such as the `end_time` of hitbox velocities and the start
+    /// times of overlaps, are reduced by the previous value of `self.time()`,
+    /// and the user must do the same for any absolute times they hold. Any
+    /// history snapshots are discarded.
+    pub fn rebase_time(&mut self) {
+        // the time spent frozen so far is applied first
+        let frozen = self.is_frozen();
+        self.set_frozen(false);
+        let offset = self.time;
+        self.shift_times(offset);
+        self.history.clear();
+        self.time = 0.0;
+        self.set_frozen(frozen);
+    }
+
+    // subtracts `offset` from all absolute times stored by the collider,
+    // except for `self.time`
+    fn shift_times(&mut self, offset: Float) {
+        for info in self.hitboxes.values_mut() {
+            info.start_time -= offset;
+            info.pub_end_time -= offset;
+            info.hitbox.vel.end_time -= offset;
+            let keys: Vec<EventKey> = info.event_keys.drain().collect();
+            for key in keys {
+                info.event_keys.insert(key.rebased(offset));
+            }
+        }
+        self.events.rebase(offset);
+        for start_time in self.overlap_start_times.values_mut() {
+            *start_time -= offset;
+        }
+    }
+
+    /// Retains up to `frames` snapshots of the collider state for use with
+    /// `rewind_to`.
+    ///
+    /// Once enabled, every call to `set_time` that advances the time will
+    /// first record a copy of the current state, discarding the oldest copy
+    /// if more than `frames` are retained. This has a memory and time cost
+    /// proportional to the number of hitboxes, so it is mostly intended for
+    /// debugging. Passing `0` disables history and discards all snapshots.
+    pub fn enable_history(&mut self, frames: usize) {
+        self.history_frames = frames;
+        while self.history.len() > frames {
+            self.history.pop_front();
+        }
+    }
+
+    /// Enables or disables `Expire` events, which are returned from
+    /// `self.next()` when a hitbox reaches the `end_time` of its velocity.
+    ///
+    /// By default, a hitbox must be updated before its `end_time` passes, and
+    /// failing to do so will panic in debug mode. When expiry events are
+    /// enabled, an `Expire` event is returned instead, and the hitbox is
+    /// stopped at its final position with an infinite `end_time` so that the
+    /// user may decide what to do with it. Events are rescheduled for existing
+    /// hitboxes.
+    pub fn set_expiry_events(&mut self, enabled: bool) {
+        if self.expiry_events != enabled {
+            self.expiry_events = enabled;
+            let ids: Vec<HbId> = self.hitboxes.keys().cloned().collect();
+            for id in ids {
+                self.internal_update_hitbox(id, None);
+            }
+        }
+    }
+
+    /// Enables or disables `Invalidated` events, which are returned from
+    /// `self.next()` when a hitbox can no longer be simulated correctly.
+    ///
+    /// A hitbox is invalidated when it shrinks below the minimum size allowed
+    /// by `padding`, or when it reaches the `end_time` of its velocity while
+    /// expiry events are disabled. By default, these conditions panic in debug
+    /// mode and are silently ignored in release mode. When invalidation events
+    /// are enabled, an `Invalidated` event is returned in either build mode
+    /// instead, and the hitbox is stopped at its current position with an
+    /// infinite `end_time`. Events are rescheduled for existing hitboxes.
+    ///
+    /// A hitbox that was invalidated for being too small is smaller than
+    /// `padding`, so it may only be given velocities that do not shrink it
+    /// further (see `set_hitbox_vel`). Otherwise, it should be removed.
+    pub fn set_invalidation_events(&mut self, enabled: bool) {
+        if self.invalidation_events != enabled {
+            self.invalidation_events = enabled;
+            let ids: Vec<HbId> = self.hitboxes.keys().cloned().collect();
+            for id in ids {
+                self.internal_update_hitbox(id, None);
+            }
+        }
+    }
+
+    /// Sets the bounds of the world, or removes them if `None` is given.
+    ///
+    /// While bounds are set, an `ExitBounds` event is returned from
+    /// `self.next()` when the bounding box of a hitbox begins to leave the
+    /// bounds. The exit time is solved for each hitbox directly, so no extra
+    /// hitboxes are placed at the boundary. After an `ExitBounds` event, no
+    /// further event is generated for that hitbox until it is updated while
+    /// its bounding box lies inside the bounds by at least `padding`. Hitboxes
+    /// that are outside of the bounds when they are added (or when the bounds
+    /// are set) do not generate an event. Rotating hitboxes are enlarged to
+    /// cover their rotation (see `HbVel::angular`), so their events may occur
+    /// slightly early.
+    ///
+    /// `bounds` must be a `Rect`.
+    pub fn set_bounds(&mut self, bounds: Option<PlacedShape>) {
+        if let Some(bounds) = bounds {
+            assert!(bounds.kind() == ShapeKind::Rect, "bounds must be a rect");
+        }
+        self.bounds = bounds;
+        let ids: Vec<HbId> = self.hitboxes.keys().cloned().collect();
+        for id in ids {
+            self.hitboxes.get_mut(&id).unwrap().out_of_bounds = false;
+            self.internal_update_hitbox(id, None);
+        }
+    }
+
+    /// Returns the bounds of the world set by `set_bounds`, if any.
+    pub fn bounds(&self) -> Option<PlacedShape> {
+        self.bounds
+    }
+
+    /// Returns the profiles of all hitboxes whose bounding box is not
+    /// contained in the bounds at the current time, sorted by id.
+    ///
+    /// Returns an empty `Vec` if no bounds are set.
+    pub fn out_of_bounds(&self) -> Vec<P> {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return Vec::new(),
+        };
+        let mut result: Vec<P> = self
+            .hitboxes
+            .values()
+            .filter(|info| {
+                let shape = info.pub_hitbox_at_time(self.time).value;
+                Card::values()
+                    .iter()
+                    .any(|&card| bounds.edge(card) < shape.edge(card))
+            })
+            .map(|info| info.profile)
+            .collect();
+        result.sort_by_key(|profile| profile.id());
+        result.dedup_by_key(|profile| profile.id());
+        result
+    }
+
+    /// Returns the number of snapshots that are currently retained.
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Restores the collider to the state it had before a previous call to
+    /// `set_time`.
+    ///
+    /// `frame` 0 restores the most recent snapshot, 1 the snapshot before
+    /// that, and so on. The restored snapshot and all newer snapshots are
+    /// discarded. Callbacks registered with `on_collide` are not part of the
+    /// history, although callbacks for hitboxes that do not exist in the
+    /// restored state are dropped. Will panic if `frame` is not less than
+    /// `self.history_len()`.
+    pub fn rewind_to(&mut self, frame: usize) {
+        assert!(frame < self.history.len(), "frame is not in history");
+        let len = self.history.len() - frame;
+        self.history.truncate(len);
+        let state = self.history.pop_back().unwrap();
+        self.restore(state);
+    }
+
+    /// Returns a copy of the current simulation state, which may later be
+    /// passed to `restore`.
+    ///
+    /// The state includes the hitboxes, the time, all pending events, and all
+    /// settings (such as the grid cell dims, bounds, and whether the collider
+    /// is frozen), so restoring it and replaying the same calls will produce
+    /// the same sequence of events. Callbacks registered with `on_collide` and
+    /// the history are not part of the state.
+    pub fn snapshot(&self) -> ColliderState<P> {
+        ColliderState {
+            hitboxes: self.hitboxes.clone(),
+            time: self.time,
+            grid: self.grid.clone(),
+            padding: self.padding,
+            separation: self.separation.clone(),
+            events: self.events.clone(),
+            overlap_start_times: self.overlap_start_times.clone(),
+            expiry_events: self.expiry_events,
+            invalidation_events: self.invalidation_events,
+            bounds: self.bounds,
+            compounds: self.compounds.clone(),
+            next_part_id: self.next_part_id,
+            frozen: self.frozen,
+        }
+    }
+
+    /// Restores a state previously returned by `snapshot`.
+    ///
+    /// Callbacks for hitboxes that do not exist in the restored state are
+    /// dropped. The history is left unchanged.
+    pub fn restore(&mut self, state: ColliderState<P>) {
+        // the interaction cache is not kept, since the restored profiles may
+        // differ from the cached ones
+        let mut restored = Collider::from_state(state);
+        mem::swap(&mut restored.collide_callbacks, &mut self.collide_callbacks);
+        mem::swap(&mut restored.history, &mut self.history);
+        restored.history_frames = self.history_frames;
+        *self = restored;
+        let hitboxes = &self.hitboxes;
+        let compounds = &self.compounds;
+        self.collide_callbacks
+            .retain(|id, _| hitboxes.contains_key(id) || compounds.contains_key(id));
+    }
+
+    // constructs a collider with the given state, no callbacks, and no history
+    fn from_state(state: ColliderState<P>) -> Collider<P> {
+        Collider {
+            hitboxes: state.hitboxes,
+            time: state.time,
+            grid: state.grid,
+            padding: state.padding,
+            separation: state.separation,
+            events: state.events,
+            collide_callbacks: FnvHashMap::default(),
+            overlap_start_times: state.overlap_start_times,
+            interact_cache: FnvHashMap::default(),
+            expiry_events: state.expiry_events,
+            invalidation_events: state.invalidation_events,
+            bounds: state.bounds,
+            compounds: state.compounds,
+            next_part_id: state.next_part_id,
+            frozen: state.frozen,
+            history: VecDeque::new(),
+            history_frames: 0,
+        }
     }
 
     /// Processes and returns the next `Collide` or `Separate` event, or returns
@@ -109,18 +548,145 @@ impl<P: HbProfile> Collider<P> {
     ///
     /// The returned value is a tuple, denoting the type of event (`Collide` or
     /// `Separate`) and the two hitbox profiles involved, in increasing order by
-    /// `HbId`.
+    /// `HbId`. If expiry events are enabled (see `set_expiry_events`), an
+    /// `Expire` event may also be returned, in which case both profiles are
+    /// those of the expired hitbox. Likewise, if bounds are set (see
+    /// `set_bounds`), an `ExitBounds` event may be returned for a single
+    /// hitbox, and if invalidation events are enabled (see
+    /// `set_invalidation_events`), an `Invalidated` event may be returned for a
+    /// single hitbox.
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<(HbEvent, P, P)> {
-        while let Some(event) = self.events.next(self.time, &mut self.hitboxes) {
-            if let Some((event, id_1, id_2)) = self.process_event(event) {
-                return Some((
+        self.next_bounded(usize::MAX)
+    }
+
+    /// Like `self.next()`, but returns `None` instead of processing more than
+    /// `max_internal` internal events (such as hitboxes moving between grid
+    /// cells) that are not returned to the user.
+    ///
+    /// This allows the work of processing many simultaneous events to be
+    /// spread across several frames. No events are lost when the limit is
+    /// reached, and further calls will resume processing where this call left
+    /// off. Since `None` may be returned before all events at the current time
+    /// have been processed, use `self.next_time() > self.time()` to check
+    /// whether any events remain.
+    pub fn next_bounded(&mut self, max_internal: usize) -> Option<(HbEvent, P, P)> {
+        self.next_event_ids(max_internal)
+            .map(|(event, id_1, id_2)| {
+                (
                     event,
                     self.hitboxes[&id_1].profile,
                     self.hitboxes[&id_2].profile,
-                ));
+                )
+            })
+    }
+
+    // like `next_bounded`, but returns the ids of the hitboxes involved, which
+    // may be the ids of compound parts
+    fn next_event_ids(&mut self, max_internal: usize) -> Option<(HbEvent, HbId, HbId)> {
+        let mut internal_count = 0;
+        loop {
+            if internal_count >= max_internal && self.peek_event().is_none() {
+                return None;
+            }
+            let event = self.events.next(self.time, &mut self.hitboxes)?;
+            // duplicates are found before processing, as `peek_event` does, so
+            // that they count as internal events either way
+            let duplicate = self.user_event(event).is_some_and(|(event, id_1, id_2)| {
+                self.is_duplicate_compound_event(event, id_1, id_2)
+            });
+            if let Some((event, mut id_1, mut id_2)) = self.process_event(event) {
+                if !duplicate {
+                    if self.parent_id(id_1) > self.parent_id(id_2) {
+                        mem::swap(&mut id_1, &mut id_2);
+                    }
+                    if event == HbEvent::Collide {
+                        let profile_1 = self.hitboxes[&id_1].profile;
+                        let profile_2 = self.hitboxes[&id_2].profile;
+                        self.invoke_collide_callbacks(profile_1, profile_2);
+                    }
+                    return Some((event, id_1, id_2));
+                }
             }
+            internal_count += 1;
+        }
+    }
+
+    /// Advances the simulation time to the given value, passing every event
+    /// that occurs up to and including `time` to `on_event`.
+    ///
+    /// This repeatedly calls `self.next()` and `self.set_time(..)`, so that no
+    /// events are skipped. The arguments to `on_event` are the same as the
+    /// values returned from `self.next()`. The callback cannot access the
+    /// collider, so any changes in response to events (such as updating
+    /// hitbox velocities) must be made after this returns, or by calling
+    /// `self.next()` and `self.set_time(..)` directly instead. Panics if `time`
+    /// is less than `self.time()`.
+    pub fn advance_to(&mut self, time: Float, mut on_event: impl FnMut(HbEvent, P, P)) {
+        assert!(time >= self.time(), "cannot rewind time");
+        loop {
+            while let Some((event, profile_1, profile_2)) = self.next() {
+                on_event(event, profile_1, profile_2);
+            }
+            if self.time() == time {
+                break;
+            }
+            let new_time = self.next_time().min(time);
+            self.set_time(new_time);
+        }
+    }
+
+    /// Like `self.next()`, but also returns the geometry of the event.
+    ///
+    /// The normal and contact point are computed from the shapes of the two
+    /// hitboxes at the time of the event. See `DetailedEvent` for details. The
+    /// normal and contact point of `Expire` and `ExitBounds` events are not
+    /// meaningful.
+    pub fn next_detailed(&mut self) -> Option<DetailedEvent<P>> {
+        self.next_event_ids(usize::MAX).map(|(event, id_1, id_2)| {
+            let shape_1 = self.get_hitbox(id_1).value;
+            let shape_2 = self.get_hitbox(id_2).value;
+            DetailedEvent {
+                event,
+                profile_1: self.hitboxes[&id_1].profile,
+                profile_2: self.hitboxes[&id_2].profile,
+                normal: shape_1.normal_from(&shape_2),
+                contact: shape_1.contact_point(&shape_2),
+            }
+        })
+    }
+
+    /// Registers a closure that is invoked whenever the hitbox with the given
+    /// `id` is involved in a `Collide` event, replacing any closure that was
+    /// previously registered for that hitbox.
+    ///
+    /// The closure is invoked from within `self.next()` with the profile of
+    /// the hitbox with `id` followed by the profile of the other hitbox. The
+    /// event is still returned from `self.next()` as usual. Collisions that are
+    /// reported as a hitbox is added are not passed to the closure. The
+    /// closure is dropped when the hitbox is removed.
+    ///
+    /// The closure is owned by the collider, so it cannot access the collider
+    /// itself. Any changes to the collider in response to a collision must be
+    /// queued (e.g. in a shared `Arc<Mutex<_>>`) and applied after
+    /// `self.next()` returns. The closure must be `Send` so that the collider
+    /// can still be moved across threads.
+    pub fn on_collide(&mut self, id: HbId, f: Box<dyn FnMut(P, P) + Send>) {
+        assert!(
+            self.hitboxes.contains_key(&id) || self.compounds.contains_key(&id),
+            "hitbox id {} not found",
+            id
+        );
+        self.collide_callbacks.insert(id, f);
+    }
+
+    fn invoke_collide_callbacks(&mut self, profile_1: P, profile_2: P) {
+        if let Some(callback) = self.collide_callbacks.get_mut(&profile_1.id()) {
+            callback(profile_1, profile_2);
+        }
+        if let Some(callback) = self.collide_callbacks.get_mut(&profile_2.id()) {
+            callback(profile_2, profile_1);
         }
-        None
     }
 
     fn process_event(&mut self, event: InternalEvent) -> Option<(HbEvent, HbId, HbId)> {
@@ -136,10 +702,12 @@ impl<P: HbProfile> Collider<P> {
                         hitbox_info_2,
                         &mut self.events,
                         self.time,
-                        self.padding,
+                        &self.separation,
                     );
                 }
                 assert!(self.hitboxes.insert(id_1, hitbox_info_1).is_none());
+                self.overlap_start_times
+                    .insert(ordered_pair(id_1, id_2), self.time);
                 Some(new_event(HbEvent::Collide, id_1, id_2))
             }
             InternalEvent::Separate(id_1, id_2) => {
@@ -148,15 +716,21 @@ impl<P: HbProfile> Collider<P> {
                     let hitbox_info_2 = self.hitboxes.get_mut(&id_2).unwrap();
                     assert!(hitbox_info_1.overlaps.remove(&id_2));
                     assert!(hitbox_info_2.overlaps.remove(&id_1));
-                    let delay = hitbox_info_1
-                        .hitbox_at_time(self.time)
-                        .collide_time(&hitbox_info_2.hitbox_at_time(self.time));
-                    self.events.add_pair_event(
-                        self.time + delay,
-                        InternalEvent::Collide(id_1, id_2),
-                        &mut hitbox_info_1.event_keys,
-                        &mut hitbox_info_2.event_keys,
-                    );
+                    self.overlap_start_times.remove(&ordered_pair(id_1, id_2));
+                    if !is_discrete_pair(&hitbox_info_1.profile, &hitbox_info_2.profile) {
+                        let delay = one_way_collide_time(
+                            &hitbox_info_1.profile,
+                            &hitbox_info_1.hitbox_at_time(self.time),
+                            &hitbox_info_2.profile,
+                            &hitbox_info_2.hitbox_at_time(self.time),
+                        );
+                        self.events.add_pair_event(
+                            self.time + delay,
+                            InternalEvent::Collide(id_1, id_2),
+                            &mut hitbox_info_1.event_keys,
+                            &mut hitbox_info_2.event_keys,
+                        );
+                    }
                 }
                 assert!(self.hitboxes.insert(id_1, hitbox_info_1).is_none());
                 Some(new_event(HbEvent::Separate, id_1, id_2))
@@ -165,29 +739,60 @@ impl<P: HbProfile> Collider<P> {
                 self.internal_update_hitbox(id, None);
                 None
             }
-            #[cfg(debug_assertions)]
-            InternalEvent::PanicSmallHitbox(id) => panic!("hitbox {} became too small", id),
-            #[cfg(debug_assertions)]
+            InternalEvent::Expire(id) => {
+                // all parts of a compound expire together
+                for part_id in self.compound_parts(self.parent_id(id)) {
+                    self.internal_update_hitbox(part_id, Some(HbVel::still()));
+                }
+                Some((HbEvent::Expire, id, id))
+            }
+            InternalEvent::ExitBounds(id) => {
+                self.hitboxes.get_mut(&id).unwrap().out_of_bounds = true;
+                self.internal_update_hitbox(id, None);
+                Some((HbEvent::ExitBounds, id, id))
+            }
+            InternalEvent::PanicSmallHitbox(id) => {
+                if !self.invalidation_events {
+                    panic!("hitbox {} became too small", id);
+                }
+                Some(self.invalidate(id, InvalidationReason::TooSmall))
+            }
             InternalEvent::PanicDurationPassed(id) => {
-                panic!("hitbox {} was not updated before duration passed", id)
+                if !self.invalidation_events {
+                    panic!("hitbox {} was not updated before duration passed", id);
+                }
+                Some(self.invalidate(id, InvalidationReason::DurationPassed))
             }
         }
     }
 
+    // stops all parts of the hitbox so that it cannot be invalidated again
+    fn invalidate(&mut self, id: HbId, reason: InvalidationReason) -> (HbEvent, HbId, HbId) {
+        for part_id in self.compound_parts(self.parent_id(id)) {
+            self.internal_update_hitbox(part_id, Some(HbVel::still()));
+        }
+        (HbEvent::Invalidated(reason), id, id)
+    }
+
     fn process_collision(
         id_1: HbId,
         hb_1: &mut HitboxInfo<P>,
         id_2: HbId,
         hb_2: &mut HitboxInfo<P>,
         events: &mut EventManager,
-        time: f64,
-        padding: f64,
+        time: Float,
+        separation: &Separation,
     ) {
         assert!(hb_1.overlaps.insert(id_2));
         assert!(hb_2.overlaps.insert(id_1));
-        let delay = hb_1
-            .hitbox_at_time(time)
-            .separate_time(&hb_2.hitbox_at_time(time), padding);
+        if is_discrete_pair(&hb_1.profile, &hb_2.profile) {
+            return;
+        }
+        let delay = separation.time(
+            &hb_1.hitbox_at_time(time),
+            &hb_2.hitbox_at_time(time),
+            (hb_1.profile.group(), hb_2.profile.group()),
+        );
         events.add_pair_event(
             time + delay,
             InternalEvent::Separate(id_1, id_2),
@@ -196,125 +801,1627 @@ impl<P: HbProfile> Collider<P> {
         );
     }
 
-    /// Returns the current state of the hitbox with the given `id`.
-    pub fn get_hitbox(&self, id: HbId) -> Hitbox {
-        self.hitboxes[&id].pub_hitbox_at_time(self.time)
+    /// Returns all events that are currently scheduled, along with the
+    /// absolute simulation time of each event, in the order they will be
+    /// processed.
+    ///
+    /// This is a read-only view intended for inspection and testing. Scheduled
+    /// events may be cancelled or rescheduled whenever a hitbox is updated.
+    pub fn scheduled_events(&self) -> Vec<(Float, EventDescription)> {
+        self.events
+            .iter()
+            .map(|(time, event)| {
+                (
+                    time + self.frozen_elapsed(),
+                    EventDescription::from_internal(event),
+                )
+            })
+            .collect()
     }
 
-    /// # Adds a new hitbox to the collider.
+    /// Returns the number of events that are currently scheduled, including
+    /// internal events.
     ///
-    /// The `profile` is used to track the hitbox over time; Collider will
-    /// return this profile in certain methods, and the ID in this profile can
-    /// be used to make updates to the hitbox. This method will panic if there
-    /// is an ID clash. `hitbox` is the initial state of the hitbox.
+    /// This is a fast constant-time operation intended for profiling.
+    pub fn pending_event_count(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns the number of scheduled events, including internal events, that
+    /// involve the hitbox with the given `id`.
     ///
-    /// Returns a vector of all hitbox profiles that this new hitbox collided
-    /// with as it was added. Note that separate collision events will not be
-    /// generated for these collisions.
-    pub fn add_hitbox(&mut self, profile: P, hitbox: Hitbox) -> Vec<P> {
-        hitbox.validate(self.padding, self.time);
-        let id = profile.id();
-        let has_group = profile.group().is_some();
-        let mut info = HitboxInfo::new(hitbox, profile, self.time);
-        self.solitaire_event_check(id, &mut info, has_group);
-        let dur_hitbox = info.hitbox.to_dur_hitbox(self.time);
-        self.update_hitbox_tracking(id, info, None, dur_hitbox)
+    /// This is a fast constant-time operation intended for profiling, e.g. to
+    /// find hitboxes that are rescheduled excessively.
+    pub fn hitbox_event_count(&self, id: HbId) -> usize {
+        self.hitboxes
+            .get(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id))
+            .event_keys
+            .len()
     }
 
-    /// Updates the velocity information of the hitbox with the given `id`.
-    pub fn set_hitbox_vel(&mut self, id: HbId, vel: HbVel) {
-        if self.hitboxes[&id].hitbox.vel != vel {
-            self.internal_update_hitbox(id, Some(vel));
+    /// Counts the scheduled `Collide` and `Separate` events by time, relative
+    /// to the current time.
+    ///
+    /// Bucket `i` of the result counts events occurring in the interval
+    /// `[self.time() + i * bucket_size, self.time() + (i + 1) * bucket_size)`.
+    /// Events at or after `self.time() + horizon` are not counted. This is a
+    /// read-only scan of the event queue intended for performance analysis,
+    /// e.g. to find bursts of simultaneous collisions. Internal events are
+    /// not counted, and scheduled events may change whenever a hitbox is
+    /// updated.
+    pub fn event_time_histogram(&self, bucket_size: Float, horizon: Float) -> Vec<usize> {
+        assert!(bucket_size > 0.0, "bucket_size must be positive");
+        assert!(
+            (0.0..HIGH_TIME).contains(&horizon),
+            "horizon is out of range"
+        );
+        let mut result = vec![0; (horizon / bucket_size).ceil() as usize];
+        for (time, event) in self.events.iter() {
+            let delay = time - self.time;
+            if delay >= horizon {
+                continue;
+            }
+            match event {
+                InternalEvent::Collide(..) | InternalEvent::Separate(..) => {
+                    let bucket = ((delay / bucket_size) as usize).min(result.len() - 1);
+                    result[bucket] += 1;
+                }
+                _ => (),
+            }
         }
+        result
     }
 
-    fn internal_update_hitbox(&mut self, id: HbId, vel: Option<HbVel>) {
-        let mut info = self
-            .hitboxes
-            .remove(&id)
-            .unwrap_or_else(|| panic!("hitbox id {} not found", id));
-        let old_hitbox = info.hitbox.to_dur_hitbox(info.start_time);
-        info.hitbox = info.pub_hitbox_at_time(self.time);
-        if let Some(vel) = vel {
-            info.hitbox.vel = vel;
-            info.hitbox.validate(self.padding, self.time);
-        }
-        info.start_time = self.time;
-        let has_group = info.profile.group().is_some();
-        self.events
-            .clear_related_events(id, &mut info.event_keys, &mut self.hitboxes);
-        self.solitaire_event_check(id, &mut info, has_group);
-        let new_hitbox = info.hitbox.to_dur_hitbox(self.time);
-        let result = self.update_hitbox_tracking(id, info, Some(old_hitbox), new_hitbox);
-        assert!(result.is_empty());
+    /// Returns true if a hitbox or compound hitbox (see `add_compound`) with the
+    /// given `id` is currently in the collider.
+    pub fn contains(&self, id: HbId) -> bool {
+        self.hitboxes.contains_key(&id) || self.compounds.contains_key(&id)
     }
 
-    /// Removes the hitbox with the given `id` from all tracking.
+    /// Returns the current state of the hitbox with the given `id`.
+    pub fn get_hitbox(&self, id: HbId) -> Hitbox {
+        self.pub_hitbox(&self.hitboxes[&id], self.time)
+    }
+
+    /// Returns the current state of the hitbox with the given `id` like
+    /// `get_hitbox`, but returns `None` instead of panicking if there is no
+    /// such hitbox.
+    pub fn try_get_hitbox(&self, id: HbId) -> Option<Hitbox> {
+        self.hitboxes
+            .get(&id)
+            .map(|info| self.pub_hitbox(info, self.time))
+    }
+
+    // returns the hitbox at the given internal time, with the `end_time` of its
+    // velocity shifted by the time spent frozen
+    fn pub_hitbox(&self, info: &HitboxInfo<P>, time: Float) -> Hitbox {
+        let mut result = info.pub_hitbox_at_time(time);
+        result.vel.end_time += self.frozen_elapsed();
+        result
+    }
+
+    // shifts the `end_time` of a velocity given by the user while frozen back
+    // by the time spent frozen
+    fn internal_vel(&self, mut vel: HbVel) -> HbVel {
+        vel.end_time -= self.frozen_elapsed();
+        vel
+    }
+
+    /// Returns the state that the hitbox with the given `id` will have at
+    /// `future_time` if it keeps moving along its current velocity.
     ///
-    /// Returns a vector of all hitbox profiles that this hitbox separated from
-    /// as it was removed. No further events will be generated for this hitbox.
-    pub fn remove_hitbox(&mut self, id: HbId) -> Vec<P> {
-        let mut info = self
+    /// This does not modify the collider, and is useful for interpolating or
+    /// predicting hitbox positions. `future_time` must be no less than the
+    /// current time and no greater than the `end_time` of the hitbox velocity.
+    pub fn hitbox_at(&self, id: HbId, future_time: Float) -> Hitbox {
+        assert!(future_time >= self.time(), "requires future_time >= time");
+        let info = self
             .hitboxes
-            .remove(&id)
+            .get(&id)
             .unwrap_or_else(|| panic!("hitbox id {} not found", id));
-        self.events
-            .clear_related_events(id, &mut info.event_keys, &mut self.hitboxes);
-        if let Some(group) = info.profile.group() {
-            let info_start_time = info.start_time;
-            let empty_group_array: &[HbGroup] = &[];
-            self.grid.update_hitbox(
-                id,
-                group,
-                Some(&info.hitbox.to_dur_hitbox(info_start_time)),
-                None,
-                empty_group_array,
-            );
-        }
-        self.clear_overlaps(id, &mut info)
+        self.pub_hitbox(info, future_time - self.frozen_elapsed())
     }
 
-    /// Returns the profiles of all currently tracked overlaps on the hitbox
-    /// with the given `id`.
-    pub fn get_overlaps(&self, id: HbId) -> Vec<P> {
+    /// Returns the instantaneous movement velocity of the hitbox with the given
+    /// `id` at `future_time`, accounting for its acceleration.
+    ///
+    /// This is useful for passing velocities to a dynamics step when an event
+    /// occurs. The same restrictions on `future_time` as `hitbox_at` apply.
+    pub fn hitbox_velocity_at(&self, id: HbId, future_time: Float) -> Vec2 {
+        self.hitbox_at(id, future_time).vel.value
+    }
+
+    /// Returns the time remaining until the `end_time` of the velocity of the
+    /// hitbox with the given `id`, or `Float::INFINITY` if it has no end time.
+    ///
+    /// The velocity must be updated before this reaches zero (see
+    /// `HbVel::end_time`).
+    pub fn hitbox_time_remaining(&self, id: HbId) -> Float {
         let info = self
             .hitboxes
             .get(&id)
             .unwrap_or_else(|| panic!("hitbox id {} not found", id));
-        info.overlaps
-            .iter()
-            .map(|other_id| self.hitboxes[other_id].profile)
-            .collect()
+        info.pub_end_time - self.time
     }
 
-    /// Returns the profile of the hitbox with the given `id`.
-    pub fn get_profile(&self, id: HbId) -> P {
-        self.hitboxes
-            .get(&id)
-            .unwrap_or_else(|| panic!("hitbox id {} not found", id))
-            .profile
+    /// Returns an iterator over the IDs and profiles of all hitboxes in the
+    /// collider, in no particular order.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (HbId, P)> + 'a {
+        self.hitboxes.iter().map(|(&id, info)| (id, info.profile))
     }
 
-    /// Returns true if there is a currently tracked overlap between the
-    /// hitboxes with `id_1` and `id_2`.
-    pub fn is_overlapping(&self, id_1: HbId, id_2: HbId) -> bool {
-        self.hitboxes
-            .get(&id_1)
+    /// Returns an iterator over the IDs of all hitboxes in the collider, in no
+    /// particular order.
+    pub fn ids<'a>(&'a self) -> impl Iterator<Item = HbId> + 'a {
+        self.hitboxes.keys().cloned()
+    }
+
+    /// Returns the number of hitboxes in the collider.
+    pub fn len(&self) -> usize {
+        self.hitboxes.len()
+    }
+
+    /// Returns `true` if the collider contains no hitboxes.
+    pub fn is_empty(&self) -> bool {
+        self.hitboxes.is_empty()
+    }
+
+    /// # Adds a new hitbox to the collider.
+    ///
+    /// The `profile` is used to track the hitbox over time; Collider will
+    /// return this profile in certain methods, and the ID in this profile can
+    /// be used to make updates to the hitbox. This method will panic if there
+    /// is an ID clash. `hitbox` is the initial state of the hitbox.
+    ///
+    /// Returns a vector of all hitbox profiles that this new hitbox collided
+    /// with as it was added. Note that separate collision events will not be
+    /// generated for these collisions.
+    pub fn add_hitbox(&mut self, profile: P, hitbox: Hitbox) -> Vec<P> {
+        let id = profile.id();
+        self.try_add_hitbox(profile, hitbox)
+            .unwrap_or_else(|err| panic!("hitbox id {}: {}", id, err))
+    }
+
+    /// Adds a new hitbox to the collider like `add_hitbox`, but returns an
+    /// error instead of panicking if the hitbox is invalid or its ID is
+    /// already in use. The collider is unchanged if an error is returned.
+    pub fn try_add_hitbox(
+        &mut self,
+        profile: P,
+        mut hitbox: Hitbox,
+    ) -> Result<Vec<P>, ColliderError> {
+        hitbox.vel = self.internal_vel(hitbox.vel);
+        hitbox.try_validate(self.padding, self.time)?;
+        let id = profile.id();
+        if self.hitboxes.contains_key(&id) || self.compounds.contains_key(&id) {
+            return Err(ColliderError::IdInUse);
+        }
+        let mut info = HitboxInfo::new(hitbox, profile, self.time);
+        self.solitaire_event_check(id, &mut info, profile.group());
+        let dur_hitbox = info.hitbox.to_dur_hitbox(self.time);
+        Ok(self.update_hitbox_tracking(id, info, None, dur_hitbox))
+    }
+
+    /// Adds many hitboxes to the collider at once.
+    ///
+    /// This produces the same results as calling `add_hitbox` for each hitbox
+    /// in order, but all hitboxes are inserted before a single pass looks for
+    /// overlaps, which is faster when loading a large number of hitboxes. All
+    /// hitboxes are validated before any are added, so this method panics
+    /// without modifying the collider if any hitbox is invalid or any ID is
+    /// already in use.
+    ///
+    /// Returns the pairs of hitbox profiles that were colliding as the hitboxes
+    /// were added, with each pair in increasing order by `HbId`. Note that
+    /// separate collision events will not be generated for these collisions.
+    pub fn add_hitboxes<I>(&mut self, hitboxes: I) -> Vec<(P, P)>
+    where
+        I: IntoIterator<Item = (P, Hitbox)>,
+    {
+        let hitboxes: Vec<(P, Hitbox)> = hitboxes
+            .into_iter()
+            .map(|(profile, mut hitbox)| {
+                hitbox.vel = self.internal_vel(hitbox.vel);
+                (profile, hitbox)
+            })
+            .collect();
+        let mut order = FnvHashMap::default();
+        for (index, &(profile, ref hitbox)) in hitboxes.iter().enumerate() {
+            let id = profile.id();
+            let mut result = hitbox.try_validate(self.padding, self.time);
+            if self.hitboxes.contains_key(&id)
+                || self.compounds.contains_key(&id)
+                || order.insert(id, index).is_some()
+            {
+                result = Err(ColliderError::IdInUse);
+            }
+            if let Err(err) = result {
+                panic!("hitbox id {}: {}", id, err);
+            }
+        }
+
+        self.reserve(hitboxes.len());
+        let mut added = Vec::with_capacity(hitboxes.len());
+        for (profile, hitbox) in hitboxes {
+            let id = profile.id();
+            let mut info = HitboxInfo::new(hitbox, profile, self.time);
+            self.solitaire_event_check(id, &mut info, profile.group());
+            let dur_hitbox = info.hitbox.to_dur_hitbox(self.time);
+            if let Some(group) = profile.group() {
+                self.grid
+                    .update_hitbox(id, group, None, Some(&dur_hitbox), GroupSet::empty());
+            }
+            self.hitboxes.insert(id, info);
+            added.push((id, dur_hitbox));
+        }
+
+        // a single pass over the occupied cells finds the candidate pairs for
+        // all of the new hitboxes, each of which is tested from the later one
+        added.retain(|&(id, _)| self.hitboxes[&id].profile.group().is_some());
+        let batch: Vec<(HbId, HbGroup, GroupSet, Aabb)> = added
+            .iter()
+            .map(|&(id, ref dur_hitbox)| {
+                let profile = &self.hitboxes[&id].profile;
+                let group = profile.group().unwrap();
+                (id, group, GroupSet::of(profile), dur_hitbox.bounding_box())
+            })
+            .collect();
+        let mut test_ids = vec![Vec::new(); batch.len()];
+        self.grid.batch_cellmates(
+            &batch,
+            |id| self.hitboxes[&id].grid_bounds(),
+            |index, other_id| {
+                let id = batch[index].0;
+                if order
+                    .get(&other_id)
+                    .map_or(true, |other| other < &order[&id])
+                {
+                    test_ids[index].push(other_id);
+                }
+            },
+        );
+
+        let mut result = Vec::new();
+        for ((id, dur_hitbox), test_ids) in added.into_iter().zip(test_ids) {
+            let mut info = self.hitboxes.remove(&id).unwrap();
+            let profile = info.profile;
+            for other in self.check_pairs(id, &mut info, true, &dur_hitbox, test_ids) {
+                if id < other.id() {
+                    result.push((profile, other));
+                } else {
+                    result.push((other, profile));
+                }
+            }
+            self.hitboxes.insert(id, info);
+        }
+        result
+    }
+
+    /// Adds stationary rectangular hitboxes covering the solid tiles of a tile
+    /// map.
+    ///
+    /// `occupancy[y][x]` denotes whether the tile in column `x` and row `y` is
+    /// solid, where rows may have differing lengths. Each tile is a square of
+    /// width `tile_size`, and the tile in column `x` and row `y` has its
+    /// lower-left corner at `origin + (x * tile_size, y * tile_size)`.
+    /// Contiguous solid tiles are greedily merged into larger rectangles so
+    /// that far fewer hitboxes are needed than one per tile. `profile_fn` is
+    /// invoked with the column and row of the lower-left tile of each
+    /// rectangle to obtain its profile.
+    ///
+    /// Returns the profiles of the hitboxes that were added.
+    pub fn add_tilemap<F>(
+        &mut self,
+        occupancy: &[&[bool]],
+        tile_size: Float,
+        origin: Vec2,
+        profile_fn: F,
+    ) -> Vec<P>
+    where
+        F: Fn(usize, usize) -> P,
+    {
+        let solid = |x: usize, y: usize| occupancy[y].get(x).cloned().unwrap_or(false);
+        let mut covered: Vec<Vec<bool>> =
+            occupancy.iter().map(|row| vec![false; row.len()]).collect();
+        let mut result = Vec::new();
+        for y in 0..occupancy.len() {
+            for x in 0..occupancy[y].len() {
+                if !solid(x, y) || covered[y][x] {
+                    continue;
+                }
+                let mut width = 1;
+                while solid(x + width, y) && !covered[y][x + width] {
+                    width += 1;
+                }
+                let mut height = 1;
+                while y + height < occupancy.len()
+                    && (x..x + width).all(|x| solid(x, y + height) && !covered[y + height][x])
+                {
+                    height += 1;
+                }
+                for row in &mut covered[y..y + height] {
+                    for tile in &mut row[x..x + width] {
+                        *tile = true;
+                    }
+                }
+                let dims = v2(width as Float, height as Float) * tile_size;
+                let pos = origin + v2(x as Float, y as Float) * tile_size + dims * 0.5;
+                let profile = profile_fn(x, y);
+                self.add_hitbox(profile, Shape::rect(dims).place(pos).still());
+                result.push(profile);
+            }
+        }
+        result
+    }
+
+    /// Updates the velocity information of the hitbox with the given `id`.
+    ///
+    /// A hitbox that has shrunk below `padding` (see
+    /// `set_invalidation_events`) may be given a velocity that does not shrink
+    /// it, but panics for any velocity that does.
+    pub fn set_hitbox_vel(&mut self, id: HbId, vel: HbVel) {
+        self.try_set_hitbox_vel(id, vel)
+            .unwrap_or_else(|err| panic!("hitbox id {}: {}", id, err));
+    }
+
+    /// Updates the velocity information of the hitbox with the given `id` like
+    /// `set_hitbox_vel`, but returns an error instead of panicking if the
+    /// hitbox is not found or the velocity is invalid. The collider is
+    /// unchanged if an error is returned.
+    pub fn try_set_hitbox_vel(&mut self, id: HbId, vel: HbVel) -> Result<(), ColliderError> {
+        let vel = self.internal_vel(vel);
+        let info = self.hitboxes.get(&id).ok_or(ColliderError::IdNotFound)?;
+        if info.hitbox.vel != vel {
+            let mut hitbox = info.pub_hitbox_at_time(self.time);
+            hitbox.vel = vel.clone();
+            // a hitbox that is already smaller than the padding (after being
+            // invalidated) may be given a velocity that stops it from shrinking
+            let dims = hitbox.value.dims();
+            let min_size = if vel.resize.x >= 0.0 && vel.resize.y >= 0.0 {
+                self.padding.min(dims.x.abs()).min(dims.y.abs())
+            } else {
+                self.padding
+            };
+            hitbox.try_validate(min_size, self.time)?;
+            self.internal_update_hitbox(id, Some(vel));
+        }
+        Ok(())
+    }
+
+    fn internal_update_hitbox(&mut self, id: HbId, vel: Option<HbVel>) {
+        let mut info = self
+            .hitboxes
+            .remove(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id));
+        let old_hitbox = info.hitbox.to_dur_hitbox(info.start_time);
+        info.hitbox = info.pub_hitbox_at_time(self.time);
+        if let Some(vel) = vel {
+            info.hitbox.vel = vel;
+        }
+        info.start_time = self.time;
+        let group = info.profile.group();
+        self.events
+            .clear_related_events(id, &mut info.event_keys, &mut self.hitboxes);
+        self.solitaire_event_check(id, &mut info, group);
+        let new_hitbox = info.hitbox.to_dur_hitbox(self.time);
+        let result = self.update_hitbox_tracking(id, info, Some(old_hitbox), new_hitbox);
+        assert!(result.is_empty());
+    }
+
+    /// Shifts the positions of all hitboxes by `delta`.
+    ///
+    /// Since all hitboxes are moved uniformly, their relative geometry is
+    /// unchanged, so all overlaps, velocities, and scheduled events are
+    /// preserved (subject to negligible rounding of the new positions). This
+    /// supports the "floating origin" technique, where the origin is
+    /// periodically re-centered to keep coordinates small and precise. The
+    /// bounds set by `set_bounds` are shifted as well.
+    pub fn translate_world(&mut self, delta: Vec2) {
+        if let Some(ref mut bounds) = self.bounds {
+            bounds.pos += delta;
+        }
+        for (&id, info) in &mut self.hitboxes {
+            let old_hitbox = info.hitbox.to_dur_hitbox(info.start_time);
+            info.hitbox.value.pos += delta;
+            if let Some(group) = info.profile.group() {
+                let new_hitbox = info.hitbox.to_dur_hitbox(info.start_time);
+                self.grid.update_hitbox(
+                    id,
+                    group,
+                    Some(&old_hitbox),
+                    Some(&new_hitbox),
+                    GroupSet::empty(),
+                );
+            }
+        }
+    }
+
+    /// Enables or disables collision tracking for the hitbox with the given
+    /// `id`, without removing it from the collider.
+    ///
+    /// A disabled hitbox keeps moving with its velocity and remains in the
+    /// grid, but no `Collide` or `Separate` events are generated for it, and it
+    /// is not included in `get_overlaps`. When disabled, the hitbox silently
+    /// separates from all overlapping hitboxes, and the profiles of those
+    /// hitboxes are returned. When enabled, the hitbox is checked for overlaps
+    /// as if it were newly added, and the profiles of the hitboxes that it
+    /// overlaps are returned (see `add_hitbox`). Does nothing if the hitbox is
+    /// already in the requested state.
+    pub fn set_hitbox_enabled(&mut self, id: HbId, enabled: bool) -> Vec<P> {
+        let mut info = self
+            .hitboxes
+            .remove(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id));
+        if info.enabled == enabled {
+            assert!(self.hitboxes.insert(id, info).is_none());
+            return Vec::new();
+        }
+        let old_hitbox = info.hitbox.to_dur_hitbox(info.start_time);
+        info.hitbox = info.pub_hitbox_at_time(self.time);
+        info.start_time = self.time;
+        info.enabled = enabled;
+        let group = info.profile.group();
+        self.events
+            .clear_related_events(id, &mut info.event_keys, &mut self.hitboxes);
+        let separated = self.clear_overlaps(id, &mut info);
+        self.solitaire_event_check(id, &mut info, group);
+        let new_hitbox = info.hitbox.to_dur_hitbox(self.time);
+        if enabled {
+            // re-inserted into the grid so that overlaps are checked as if the
+            // hitbox were newly added
+            if let Some(group) = group {
+                self.grid
+                    .update_hitbox(id, group, Some(&old_hitbox), None, GroupSet::empty());
+            }
+            self.update_hitbox_tracking(id, info, None, new_hitbox)
+        } else {
+            let result = self.update_hitbox_tracking(id, info, Some(old_hitbox), new_hitbox);
+            assert!(result.is_empty());
+            separated
+        }
+    }
+
+    /// Replaces the profile of the hitbox with the given `id`, keeping its
+    /// shape, velocity, and overlaps.
+    ///
+    /// The new profile must have the same `id`. If the group changes, then the
+    /// hitbox is moved to the new group. Overlaps with hitboxes that no longer
+    /// interact with the new profile are silently dropped, and the profiles of
+    /// those hitboxes are returned. Hitboxes that can newly interact with it and
+    /// are already overlapping it will generate `Collide` events at the current
+    /// time.
+    pub fn set_hitbox_profile(&mut self, id: HbId, profile: P) -> Vec<P> {
+        assert_eq!(profile.id(), id, "profile id must match hitbox id");
+        let mut info = self
+            .hitboxes
+            .remove(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id));
+        let old_hitbox = info.hitbox.to_dur_hitbox(info.start_time);
+        info.hitbox = info.pub_hitbox_at_time(self.time);
+        info.start_time = self.time;
+        let old_group = info.profile.group();
+        let group = profile.group();
+        info.profile = profile;
+        self.events
+            .clear_related_events(id, &mut info.event_keys, &mut self.hitboxes);
+        let dropped: Vec<HbId> = info
+            .overlaps
+            .iter()
+            .cloned()
+            .filter(|other_id| {
+                let other_profile = &self.hitboxes[other_id].profile;
+                group.is_none()
+                    || other_profile.group().is_none()
+                    || is_same_team(&profile, other_profile)
+                    || !profile.can_interact(other_profile)
+            })
+            .collect();
+        let mut separated = Vec::new();
+        for other_id in dropped {
+            let other_info = self.hitboxes.get_mut(&other_id).unwrap();
+            assert!(info.overlaps.remove(&other_id));
+            assert!(other_info.overlaps.remove(&id));
+            self.overlap_start_times.remove(&ordered_pair(id, other_id));
+            separated.push(other_info.profile);
+        }
+        if old_group != group {
+            // moved between groups in the grid before the hitbox is updated
+            if let Some(old_group) = old_group {
+                self.grid
+                    .update_hitbox(id, old_group, Some(&old_hitbox), None, GroupSet::empty());
+            }
+            if let Some(group) = group {
+                self.grid
+                    .update_hitbox(id, group, None, Some(&old_hitbox), GroupSet::empty());
+            }
+        }
+        self.solitaire_event_check(id, &mut info, group);
+        let new_hitbox = info.hitbox.to_dur_hitbox(self.time);
+        let result = self.update_hitbox_tracking(id, info, Some(old_hitbox), new_hitbox);
+        assert!(result.is_empty());
+        separated
+    }
+
+    /// Removes all hitboxes whose profiles belong to the given `group` from all
+    /// tracking.
+    ///
+    /// Returns the profiles of the removed hitboxes, sorted by id. Compound
+    /// hitboxes in the group are removed as a whole and their profiles are
+    /// returned once. No further events will be generated for these hitboxes.
+    /// This is more efficient than calling `remove_hitbox` for each hitbox in
+    /// the group.
+    pub fn remove_group(&mut self, group: HbGroup) -> Vec<P> {
+        let ids: FnvHashSet<HbId> = self
+            .hitboxes
+            .iter()
+            .filter(|&(_, info)| info.profile.group() == Some(group))
+            .map(|(&id, _)| id)
+            .collect();
+        let mut removed: Vec<(HbId, HitboxInfo<P>)> = ids
+            .iter()
+            .map(|&id| (id, self.hitboxes.remove(&id).unwrap()))
+            .collect();
+        removed.sort_by_key(|&(id, _)| id);
+        for &mut (id, ref mut info) in &mut removed {
+            self.events
+                .clear_removed_events(&mut info.event_keys, &ids, &mut self.hitboxes);
+            self.grid.update_hitbox(
+                id,
+                group,
+                Some(&info.hitbox.to_dur_hitbox(info.start_time)),
+                None,
+                GroupSet::empty(),
+            );
+            for other_id in info.overlaps.drain() {
+                self.overlap_start_times.remove(&ordered_pair(id, other_id));
+                if let Some(other_info) = self.hitboxes.get_mut(&other_id) {
+                    assert!(other_info.overlaps.remove(&id));
+                }
+            }
+        }
+        let mut result: Vec<P> = removed.into_iter().map(|(_, info)| info.profile).collect();
+        result.sort_by_key(|profile| profile.id());
+        result.dedup_by_key(|profile| profile.id());
+        for profile in &result {
+            self.compounds.remove(&profile.id());
+            self.collide_callbacks.remove(&profile.id());
+        }
+        result
+    }
+
+    /// Removes the hitbox with the given `id` from all tracking.
+    ///
+    /// Returns a vector of all hitbox profiles that this hitbox separated from
+    /// as it was removed, sorted by id. No further events will be generated
+    /// for this hitbox.
+    pub fn remove_hitbox(&mut self, id: HbId) -> Vec<P> {
+        let mut info = self
+            .hitboxes
+            .remove(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id));
+        self.events
+            .clear_related_events(id, &mut info.event_keys, &mut self.hitboxes);
+        self.collide_callbacks.remove(&id);
+        if let Some(group) = info.profile.group() {
+            let info_start_time = info.start_time;
+            self.grid.update_hitbox(
+                id,
+                group,
+                Some(&info.hitbox.to_dur_hitbox(info_start_time)),
+                None,
+                GroupSet::empty(),
+            );
+        }
+        self.clear_overlaps(id, &mut info)
+    }
+
+    /// Adds a compound hitbox, made up of several shapes that move together as
+    /// a unit under a single `HbId`.
+    ///
+    /// Each part is placed at its offset from the origin (so the position of
+    /// the entity should be added to each offset) and moves with `vel`. The
+    /// parts of a compound never interact with each other. A `Collide` event
+    /// between the compound and another hitbox is returned once, when the
+    /// first part starts overlapping it, and a `Separate` event is returned
+    /// once all parts have separated from it. `Expire` events are likewise
+    /// returned once for the compound, and an `ExitBounds` event is returned
+    /// when the first part leaves the bounds, while no other part is out of
+    /// them.
+    ///
+    /// The compound is updated and removed with `set_compound_vel` and
+    /// `remove_compound`. Other methods that take an `HbId` do not accept the
+    /// id of a compound unless stated otherwise. Parts are internally assigned
+    /// ids of `1 << 63` and above, so those ids must not be used by other
+    /// hitboxes. Panics if `parts` is empty, if `vel` has an angular velocity,
+    /// or under the same conditions as `add_hitbox`.
+    ///
+    /// Returns the profiles of all hitboxes that the compound collided with as
+    /// it was added, sorted by id.
+    pub fn add_compound(&mut self, profile: P, parts: Vec<(Vec2, Shape)>, vel: HbVel) -> Vec<P> {
+        let id = profile.id();
+        assert!(!parts.is_empty(), "compound hitbox must have a part");
+        assert!(vel.angular == 0.0, "compound hitbox must not rotate");
+        assert!(
+            id < COMPOUND_PART_ID_START,
+            "hitbox id {} is reserved for compound parts",
+            id
+        );
+        if self.hitboxes.contains_key(&id) || self.compounds.contains_key(&id) {
+            panic!("hitbox id {}: {}", id, ColliderError::IdInUse);
+        }
+        let vel = self.internal_vel(vel);
+        let hitboxes: Vec<Hitbox> = parts
+            .into_iter()
+            .map(|(offset, shape)| Hitbox::new(shape.place(offset), vel.clone()))
+            .collect();
+        for hitbox in &hitboxes {
+            if let Err(err) = hitbox.try_validate(self.padding, self.time) {
+                panic!("hitbox id {}: {}", id, err);
+            }
+        }
+
+        let mut part_ids = Vec::with_capacity(hitboxes.len());
+        let mut result = Vec::new();
+        for hitbox in hitboxes {
+            let part_id = self.next_part_id;
+            self.next_part_id += 1;
+            let mut info = HitboxInfo::new(hitbox, profile, self.time);
+            self.solitaire_event_check(part_id, &mut info, profile.group());
+            let dur_hitbox = info.hitbox.to_dur_hitbox(self.time);
+            result.extend(self.update_hitbox_tracking(part_id, info, None, dur_hitbox));
+            part_ids.push(part_id);
+        }
+        self.compounds.insert(id, part_ids);
+        result.sort_by_key(|profile| profile.id());
+        result.dedup_by_key(|profile| profile.id());
+        result
+    }
+
+    /// Updates the velocity of all parts of the compound hitbox with the given
+    /// `id` (see `add_compound`).
+    ///
+    /// Panics if `vel` has an angular velocity, or under the same conditions as
+    /// `set_hitbox_vel`.
+    pub fn set_compound_vel(&mut self, id: HbId, vel: HbVel) {
+        assert!(vel.angular == 0.0, "compound hitbox must not rotate");
+        for part_id in self.compound_part_ids(id) {
+            self.set_hitbox_vel(part_id, vel.clone());
+        }
+    }
+
+    /// Returns the current state of each part of the compound hitbox with the
+    /// given `id`, in the order that the parts were given to `add_compound`.
+    pub fn get_compound(&self, id: HbId) -> Vec<Hitbox> {
+        self.compound_part_ids(id)
+            .into_iter()
+            .map(|part_id| self.get_hitbox(part_id))
+            .collect()
+    }
+
+    /// Removes the compound hitbox with the given `id` from all tracking.
+    ///
+    /// Returns the profiles of all hitboxes that the compound separated from as
+    /// it was removed, sorted by id. No further events will be generated for
+    /// the compound.
+    pub fn remove_compound(&mut self, id: HbId) -> Vec<P> {
+        let part_ids = self.compound_part_ids(id);
+        self.compounds.remove(&id);
+        self.collide_callbacks.remove(&id);
+        let mut result = Vec::new();
+        for part_id in part_ids {
+            result.extend(self.remove_hitbox(part_id));
+        }
+        result.sort_by_key(|profile| profile.id());
+        result.dedup_by_key(|profile| profile.id());
+        result
+    }
+
+    fn compound_part_ids(&self, id: HbId) -> Vec<HbId> {
+        self.compounds
+            .get(&id)
+            .unwrap_or_else(|| panic!("compound hitbox id {} not found", id))
+            .clone()
+    }
+
+    // returns the id of the hitbox or compound hitbox that owns the hitbox
+    // with the given id
+    fn parent_id(&self, id: HbId) -> HbId {
+        self.hitboxes[&id].profile.id()
+    }
+
+    // returns the ids of the hitboxes belonging to the given parent id, which
+    // is just the parent itself unless it is a compound
+    fn compound_parts(&self, parent_id: HbId) -> Vec<HbId> {
+        self.compounds
+            .get(&parent_id)
+            .cloned()
+            .unwrap_or_else(|| vec![parent_id])
+    }
+
+    // a compound collides with another hitbox when the first pair of parts
+    // starts overlapping, separates when the last pair stops overlapping, and
+    // leaves the bounds when its first part does (checked before the event is
+    // processed)
+    fn is_duplicate_compound_event(&self, event: HbEvent, id_1: HbId, id_2: HbId) -> bool {
+        let (parent_1, parent_2) = (self.parent_id(id_1), self.parent_id(id_2));
+        if parent_1 == id_1 && parent_2 == id_2 {
+            return false;
+        }
+        if event == HbEvent::ExitBounds {
+            return self
+                .compound_parts(parent_1)
+                .iter()
+                .any(|part_id| self.hitboxes[part_id].out_of_bounds);
+        }
+        let overlap_count: usize = self
+            .compound_parts(parent_1)
+            .iter()
+            .map(|part_id| {
+                self.hitboxes[part_id]
+                    .overlaps
+                    .iter()
+                    .filter(|&&other_id| self.parent_id(other_id) == parent_2)
+                    .count()
+            })
+            .sum();
+        match event {
+            HbEvent::Collide => overlap_count > 0,
+            HbEvent::Separate => overlap_count > 1,
+            _ => false,
+        }
+    }
+
+    /// Returns the profiles of all currently tracked overlaps on the hitbox
+    /// with the given `id`, sorted by id.
+    pub fn get_overlaps(&self, id: HbId) -> Vec<P> {
+        let info = self
+            .hitboxes
+            .get(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id));
+        let mut result: Vec<P> = info
+            .overlaps
+            .iter()
+            .map(|other_id| self.hitboxes[other_id].profile)
+            .collect();
+        result.sort_by_key(|profile| profile.id());
+        result.dedup_by_key(|profile| profile.id());
+        result
+    }
+
+    /// Calls `f` with the profile of each currently tracked overlap on the
+    /// hitbox with the given `id`, in no particular order.
+    ///
+    /// This is `get_overlaps` without allocating a `Vec` for the result. Like
+    /// `get_overlaps`, a compound hitbox is reported once, however many of its
+    /// parts overlap the hitbox.
+    pub fn for_each_overlap(&self, id: HbId, mut f: impl FnMut(P)) {
+        let info = self
+            .hitboxes
+            .get(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id));
+        for &other_id in info.overlaps.iter() {
+            let profile = self.hitboxes[&other_id].profile;
+            // only the overlapping part with the lowest id reports a compound
+            let repeated = self.compounds.get(&profile.id()).is_some_and(|parts| {
+                parts
+                    .iter()
+                    .any(|part_id| *part_id < other_id && info.overlaps.contains(part_id))
+            });
+            if !repeated {
+                f(profile);
+            }
+        }
+    }
+
+    /// Returns every pair of hitboxes with a currently tracked overlap.
+    ///
+    /// Each pair is returned once, with the profiles in increasing order by
+    /// `HbId`, and the pairs are sorted by their ids.
+    pub fn all_overlaps(&self) -> Vec<(P, P)> {
+        let mut result: Vec<(P, P)> = self
+            .hitboxes
+            .values()
+            .flat_map(|info| {
+                info.overlaps
+                    .iter()
+                    .map(move |other_id| (info.profile, self.hitboxes[other_id].profile))
+                    .filter(|&(profile, other)| profile.id() < other.id())
+            })
+            .collect();
+        result.sort_by_key(|&(profile_1, profile_2)| (profile_1.id(), profile_2.id()));
+        result.dedup_by_key(|&mut (profile_1, profile_2)| (profile_1.id(), profile_2.id()));
+        result
+    }
+
+    /// Returns the number of currently tracked overlaps on the hitbox with the
+    /// given `id`.
+    ///
+    /// This is a fast constant-time operation.
+    pub fn overlap_count(&self, id: HbId) -> usize {
+        self.hitboxes
+            .get(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id))
+            .overlaps
+            .len()
+    }
+
+    /// Returns true if there are any currently tracked overlaps on the hitbox
+    /// with the given `id`.
+    pub fn has_any_overlap(&self, id: HbId) -> bool {
+        !self
+            .hitboxes
+            .get(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id))
+            .overlaps
+            .is_empty()
+    }
+
+    /// Returns the time at which the currently tracked overlap between the
+    /// hitboxes with `id_1` and `id_2` began, or `None` if they are not
+    /// overlapping.
+    pub fn overlap_start_time(&self, id_1: HbId, id_2: HbId) -> Option<Float> {
+        self.overlap_start_times
+            .get(&ordered_pair(id_1, id_2))
+            .map(|&start_time| start_time + self.frozen_elapsed())
+    }
+
+    /// Returns all pairs of currently overlapping hitboxes that have been
+    /// overlapping for longer than `min_duration` and currently overlap by more
+    /// than `min_depth`, along with their overlap depth.
+    ///
+    /// The depth is the length of `normal_from` between the current shapes.
+    /// Pairs are given in increasing order by `HbId`. A compound hitbox is
+    /// reported once per other hitbox, with the deepest overlap of its parts. This is a diagnostic for
+    /// detecting hitboxes that have become wedged into each other, such as
+    /// when collision response fails.
+    pub fn persistent_overlaps(&self, min_depth: Float, min_duration: Float) -> Vec<(P, P, Float)> {
+        let mut result: Vec<(P, P, Float)> = self
+            .overlap_start_times
+            .iter()
+            .filter(|&(_, &start_time)| self.time - start_time > min_duration)
+            .filter_map(|(&(id_1, id_2), _)| {
+                let shape_1 = self.get_hitbox(id_1).value;
+                let shape_2 = self.get_hitbox(id_2).value;
+                let depth = shape_1.normal_from(&shape_2).len();
+                if depth > min_depth {
+                    let profile_1 = self.hitboxes[&id_1].profile;
+                    let profile_2 = self.hitboxes[&id_2].profile;
+                    if profile_1.id() < profile_2.id() {
+                        Some((profile_1, profile_2, depth))
+                    } else {
+                        Some((profile_2, profile_1, depth))
+                    }
+                } else {
+                    None
+                }
+            })
+            .collect();
+        result.sort_by_key(|&(profile_1, profile_2, depth)| {
+            (profile_1.id(), profile_2.id(), Reverse(n64(depth)))
+        });
+        result.dedup_by_key(|&mut (profile_1, profile_2, _)| (profile_1.id(), profile_2.id()));
+        result
+    }
+
+    /// Returns the profile of the hitbox with the given `id`.
+    pub fn get_profile(&self, id: HbId) -> P {
+        self.hitboxes
+            .get(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id))
+            .profile
+    }
+
+    /// Returns true if there is a currently tracked overlap between the
+    /// hitboxes with `id_1` and `id_2`.
+    pub fn is_overlapping(&self, id_1: HbId, id_2: HbId) -> bool {
+        self.hitboxes
+            .get(&id_1)
             .map(|info| info.overlaps.contains(&id_2))
             .unwrap_or(false)
     }
 
-    /// Returns the profiles of all hitboxes that overlap the given `shape` and
-    /// interact with the given `profile`.
-    pub fn query_overlaps(&self, shape: &PlacedShape, profile: &P) -> Vec<P> {
-        self.grid
-            .shape_cellmates(shape, profile.interact_groups())
+    /// Returns the normal pointing from the hitbox with `id_2` towards the
+    /// hitbox with `id_1`, with a length equal to the depth of their overlap,
+    /// or `None` if there is no currently tracked overlap between them.
+    ///
+    /// The normal is computed from the current shapes using
+    /// `PlacedShape::normal_from`. Since overlaps are tracked with some padding
+    /// (see `Collider::new`), the length may be slightly negative for hitboxes
+    /// that are about to separate.
+    pub fn overlap_normal(&self, id_1: HbId, id_2: HbId) -> Option<DirVec2> {
+        if !self.is_overlapping(id_1, id_2) {
+            return None;
+        }
+        let shape_1 = self.get_hitbox(id_1).value;
+        let shape_2 = self.get_hitbox(id_2).value;
+        Some(shape_1.normal_from(&shape_2))
+    }
+
+    /// Returns the normal pointing from the hitbox with `id_2` towards the
+    /// hitbox with `id_1` at the current time, describing the direction in
+    /// which they parted.
+    ///
+    /// This is only meaningful immediately after a `Separate` event between
+    /// the two hitboxes, before either of them is updated. The hitboxes are no
+    /// longer touching at that time, so the length of the normal is negative
+    /// (see `DetailedEvent::normal`). The normal is computed from the current
+    /// shapes using `PlacedShape::normal_from`.
+    pub fn separation_normal(&self, id_1: HbId, id_2: HbId) -> DirVec2 {
+        let (hitbox_1, hitbox_2) = self.pub_dur_hitbox_pair(id_1, id_2);
+        hitbox_1.value.normal_from(&hitbox_2.value)
+    }
+
+    /// Returns the minimum displacement that would move the hitbox with `id_1`
+    /// out of the hitbox with `id_2`, using only normal directions permitted by
+    /// `mask`.
+    ///
+    /// The displacement is computed from the current shapes using
+    /// `PlacedShape::masked_normal_from`. Returns `None` if `mask` is empty, if
+    /// the hitbox with `id_2` is not solid with respect to `id_1` (see
+    /// `HbProfile::is_solid`), or if the masked normal indicates that no
+    /// displacement along a permitted direction is needed (e.g. the shapes are
+    /// not overlapping). Panics under the same conditions as
+    /// `masked_normal_from` otherwise.
+    pub fn resolve_overlap_masked(&self, id_1: HbId, id_2: HbId, mask: CardMask) -> Option<Vec2> {
+        if mask == CardMask::empty() {
+            return None;
+        }
+        if !self.get_profile(id_1).is_solid(&self.get_profile(id_2)) {
+            return None;
+        }
+        let shape_1 = self.get_hitbox(id_1).value;
+        let shape_2 = self.get_hitbox(id_2).value;
+        let normal = shape_1.masked_normal_from(&shape_2, mask);
+        if normal.len() > 0.0 {
+            Some(normal.into())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the profiles of all hitboxes that the given `shape` would overlap
+    /// at some point while moving with velocity `vel` for the next `duration`
+    /// units of time, and that interact with the given `profile`.
+    ///
+    /// The profiles are sorted in increasing order by the time at which they
+    /// are first overlapped, and a compound hitbox is reported once at the time
+    /// its first part is overlapped. Other hitboxes are assumed to keep moving
+    /// along their current velocities. The shape is not added to the collider,
+    /// and no events are generated. As with `collisions_within`, `duration`
+    /// should be short (on the order of a frame).
+    pub fn query_sweep(
+        &self,
+        shape: &PlacedShape,
+        vel: Vec2,
+        duration: Float,
+        profile: &P,
+    ) -> Vec<P> {
+        assert!(duration >= 0.0, "requires duration >= 0");
+        let hitbox = DurHitbox {
+            value: *shape,
+            vel: DurHbVel {
+                value: vel,
+                duration,
+                ..DurHbVel::still()
+            },
+        };
+        let mut result: Vec<(P, Float)> = self
+            .grid
+            .aabb_cellmates(&hitbox.bounding_box(), GroupSet::of(profile))
+            .iter()
+            .map(|id| &self.hitboxes[id])
+            .filter(|info| info.profile.can_interact(profile))
+            .filter_map(|info| {
+                let other_hitbox = info.pub_dur_hitbox_within(self.time, duration);
+                let delay = one_way_collide_time(profile, &hitbox, &info.profile, &other_hitbox);
+                if delay <= duration {
+                    Some((info.profile, delay))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        result.sort_by_key(|&(profile, delay)| (n64(delay), profile.id()));
+        retain_earliest(&mut result);
+        result.into_iter().map(|(profile, _)| profile).collect()
+    }
+
+    /// Returns the profiles of all hitboxes that overlap the given `shape` and
+    /// interact with the given `profile`, sorted by id.
+    pub fn query_overlaps(&self, shape: &PlacedShape, profile: &P) -> Vec<P> {
+        let mut result = Vec::new();
+        self.for_each_query_overlap(shape, profile, |other| result.push(other));
+        result.sort_by_key(|profile| profile.id());
+        result.dedup_by_key(|profile| profile.id());
+        result
+    }
+
+    /// Calls `f` with the profile of each hitbox that overlaps the given
+    /// `shape` and interacts with the given `profile`, in no particular order.
+    ///
+    /// This is `query_overlaps` without allocating a `Vec` for the result,
+    /// which is useful for queries made in hot loops. Like `query_overlaps`, a
+    /// compound hitbox is reported once, however many of its parts overlap
+    /// `shape`.
+    pub fn for_each_query_overlap(&self, shape: &PlacedShape, profile: &P, mut f: impl FnMut(P)) {
+        let overlaps_shape = |id: HbId| {
+            self.hitboxes[&id]
+                .pub_hitbox_at_time(self.time)
+                .value
+                .overlaps(shape)
+        };
+        self.grid.for_each_aabb_cellmate(
+            &Aabb::from_placed_shape(shape),
+            GroupSet::of(profile),
+            |id| self.hitboxes[&id].grid_bounds(),
+            |id| {
+                let info = &self.hitboxes[&id];
+                if !info.profile.can_interact(profile) || !overlaps_shape(id) {
+                    return;
+                }
+                // only the first overlapping part reports a compound
+                let repeated = self.compounds.get(&info.profile.id()).is_some_and(|parts| {
+                    parts
+                        .iter()
+                        .take_while(|&&part_id| part_id != id)
+                        .any(|&part_id| overlaps_shape(part_id))
+                });
+                if !repeated {
+                    f(info.profile);
+                }
+            },
+        );
+    }
+
+    /// Returns the profiles of all hitboxes whose bounding box overlaps
+    /// `region` and that interact with the given `profile`, sorted by id.
+    ///
+    /// Unlike `query_overlaps`, the shapes are not tested precisely, so
+    /// hitboxes that are merely near `region` may be returned. This is a
+    /// cheaper query intended for coarse spatial lookups such as culling.
+    /// Bounding boxes that only touch are considered overlapping. The bounding
+    /// box of a shape may be obtained using `Aabb::from_placed_shape`.
+    pub fn query_region(&self, region: &Aabb, profile: &P) -> Vec<P> {
+        let mut result: Vec<P> = self
+            .grid
+            .aabb_cellmates(region, GroupSet::of(profile))
+            .iter()
+            .map(|id| &self.hitboxes[id])
+            .filter(|info| info.profile.can_interact(profile))
+            .filter(|info| {
+                let shape = info.pub_hitbox_at_time(self.time).value;
+                region.intersects(&Aabb::from_placed_shape(&shape))
+            })
+            .map(|info| info.profile)
+            .collect();
+        result.sort_by_key(|profile| profile.id());
+        result.dedup_by_key(|profile| profile.id());
+        result
+    }
+
+    /// Returns the profiles of all hitboxes that contain the given `point` and
+    /// interact with the given `profile`, sorted by id.
+    ///
+    /// Points exactly on the edge of a hitbox are contained. A compound hitbox
+    /// is reported once, however many of its parts contain `point`.
+    pub fn query_point(&self, point: Vec2, profile: &P) -> Vec<P> {
+        let mut result: Vec<P> = self
+            .grid
+            .point_cellmates(point, GroupSet::of(profile))
+            .iter()
+            .map(|id| &self.hitboxes[id])
+            .filter(|info| info.profile.can_interact(profile))
+            .filter(|info| {
+                info.pub_hitbox_at_time(self.time)
+                    .value
+                    .contains_point(point)
+            })
+            .map(|info| info.profile)
+            .collect();
+        result.sort_by_key(|profile| profile.id());
+        result.dedup_by_key(|profile| profile.id());
+        result
+    }
+
+    /// Returns the hitbox nearest to `point` that interacts with `profile`,
+    /// along with its distance from `point`, or `None` if no such hitbox is
+    /// within `max_dist`.
+    ///
+    /// The distance is negative if `point` is inside the hitbox. Grid cells are
+    /// searched in rings of increasing distance around `point`, stopping once
+    /// no closer hitbox can be found or no occupied cells remain. Ties are
+    /// broken by the smaller `HbId`. Panics if `max_dist` is negative or not
+    /// finite.
+    pub fn nearest(&self, point: Vec2, max_dist: Float, profile: &P) -> Option<(P, Float)> {
+        assert!(
+            max_dist >= 0.0 && max_dist.is_finite(),
+            "max_dist must be non-negative and finite"
+        );
+        let max_ring = self.grid.max_occupied_ring(point, GroupSet::of(profile))?;
+        let mut best: Option<(P, Float)> = None;
+        let mut seen = FnvHashSet::default();
+        let mut ring = 0;
+        loop {
+            for id in self.grid.ring_cellmates(point, ring, GroupSet::of(profile)) {
+                let info = &self.hitboxes[&id];
+                if !seen.insert(id) || !info.profile.can_interact(profile) {
+                    continue;
+                }
+                let dist = info
+                    .pub_hitbox_at_time(self.time)
+                    .value
+                    .distance_to_point(point);
+                let key = (n64(dist), id);
+                if dist <= max_dist
+                    && best.map_or(true, |(best, best_dist)| key < (n64(best_dist), best.id()))
+                {
+                    best = Some((info.profile, dist));
+                }
+            }
+            // hitboxes in farther rings are at least this distance away
+            let min_dist = ring as Float * self.grid.min_cell_side(GroupSet::of(profile));
+            if ring >= max_ring
+                || min_dist > max_dist
+                || best.is_some_and(|(_, dist)| dist < min_dist)
+            {
+                return best;
+            }
+            ring += 1;
+        }
+    }
+
+    /// Returns the nearest hitbox hit by a ray that starts at `origin` and
+    /// travels along the direction `dir` for at most `max_dist`, along with the
+    /// distance to that hitbox and the point where it was hit.
+    ///
+    /// Hitboxes are tested in their current positions and velocities are
+    /// ignored. Only hitboxes that `profile` can interact with are considered.
+    /// A hitbox that contains `origin` is hit at a distance of `0.0`. Ties are
+    /// broken by the smaller `HbId`. Panics if `dir` is the zero vector or if
+    /// `max_dist` is negative or not finite.
+    pub fn raycast(
+        &self,
+        origin: Vec2,
+        dir: Vec2,
+        max_dist: Float,
+        profile: &P,
+    ) -> Option<(P, Float, Vec2)> {
+        assert!(
+            max_dist >= 0.0 && max_dist.is_finite(),
+            "max_dist must be non-negative and finite"
+        );
+        let dir = dir.normalize().expect("ray direction must be non-zero");
+        let mut best: Option<(P, Float)> = None;
+        let groups = GroupSet::of(profile);
+        self.grid
+            .for_each_ray_cell(origin, dir, max_dist, groups, |entry, ids| {
+                // a hitbox hit before the ray enters this cell is registered in
+                // an earlier cell, so no nearer hit remains
+                if best.is_some_and(|(_, best_dist)| best_dist < entry) {
+                    return false;
+                }
+                for id in ids {
+                    let info = &self.hitboxes[&id];
+                    if !info.profile.can_interact(profile) {
+                        continue;
+                    }
+                    let shape = info.pub_hitbox_at_time(self.time).value;
+                    if let Some(dist) = shape.ray_dist(origin, dir, max_dist) {
+                        let key = (n64(dist), info.profile.id());
+                        if best.map_or(true, |(best, best_dist)| key < (n64(best_dist), best.id()))
+                        {
+                            best = Some((info.profile, dist));
+                        }
+                    }
+                }
+                true
+            });
+        best.map(|(profile, dist)| (profile, dist, origin + dir * dist))
+    }
+
+    /// Returns the first hitbox that the hitbox with the given `id` will collide
+    /// with if it keeps moving along its current velocity, along with the
+    /// position of the hitbox at the time of the collision and that time.
+    ///
+    /// Unlike scheduled events, this prediction is not limited to nearby grid
+    /// cells, and it assumes that no hitboxes are updated in the meantime.
+    /// Hitboxes that are currently overlapping are ignored. This query checks
+    /// every interacting hitbox, so it is slower than most queries.
+    pub fn projected_stop(&self, id: HbId) -> Option<(P, Vec2, Float)> {
+        let info = self
+            .hitboxes
+            .get(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id));
+        info.profile.group()?;
+        let groups = GroupSet::of(&info.profile);
+        let hitbox = info.pub_dur_hitbox_within(self.time, Float::INFINITY);
+        self.hitboxes
+            .iter()
+            .filter(|&(&other_id, _)| other_id != id && !info.overlaps.contains(&other_id))
+            .map(|(_, other_info)| other_info)
+            .filter(|other_info| {
+                other_info
+                    .profile
+                    .group()
+                    .is_some_and(|group| groups.contains(group))
+            })
+            .filter(|other_info| info.profile.can_interact(&other_info.profile))
+            .filter_map(|other_info| {
+                let other_hitbox = other_info.pub_dur_hitbox_within(self.time, Float::INFINITY);
+                let delay = one_way_collide_time(
+                    &info.profile,
+                    &hitbox,
+                    &other_info.profile,
+                    &other_hitbox,
+                );
+                // hits too far in the future to advance the hitbox to are
+                // ignored, as they are for events
+                if delay < HIGH_TIME {
+                    Some((other_info.profile, delay))
+                } else {
+                    None
+                }
+            })
+            .min_by_key(|&(profile, delay)| (n64(delay), profile.id()))
+            .map(|(profile, delay)| {
+                (
+                    profile,
+                    hitbox.advanced_shape(delay).pos,
+                    self.time() + delay,
+                )
+            })
+    }
+
+    /// Returns the time at which the hitboxes with the given IDs will collide if
+    /// they keep moving along their current velocities, or `None` if they will
+    /// not collide.
+    ///
+    /// Groups and interactivity are ignored. If the hitboxes are currently
+    /// overlapping, then the current time is returned.
+    pub fn predicted_collision_time(&self, id_1: HbId, id_2: HbId) -> Option<Float> {
+        let (hitbox_1, hitbox_2) = self.pub_dur_hitbox_pair(id_1, id_2);
+        let delay = hitbox_1.collide_time(&hitbox_2);
+        if delay < Float::INFINITY {
+            Some(self.time() + delay)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the time at which the hitboxes with the given IDs will collide if
+    /// they keep moving along their current velocities, along with the point of
+    /// contact between them at that time (see `PlacedShape::contact_point`), or
+    /// `None` if they will not collide.
+    ///
+    /// Groups and interactivity are ignored. If the hitboxes are currently
+    /// overlapping, then the current time and contact point are returned.
+    pub fn predicted_contact(&self, id_1: HbId, id_2: HbId) -> Option<(Float, Vec2)> {
+        let (hitbox_1, hitbox_2) = self.pub_dur_hitbox_pair(id_1, id_2);
+        hitbox_1
+            .collide_time_and_point(&hitbox_2)
+            .map(|(delay, point)| (self.time() + delay, point))
+    }
+
+    /// Returns the time at which the hitboxes with the given IDs will separate
+    /// if they keep moving along their current velocities, or `None` if they
+    /// will not separate.
+    ///
+    /// This uses the same separation margin as `Separate` events. Groups and
+    /// interactivity are ignored. If the hitboxes are not currently
+    /// overlapping, then the current time is returned.
+    pub fn predicted_separation_time(&self, id_1: HbId, id_2: HbId) -> Option<Float> {
+        let (hitbox_1, hitbox_2) = self.pub_dur_hitbox_pair(id_1, id_2);
+        let groups = (
+            self.get_profile(id_1).group(),
+            self.get_profile(id_2).group(),
+        );
+        let delay = self.separation.time(&hitbox_1, &hitbox_2, groups);
+        if delay < Float::INFINITY {
+            Some(self.time() + delay)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the time at which the centers of the hitboxes with the given IDs
+    /// will be nearest to each other if they keep moving along their current
+    /// velocities, along with the distance between the centers at that time.
+    ///
+    /// Only the remaining durations of the hitbox velocities are considered,
+    /// and the centers follow any acceleration of the hitboxes. If the hitboxes
+    /// are moving apart, then the current time is returned.
+    pub fn closest_approach(&self, id_1: HbId, id_2: HbId) -> (Float, Float) {
+        let (hitbox_1, hitbox_2) = self.pub_dur_hitbox_pair(id_1, id_2);
+        let (delay, dist) = hitbox_1.closest_approach(&hitbox_2);
+        (self.time() + delay, dist)
+    }
+
+    /// Returns the axis-aligned rectangle enclosing the motion of the hitbox
+    /// with the given `id` over the next `duration` units of time.
+    ///
+    /// Only the remaining duration of the hitbox velocity is considered. The
+    /// hitbox is enclosed at every time in the interval, including while it
+    /// resizes or accelerates. If the hitbox is still, then this is the
+    /// bounding rectangle of its current shape. A rotating hitbox is enclosed
+    /// conservatively (see `HbVel::angular`).
+    pub fn swept_bounds(&self, id: HbId, duration: Float) -> PlacedShape {
+        assert!(duration >= 0.0, "duration must be non-negative");
+        self.hitboxes
+            .get(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id))
+            .pub_dur_hitbox_within(self.time, duration)
+            .bounding_box()
+            .to_rect()
+    }
+
+    /// Returns the current distance between the shapes of the hitboxes with the
+    /// given IDs, which is negative if they are overlapping.
+    ///
+    /// See `PlacedShape::distance` for details.
+    pub fn distance(&self, id_1: HbId, id_2: HbId) -> Float {
+        let (hitbox_1, hitbox_2) = self.pub_dur_hitbox_pair(id_1, id_2);
+        hitbox_1.value.distance(&hitbox_2.value)
+    }
+
+    fn pub_dur_hitbox_pair(&self, id_1: HbId, id_2: HbId) -> (DurHitbox, DurHitbox) {
+        let hitbox = |id| {
+            self.hitboxes
+                .get(&id)
+                .unwrap_or_else(|| panic!("hitbox id {} not found", id))
+                .pub_dur_hitbox_within(self.time, Float::INFINITY)
+        };
+        (hitbox(id_1), hitbox(id_2))
+    }
+
+    /// Returns the profiles of all hitboxes that the hitbox with the given `id`
+    /// would overlap if its width and height were each expanded by `margin` on
+    /// both sides.
+    ///
+    /// This is `query_overlaps` using the current shape of the hitbox inflated
+    /// by `margin`, filtered by the interactivity of its own profile. The hitbox
+    /// itself is not included in the result. This is useful for forgiving edge
+    /// detection, such as checking for ground slightly below a character. A
+    /// segment or triangle is inflated as its bounding rectangle, an oriented
+    /// rect is inflated along its own axes, a point is inflated as a circle, and
+    /// the rectangle of an inverted rect is shrunk.
+    pub fn query_overlaps_inflated(&self, id: HbId, margin: Float) -> Vec<P> {
+        let info = self
+            .hitboxes
+            .get(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id));
+        let mut shape = info.pub_hitbox_at_time(self.time).value;
+        if shape.kind().allows_negative_dims() {
+            shape = shape.as_rect();
+        }
+        let margin = if shape.kind() == ShapeKind::InvertedRect {
+            -margin
+        } else {
+            margin
+        };
+        shape.shape = shape
+            .shape
+            .with_dims(shape.dims() + v2(margin, margin) * 2.0);
+        let mut result = self.query_overlaps(&shape, &info.profile);
+        result.retain(|other| other.id() != id);
+        result
+    }
+
+    /// Returns the profiles of hitboxes that the hitbox with the given `id` will
+    /// collide with within the next `dt` units of time, along with the absolute
+    /// time of each collision, in increasing order by time.
+    ///
+    /// Collisions are predicted assuming that no hitboxes are updated in the
+    /// meantime. Hitboxes that are currently overlapping are not reported.
+    /// Only hitboxes registered in grid cells along the path of the hitbox are
+    /// considered, so `dt` should be short (on the order of a frame). This
+    /// query does not modify the scheduled events. A compound hitbox is
+    /// reported once, at the earliest collision with any of its parts.
+    pub fn collisions_within(&self, id: HbId, dt: Float) -> Vec<(P, Float)> {
+        let info = self
+            .hitboxes
+            .get(&id)
+            .unwrap_or_else(|| panic!("hitbox id {} not found", id));
+        if info.profile.group().is_none() {
+            return Vec::new();
+        }
+        let hitbox = info.pub_dur_hitbox_within(self.time, dt);
+        let mut result: Vec<(P, Float)> = self
+            .grid
+            .aabb_cellmates(&hitbox.bounding_box(), GroupSet::of(&info.profile))
             .iter()
-            .map(|id| &self.hitboxes[id])
-            .filter(|info| info.profile.can_interact(profile))
-            .filter(|info| info.pub_hitbox_at_time(self.time).value.overlaps(shape))
-            .map(|info| info.profile)
-            .collect()
+            .filter(|&&other_id| other_id != id && !info.overlaps.contains(&other_id))
+            .map(|other_id| &self.hitboxes[other_id])
+            .filter(|other_info| info.profile.can_interact(&other_info.profile))
+            .filter_map(|other_info| {
+                let other_hitbox = other_info.pub_dur_hitbox_within(self.time, dt);
+                let delay = one_way_collide_time(
+                    &info.profile,
+                    &hitbox,
+                    &other_info.profile,
+                    &other_hitbox,
+                );
+                if delay <= dt {
+                    Some((other_info.profile, self.time() + delay))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        result.sort_by_key(|&(profile, time)| (n64(time), profile.id()));
+        retain_earliest(&mut result);
+        result
+    }
+
+    /// Searches outward from `near` for a position where `shape` does not
+    /// overlap any hitbox that interacts with `profile`.
+    ///
+    /// Candidate positions are sampled on rings of increasing radius around
+    /// `near`, spaced about half the size of `shape` apart. Returns the first
+    /// clear position found within `max_radius` of `near`, or `None` if every
+    /// sampled position is blocked. Panics if `max_radius` is negative or not
+    /// finite.
+    pub fn find_free_position(
+        &self,
+        shape: Shape,
+        near: Vec2,
+        profile: &P,
+        max_radius: Float,
+    ) -> Option<Vec2> {
+        assert!(
+            max_radius >= 0.0 && max_radius.is_finite(),
+            "max_radius must be non-negative and finite"
+        );
+        let step = (shape.dims().x.max(shape.dims().y) * 0.5).max(self.padding);
+        let mut radius = 0.0;
+        while radius <= max_radius {
+            let samples = ((2.0 * consts::PI * radius / step).ceil() as usize).max(1);
+            for i in 0..samples {
+                let angle = 2.0 * consts::PI * (i as Float) / (samples as Float);
+                let pos = near + v2(radius, 0.0).rotate(angle);
+                if self.query_overlaps(&shape.place(pos), profile).is_empty() {
+                    return Some(pos);
+                }
+            }
+            radius += step;
+        }
+        None
+    }
+
+    /// Enables or disables tracking of the grid cells that become occupied or
+    /// empty, which are returned by `take_cell_changes`. Disabled by default.
+    ///
+    /// Tracking adds a little work to every grid update, so it should only be
+    /// enabled when the changes are used. Disabling tracking discards any
+    /// changes that have not been taken.
+    pub fn set_cell_change_tracking(&mut self, enabled: bool) {
+        self.grid.set_cell_change_tracking(enabled);
+    }
+
+    /// Returns the grid cells that became occupied (`true`) or empty (`false`)
+    /// since the last call to this method, as `(group, x, y, occupied)` tuples
+    /// in increasing order.
+    ///
+    /// Each group has its own cells (see `set_group_cell_dims`), and a cell is
+    /// occupied if any hitbox of its group is registered in it. Cells that
+    /// changed and then changed back are not reported. This allows dependent
+    /// spatial structures to be updated incrementally. Returns an empty `Vec`
+    /// unless tracking has been enabled with `set_cell_change_tracking`.
+    pub fn take_cell_changes(&mut self) -> Vec<(HbGroup, i32, i32, bool)> {
+        self.grid.take_cell_changes()
+    }
+
+    /// Returns the ids of all hitboxes that are registered in the grid cell
+    /// with the given coordinates, across all groups, in increasing order.
+    ///
+    /// The cell with coordinates `(x, y)` covers positions from
+    /// `(x * cell_dims.x, y * cell_dims.y)` to
+    /// `((x + 1) * cell_dims.x, (y + 1) * cell_dims.y)`, where `cell_dims` are
+    /// the cell dims of the hitbox's group (see `set_group_cell_dims`). A moving hitbox is
+    /// registered in every cell that it will pass through before it is next
+    /// moved within the grid, and hitboxes without a group are not registered
+    /// at all. This is mostly useful for debugging.
+    pub fn hitboxes_in_cell(&self, cell: (i32, i32)) -> Vec<HbId> {
+        self.grid.cell_ids(cell)
+    }
+
+    /// Returns the coordinates of the grid cell containing `point`, using the
+    /// cell dims that the collider was constructed with.
+    ///
+    /// See `hitboxes_in_cell` for how cell coordinates map to positions. A
+    /// point on a cell boundary belongs to the cell with the greater
+    /// coordinate. This is mostly useful for debugging.
+    pub fn cell_of(&self, point: Vec2) -> (i32, i32) {
+        self.grid.cell_of(point)
+    }
+
+    /// Returns the `cell_width` that the collider was constructed with, or the
+    /// width along the x-axis if the cells are not square.
+    ///
+    /// Groups may override this width using `set_group_cell_width`.
+    pub fn cell_width(&self) -> Float {
+        self.grid.cell_dims().x
+    }
+
+    /// Returns the width and height of the grid cells that the collider was
+    /// constructed with.
+    ///
+    /// Groups may override these dims using `set_group_cell_dims`.
+    pub fn cell_dims(&self) -> Vec2 {
+        self.grid.cell_dims()
+    }
+
+    /// Returns statistics about how hitboxes are distributed in the grid cells,
+    /// which may help with choosing a `cell_width`.
+    pub fn grid_stats(&self) -> GridStats {
+        self.grid.stats()
+    }
+
+    /// Sets the separation margin used for pairs of hitboxes involving the given
+    /// `group`, overriding the separation margin (or `padding`) that the
+    /// collider was constructed with.
+    ///
+    /// Pairs of hitboxes use the greater of the margins of their two groups.
+    /// This allows large, slow hitboxes to use a larger margin than small, fast
+    /// ones. The margin only affects `Separate` events scheduled after this
+    /// call. The minimum hitbox size is still governed by `padding`. Panics if
+    /// `padding` is not positive.
+    pub fn set_group_padding(&mut self, group: HbGroup, padding: Float) {
+        assert!(padding > 0.0, "requires padding > 0.0");
+        self.separation.group_margins.insert(group, padding);
+    }
+
+    /// Sets the width of the grid cells used for hitboxes in the given `group`,
+    /// overriding the `cell_width` that the collider was constructed with.
+    ///
+    /// This is useful when groups have hitboxes of very different sizes, such
+    /// as small bullets and large terrain pieces. Hitboxes already in the group
+    /// are moved into the new cells, and overlaps and scheduled events are
+    /// unaffected. Panics if `cell_width` is not greater than `padding`.
+    pub fn set_group_cell_width(&mut self, group: HbGroup, cell_width: Float) {
+        self.set_group_cell_dims(group, v2(cell_width, cell_width));
+    }
+
+    /// Like `set_group_cell_width`, but allows the grid cells of the `group`
+    /// to have different widths along the x and y axes. Panics if either
+    /// component of `cell_dims` is not greater than `padding`.
+    pub fn set_group_cell_dims(&mut self, group: HbGroup, cell_dims: Vec2) {
+        assert!(
+            cell_dims.x > self.padding && cell_dims.y > self.padding,
+            "requires cell_width > padding"
+        );
+        let members: Vec<(HbId, DurHitbox)> = self
+            .hitboxes
+            .iter()
+            .filter(|&(_, info)| info.profile.group() == Some(group))
+            .map(|(&id, info)| (id, info.hitbox.to_dur_hitbox(info.start_time)))
+            .collect();
+        for &(id, ref hitbox) in &members {
+            self.grid
+                .update_hitbox(id, group, Some(hitbox), None, GroupSet::empty());
+        }
+        self.grid.set_group_cell_dims(group, cell_dims);
+        for &(id, ref hitbox) in &members {
+            self.grid
+                .update_hitbox(id, group, None, Some(hitbox), GroupSet::empty());
+        }
+    }
+
+    /// Returns the smallest rectangle enclosing every hitbox in the given
+    /// `group` at the current time, or `None` if the group has no hitboxes.
+    ///
+    /// This may be used for debug rendering or for culling a whole group at
+    /// once. Runs in time linear to the total number of hitboxes.
+    pub fn group_bounds(&self, group: HbGroup) -> Option<PlacedShape> {
+        self.hitboxes
+            .values()
+            .filter(|info| info.profile.group() == Some(group))
+            .map(|info| Aabb::from_placed_shape(&info.pub_hitbox_at_time(self.time).value))
+            .fold(None, |bounds: Option<Aabb>, aabb| match bounds {
+                Some(bounds) => Some(bounds.union(&aabb)),
+                None => Some(aabb),
+            })
+            .map(|bounds| bounds.to_rect())
     }
 
     fn update_hitbox_tracking(
@@ -324,12 +2431,17 @@ impl<P: HbProfile> Collider<P> {
         old_hitbox: Option<DurHitbox>,
         new_hitbox: DurHitbox,
     ) -> Vec<P> {
-        let mut result = Vec::new();
-        if let Some(group) = info.profile.group() {
+        let result = if let Some(group) = info.profile.group() {
             for &other_id in info.overlaps.clone().iter() {
                 let other_info = self.hitboxes.get_mut(&other_id).unwrap();
-                let delay =
-                    new_hitbox.separate_time(&other_info.hitbox_at_time(self.time), self.padding);
+                let delay = self.separation.time(
+                    &new_hitbox,
+                    &other_info.hitbox_at_time(self.time),
+                    (Some(group), other_info.profile.group()),
+                );
+                if delay > 0.0 && is_discrete_pair(&info.profile, &other_info.profile) {
+                    continue;
+                }
                 self.events.add_pair_event(
                     self.time + delay,
                     InternalEvent::Separate(id, other_id),
@@ -344,107 +2456,368 @@ impl<P: HbProfile> Collider<P> {
                     group,
                     old_hitbox.as_ref(),
                     Some(&new_hitbox),
-                    info.profile.interact_groups(),
+                    GroupSet::of(&info.profile),
                 )
                 .unwrap();
-            for other_id in test_ids {
-                if old_hitbox.is_none() || !info.overlaps.contains(&other_id) {
-                    let other_info = self.hitboxes.get_mut(&other_id).unwrap();
-                    if info.profile.can_interact(&other_info.profile) {
-                        let delay = new_hitbox.collide_time(&other_info.hitbox_at_time(self.time));
-                        if old_hitbox.is_none() && delay == 0.0 {
-                            result.push(other_info.profile);
-                            Collider::process_collision(
-                                id,
-                                &mut info,
-                                other_id,
-                                other_info,
-                                &mut self.events,
-                                self.time,
-                                self.padding,
-                            );
-                        } else {
-                            self.events.add_pair_event(
-                                self.time + delay,
-                                InternalEvent::Collide(id, other_id),
-                                &mut info.event_keys,
-                                &mut other_info.event_keys,
-                            );
-                        }
+            self.check_pairs(id, &mut info, old_hitbox.is_none(), &new_hitbox, test_ids)
+        } else {
+            Vec::new()
+        };
+
+        assert!(self.hitboxes.insert(id, info).is_none());
+        result
+    }
+
+    // schedules Collide events between the given hitbox and the hitboxes with
+    // `test_ids`, returning the profiles of hitboxes that a newly added hitbox
+    // is already colliding with
+    fn check_pairs(
+        &mut self,
+        id: HbId,
+        info: &mut HitboxInfo<P>,
+        is_new: bool,
+        new_hitbox: &DurHitbox,
+        test_ids: impl IntoIterator<Item = HbId>,
+    ) -> Vec<P> {
+        let mut result = Vec::new();
+        if !info.enabled {
+            return result;
+        }
+        for other_id in test_ids {
+            if is_new || !info.overlaps.contains(&other_id) {
+                let other_info = self.hitboxes.get_mut(&other_id).unwrap();
+                // the parts of a compound hitbox share a profile and never interact
+                if other_info.enabled
+                    && other_info.profile.id() != info.profile.id()
+                    && !is_same_team(&info.profile, &other_info.profile)
+                    && cached_can_interact(
+                        &mut self.interact_cache,
+                        &info.profile,
+                        &other_info.profile,
+                    )
+                {
+                    let delay = one_way_collide_time(
+                        &info.profile,
+                        new_hitbox,
+                        &other_info.profile,
+                        &other_info.hitbox_at_time(self.time),
+                    );
+                    if is_new && delay == 0.0 {
+                        result.push(other_info.profile);
+                        Collider::process_collision(
+                            id,
+                            info,
+                            other_id,
+                            other_info,
+                            &mut self.events,
+                            self.time,
+                            &self.separation,
+                        );
+                        self.overlap_start_times
+                            .insert(ordered_pair(id, other_id), self.time);
+                    } else if delay == 0.0 || !is_discrete_pair(&info.profile, &other_info.profile)
+                    {
+                        self.events.add_pair_event(
+                            self.time + delay,
+                            InternalEvent::Collide(id, other_id),
+                            &mut info.event_keys,
+                            &mut other_info.event_keys,
+                        );
                     }
                 }
             }
         }
-
-        assert!(self.hitboxes.insert(id, info).is_none());
+        result.sort_by_key(|profile| profile.id());
+        result.dedup_by_key(|profile| profile.id());
         result
     }
 
     fn clear_overlaps(&mut self, id: HbId, hitbox_info: &mut HitboxInfo<P>) -> Vec<P> {
-        hitbox_info
+        let hitboxes = &mut self.hitboxes;
+        let overlap_start_times = &mut self.overlap_start_times;
+        let mut result: Vec<P> = hitbox_info
             .overlaps
             .drain()
             .map(|other_id| {
-                let other_hitbox_info = self.hitboxes.get_mut(&other_id).unwrap();
+                let other_hitbox_info = hitboxes.get_mut(&other_id).unwrap();
                 assert!(other_hitbox_info.overlaps.remove(&id));
+                overlap_start_times.remove(&ordered_pair(id, other_id));
                 other_hitbox_info.profile
             })
-            .collect()
+            .collect();
+        result.sort_by_key(|profile| profile.id());
+        result.dedup_by_key(|profile| profile.id());
+        result
+    }
+
+    // returns the delay until the bounding box of the hitbox begins to leave
+    // the bounds, marking the hitbox as out of bounds if it is not inside them
+    fn bounds_exit_delay(&self, hitbox_info: &mut HitboxInfo<P>) -> Float {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return Float::INFINITY,
+        };
+        let hitbox = hitbox_info.hitbox.to_dur_hitbox(self.time);
+        let margin = if hitbox_info.out_of_bounds {
+            self.padding
+        } else {
+            0.0
+        };
+        hitbox_info.out_of_bounds = Card::values()
+            .iter()
+            .any(|&card| bounds.edge(card) - hitbox.value.edge(card) < margin);
+        if hitbox_info.out_of_bounds {
+            return Float::INFINITY;
+        }
+        Card::values()
+            .iter()
+            .filter_map(|&card| {
+                // solves for when the edge of the hitbox crosses the edge of the
+                // bounds, ignoring a tangent root where it only touches
+                let gap = bounds.edge(card) - hitbox.value.edge(card);
+                let card_accel = Vec2::from(card) * hitbox.vel.accel;
+                let edge_vel = hitbox.vel.edge(card);
+                quad_root_ascending(card_accel * 0.5, edge_vel, -gap)
+                    .filter(|&delay| delay >= 0.0 && card_accel * delay + edge_vel > 0.0)
+            })
+            .fold(Float::INFINITY, Float::min)
     }
 
-    #[cfg(debug_assertions)]
     fn solitaire_event_check(
         &mut self,
         id: HbId,
         hitbox_info: &mut HitboxInfo<P>,
-        has_group: bool,
+        group: Option<HbGroup>,
     ) {
+        // invalid hitboxes are only detected in debug mode unless the user
+        // has opted in to `Invalidated` events
+        let check_invalid = cfg!(debug_assertions) || self.invalidation_events;
         hitbox_info.pub_end_time = hitbox_info.hitbox.vel.end_time;
-        let mut result = (
-            self.time + self.grid.cell_period(&hitbox_info.hitbox, has_group),
-            InternalEvent::Reiterate(id),
-        );
+        let mut period = self.grid.cell_period(&hitbox_info.hitbox, group);
+        if group.is_some() {
+            period = period.min(hitbox_info.hitbox.rotation_period());
+        }
+        let mut result = (self.time + period, Some(InternalEvent::Reiterate(id)));
+        let exit_time = self.time + self.bounds_exit_delay(hitbox_info);
+        if exit_time < result.0 {
+            result = (exit_time, Some(InternalEvent::ExitBounds(id)));
+        }
         let end_time = hitbox_info.hitbox.vel.end_time;
         if end_time < result.0 {
-            result = (end_time, InternalEvent::PanicDurationPassed(id));
+            result = if self.expiry_events {
+                (end_time, Some(InternalEvent::Expire(id)))
+            } else {
+                (
+                    end_time,
+                    Some(InternalEvent::PanicDurationPassed(id)).filter(|_| check_invalid),
+                )
+            };
         }
         let end_time = self.time + hitbox_info.hitbox.time_until_too_small(self.padding);
         if end_time < result.0 {
-            result = (end_time, InternalEvent::PanicSmallHitbox(id));
+            result = (
+                end_time,
+                Some(InternalEvent::PanicSmallHitbox(id)).filter(|_| check_invalid),
+            );
         }
         hitbox_info.hitbox.vel.end_time = result.0;
-        self.events
-            .add_solitaire_event(result.0, result.1, &mut hitbox_info.event_keys);
+        if let Some(event) = result.1 {
+            self.events
+                .add_solitaire_event(result.0, event, &mut hitbox_info.event_keys);
+        }
     }
+}
 
-    #[cfg(not(debug_assertions))]
-    fn solitaire_event_check(
-        &mut self,
-        id: HbId,
-        hitbox_info: &mut HitboxInfo<P>,
-        has_group: bool,
-    ) {
-        hitbox_info.pub_end_time = hitbox_info.hitbox.vel.end_time;
-        let mut result = (
-            self.time + self.grid.cell_period(&hitbox_info.hitbox, has_group),
-            true,
-        );
-        let end_time = hitbox_info.hitbox.vel.end_time;
-        if end_time < result.0 {
-            result = (end_time, false);
+/// A builder for configuring and constructing a `Collider` instance.
+///
+/// See `Collider::new` for a description of `cell_width` and `padding`, which
+/// must both be set before calling `build`.
+#[derive(Clone, Debug)]
+pub struct ColliderBuilder {
+    cell_dims: Option<Vec2>,
+    padding: Option<Float>,
+    separation_margin: Option<Float>,
+    circle_padding_scale: Float,
+    expiry_events: bool,
+    invalidation_events: bool,
+    deterministic_ties: bool,
+}
+
+impl ColliderBuilder {
+    /// Constructs a new `ColliderBuilder` with no parameters set.
+    pub fn new() -> ColliderBuilder {
+        ColliderBuilder {
+            cell_dims: None,
+            padding: None,
+            separation_margin: None,
+            circle_padding_scale: 1.0,
+            expiry_events: false,
+            invalidation_events: false,
+            deterministic_ties: false,
         }
-        let end_time = self.time + hitbox_info.hitbox.time_until_too_small(self.padding);
-        if end_time < result.0 {
-            result = (end_time, false);
+    }
+
+    /// Sets the width of the cells used in the grid.
+    pub fn cell_width(mut self, cell_width: Float) -> ColliderBuilder {
+        self.cell_dims = Some(v2(cell_width, cell_width));
+        self
+    }
+
+    /// Sets the width and height of the cells used in the grid, for cells that
+    /// are not square. Either this or `cell_width` must be set.
+    ///
+    /// Wide cells suit scenes such as side-scrollers, where hitboxes are
+    /// spread out much further along the x-axis than the y-axis.
+    pub fn cell_dims(mut self, cell_dims: Vec2) -> ColliderBuilder {
+        self.cell_dims = Some(cell_dims);
+        self
+    }
+
+    /// Sets the padding, which is the minimum width and height of hitboxes.
+    ///
+    /// Unless `separation_margin` is set, this is also used as the distance
+    /// that two hitboxes must be apart before they are considered separated.
+    pub fn padding(mut self, padding: Float) -> ColliderBuilder {
+        self.padding = Some(padding);
+        self
+    }
+
+    /// Sets the distance that two hitboxes must be apart after a collision
+    /// before they are considered separated. Defaults to `padding`.
+    ///
+    /// A larger margin reduces collide/separate flicker for hitboxes that rest
+    /// against each other, without requiring a larger minimum hitbox size.
+    pub fn separation_margin(mut self, separation_margin: Float) -> ColliderBuilder {
+        self.separation_margin = Some(separation_margin);
+        self
+    }
+
+    /// Sets a factor that scales the separation margin for pairs of hitboxes
+    /// where at least one is round, meaning a circle, capsule, or point.
+    /// Defaults to `1.0`.
+    ///
+    /// Normals involving round ends are not axis-aligned, so a circle or
+    /// capsule resting in the corner of a rectangle may need a different
+    /// separation margin to avoid flickering between `Collide` and `Separate`
+    /// events than two rectangles would.
+    pub fn circle_padding_scale(mut self, circle_padding_scale: Float) -> ColliderBuilder {
+        self.circle_padding_scale = circle_padding_scale;
+        self
+    }
+
+    /// Sets whether `Expire` events are returned when a hitbox reaches the
+    /// `end_time` of its velocity. Defaults to `false`.
+    ///
+    /// See `Collider::set_expiry_events` for details.
+    pub fn expiry_events(mut self, expiry_events: bool) -> ColliderBuilder {
+        self.expiry_events = expiry_events;
+        self
+    }
+
+    /// Sets whether `Invalidated` events are returned when a hitbox becomes
+    /// too small or outlives its velocity. Defaults to `false`.
+    ///
+    /// See `Collider::set_invalidation_events` for details.
+    pub fn invalidation_events(mut self, invalidation_events: bool) -> ColliderBuilder {
+        self.invalidation_events = invalidation_events;
+        self
+    }
+
+    /// Sets whether events that occur at the exact same time are processed in
+    /// order of the `HbId`s involved. Defaults to `false`.
+    ///
+    /// By default, the order of simultaneous events depends on the order in
+    /// which they were scheduled, which depends on the history of updates to
+    /// the hitboxes. With deterministic ties, simultaneous events are ordered
+    /// by the pair of `HbId`s involved (with internal events such as `Expire`
+    /// still coming before `Collide` and `Separate` events), so that the order
+    /// only depends on the current scene. This is useful for reproducibility
+    /// when event handlers mutate shared state.
+    pub fn deterministic_ties(mut self, deterministic_ties: bool) -> ColliderBuilder {
+        self.deterministic_ties = deterministic_ties;
+        self
+    }
+
+    /// Constructs a new `Collider` instance with the configured parameters.
+    pub fn build<P: HbProfile>(&self) -> Collider<P> {
+        let cell_dims = self.cell_dims.expect("cell_width must be set");
+        let padding = self.padding.expect("padding must be set");
+        let separation_margin = self.separation_margin.unwrap_or(padding);
+        assert!(
+            cell_dims.x > padding && cell_dims.y > padding,
+            "requires cell_width > padding"
+        );
+        assert!(padding > 0.0, "requires padding > 0.0");
+        assert!(separation_margin > 0.0, "requires separation_margin > 0.0");
+        assert!(
+            self.circle_padding_scale > 0.0,
+            "requires circle_padding_scale > 0.0"
+        );
+        Collider {
+            hitboxes: FnvHashMap::default(),
+            time: 0.0,
+            grid: Grid::new(cell_dims),
+            padding,
+            separation: Separation {
+                margin: separation_margin,
+                group_margins: FnvHashMap::default(),
+                circle_scale: self.circle_padding_scale,
+            },
+            events: EventManager::new(self.deterministic_ties),
+            collide_callbacks: FnvHashMap::default(),
+            overlap_start_times: FnvHashMap::default(),
+            interact_cache: FnvHashMap::default(),
+            expiry_events: self.expiry_events,
+            invalidation_events: self.invalidation_events,
+            bounds: None,
+            compounds: FnvHashMap::default(),
+            next_part_id: COMPOUND_PART_ID_START,
+            frozen: None,
+            history: VecDeque::new(),
+            history_frames: 0,
         }
-        hitbox_info.hitbox.vel.end_time = result.0;
-        if result.1 {
-            self.events.add_solitaire_event(
-                result.0,
-                InternalEvent::Reiterate(id),
-                &mut hitbox_info.event_keys,
-            );
+    }
+}
+
+impl Default for ColliderBuilder {
+    fn default() -> ColliderBuilder {
+        ColliderBuilder::new()
+    }
+}
+
+// Separation holds the configuration of the distance that two hitboxes must be
+// apart before they are considered separated.
+#[derive(Clone)]
+struct Separation {
+    margin: Float,
+    group_margins: FnvHashMap<HbGroup, Float>,
+    circle_scale: Float,
+}
+
+impl Separation {
+    fn group_margin(&self, group: Option<HbGroup>) -> Float {
+        group
+            .and_then(|group| self.group_margins.get(&group))
+            .map_or(self.margin, |&margin| margin)
+    }
+
+    fn time(
+        &self,
+        a: &DurHitbox,
+        b: &DurHitbox,
+        groups: (Option<HbGroup>, Option<HbGroup>),
+    ) -> Float {
+        let mut margin = self.group_margin(groups.0).max(self.group_margin(groups.1));
+        let is_round = |hitbox: &DurHitbox| {
+            matches!(
+                hitbox.value.kind(),
+                ShapeKind::Circle | ShapeKind::Capsule | ShapeKind::Point
+            )
+        };
+        if is_round(a) || is_round(b) {
+            margin *= self.circle_scale;
         }
+        a.separate_time(b, margin)
     }
 }
 
@@ -454,17 +2827,39 @@ impl<P: HbProfile> EventKeysMap for FnvHashMap<HbId, HitboxInfo<P>> {
     }
 }
 
+/// A copy of the simulation state of a `Collider`, created by
+/// `Collider::snapshot`.
+#[derive(Clone)]
+pub struct ColliderState<P: HbProfile> {
+    hitboxes: FnvHashMap<HbId, HitboxInfo<P>>,
+    time: Float,
+    grid: Grid,
+    padding: Float,
+    separation: Separation,
+    events: EventManager,
+    overlap_start_times: FnvHashMap<(HbId, HbId), Float>,
+    expiry_events: bool,
+    invalidation_events: bool,
+    bounds: Option<PlacedShape>,
+    compounds: FnvHashMap<HbId, Vec<HbId>>,
+    next_part_id: HbId,
+    frozen: Option<Float>,
+}
+
+#[derive(Clone)]
 struct HitboxInfo<P: HbProfile> {
     profile: P,
     hitbox: Hitbox,
-    start_time: f64,
-    pub_end_time: f64,
+    start_time: Float,
+    pub_end_time: Float,
     event_keys: TightSet<EventKey>,
     overlaps: TightSet<HbId>,
+    enabled: bool,
+    out_of_bounds: bool,
 }
 
 impl<P: HbProfile> HitboxInfo<P> {
-    fn new(hitbox: Hitbox, profile: P, start_time: f64) -> HitboxInfo<P> {
+    fn new(hitbox: Hitbox, profile: P, start_time: Float) -> HitboxInfo<P> {
         HitboxInfo {
             profile,
             pub_end_time: hitbox.vel.end_time,
@@ -472,27 +2867,44 @@ impl<P: HbProfile> HitboxInfo<P> {
             start_time,
             event_keys: TightSet::new(),
             overlaps: TightSet::new(),
+            enabled: true,
+            out_of_bounds: false,
         }
     }
 
-    fn hitbox_at_time(&self, time: f64) -> DurHitbox {
+    fn hitbox_at_time(&self, time: Float) -> DurHitbox {
         assert!(
             time >= self.start_time && time <= self.hitbox.vel.end_time,
             "invalid time"
         );
         let mut result = self.hitbox.clone();
-        result.value = result.advanced_shape(time - self.start_time);
+        result.advance(time - self.start_time);
         result.to_dur_hitbox(time)
     }
 
-    fn pub_hitbox_at_time(&self, time: f64) -> Hitbox {
+    // the bounds that the hitbox was last added to the grid with
+    fn grid_bounds(&self) -> Aabb {
+        self.hitbox.to_dur_hitbox(self.start_time).bounding_box()
+    }
+
+    fn pub_dur_hitbox_within(&self, time: Float, duration: Float) -> DurHitbox {
+        // the end time is limited first, so that a rotating hitbox is enlarged
+        // only for its rotation within `duration`
+        let mut hitbox = self.pub_hitbox_at_time(time);
+        hitbox.vel.end_time = hitbox.vel.end_time.min(time + duration);
+        let mut result = hitbox.to_dur_hitbox(time);
+        result.vel.duration = result.vel.duration.min(duration);
+        result
+    }
+
+    fn pub_hitbox_at_time(&self, time: Float) -> Hitbox {
         assert!(
             time >= self.start_time && time <= self.pub_end_time,
             "invalid time"
         );
         let mut result = self.hitbox.clone();
         result.vel.end_time = self.pub_end_time;
-        result.value = result.advanced_shape(time - self.start_time);
+        result.advance(time - self.start_time);
         result
     }
 }
@@ -508,6 +2920,177 @@ pub enum HbEvent {
     /// A second `Collide` between two hitboxes may not occur before a
     /// `Separate`. A `Separate` event must come after a `Collide` event.
     Separate,
+
+    /// Occurs when a single hitbox reaches the `end_time` of its velocity
+    /// without having been updated. Only generated if enabled with
+    /// `Collider::set_expiry_events`.
+    ///
+    /// Both profiles returned with this event are those of the expired hitbox,
+    /// which has been stopped at its final position.
+    Expire,
+
+    /// Occurs when the bounding box of a single hitbox begins to leave the
+    /// bounds of the world. Only generated if bounds are set with
+    /// `Collider::set_bounds`.
+    ///
+    /// Both profiles returned with this event are those of the hitbox, which
+    /// continues moving with its current velocity.
+    ExitBounds,
+
+    /// Occurs when a single hitbox can no longer be simulated correctly. Only
+    /// generated if enabled with `Collider::set_invalidation_events`.
+    ///
+    /// Both profiles returned with this event are those of the invalidated
+    /// hitbox, which has been stopped at its current position.
+    Invalidated(InvalidationReason),
+}
+
+/// The reason that a hitbox was invalidated, given with an
+/// `HbEvent::Invalidated` event.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum InvalidationReason {
+    /// The hitbox shrank below the minimum size allowed by `padding`.
+    TooSmall,
+
+    /// The hitbox reached the `end_time` of its velocity without having been
+    /// updated, while expiry events were disabled.
+    DurationPassed,
+}
+
+/// An event returned from `Collider::next_detailed`, along with the geometry
+/// of the two hitboxes at the time of the event.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DetailedEvent<P> {
+    /// The type of event.
+    pub event: HbEvent,
+
+    /// The profile of the hitbox with the lower `HbId`.
+    pub profile_1: P,
+
+    /// The profile of the hitbox with the higher `HbId`.
+    pub profile_2: P,
+
+    /// The normal between the two hitboxes, pointing from the second hitbox
+    /// towards the first (see `PlacedShape::normal_from`).
+    ///
+    /// For a `Separate` event, the hitboxes are no longer touching, so the
+    /// length of the normal is negative, but the direction still points in
+    /// the direction that the first hitbox separated in.
+    pub normal: DirVec2,
+
+    /// The point of contact between the two hitboxes, or the nearest point
+    /// between them for a `Separate` event (see `PlacedShape::contact_point`).
+    pub contact: Vec2,
+}
+
+/// A description of an event that is scheduled in a `Collider` instance.
+///
+/// Pairs of ids are given in increasing order.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum EventDescription {
+    /// A scheduled `Collide` event between two hitboxes.
+    Collide(HbId, HbId),
+
+    /// A scheduled `Separate` event between two hitboxes.
+    Separate(HbId, HbId),
+
+    /// An internal event for a single hitbox, such as moving it to new cells
+    /// in the grid. These events are never returned by `Collider::next`.
+    Reiterate(HbId),
+
+    /// A scheduled `Expire` event for a single hitbox.
+    Expire(HbId),
+
+    /// A scheduled `ExitBounds` event for a single hitbox.
+    ExitBounds(HbId),
+
+    /// A scheduled `Invalidated` event for a single hitbox. Unless invalidation
+    /// events are enabled (see `Collider::set_invalidation_events`), this
+    /// panics in debug mode instead of being returned.
+    Invalidated(HbId, InvalidationReason),
+}
+
+impl EventDescription {
+    fn from_internal(event: InternalEvent) -> EventDescription {
+        match event {
+            InternalEvent::Collide(id_1, id_2) => {
+                let (_, id_1, id_2) = new_event(HbEvent::Collide, id_1, id_2);
+                EventDescription::Collide(id_1, id_2)
+            }
+            InternalEvent::Separate(id_1, id_2) => {
+                let (_, id_1, id_2) = new_event(HbEvent::Separate, id_1, id_2);
+                EventDescription::Separate(id_1, id_2)
+            }
+            InternalEvent::Reiterate(id) => EventDescription::Reiterate(id),
+            InternalEvent::Expire(id) => EventDescription::Expire(id),
+            InternalEvent::ExitBounds(id) => EventDescription::ExitBounds(id),
+            InternalEvent::PanicSmallHitbox(id) => {
+                EventDescription::Invalidated(id, InvalidationReason::TooSmall)
+            }
+            InternalEvent::PanicDurationPassed(id) => {
+                EventDescription::Invalidated(id, InvalidationReason::DurationPassed)
+            }
+        }
+    }
+}
+
+// calls `can_interact`, reusing the result for profiles with nonzero interact keys
+fn cached_can_interact<P: HbProfile>(cache: &mut InteractCache, a: &P, b: &P) -> bool {
+    let (key_a, key_b) = (a.interact_key(), b.interact_key());
+    if key_a == 0 || key_b == 0 {
+        return a.can_interact(b);
+    }
+    let (key_a, key_b) = ((key_a, a.group()), (key_b, b.group()));
+    let pair = if key_a <= key_b {
+        (key_a, key_b)
+    } else {
+        (key_b, key_a)
+    };
+    *cache.entry(pair).or_insert_with(|| a.can_interact(b))
+}
+
+// returns the delay until the hitboxes collide, or infinity if the collision is
+// not allowed by the `one_way` direction of either profile
+fn one_way_collide_time<P: HbProfile>(
+    profile_1: &P,
+    hitbox_1: &DurHitbox,
+    profile_2: &P,
+    hitbox_2: &DurHitbox,
+) -> Float {
+    let delay = hitbox_1.collide_time(hitbox_2);
+    if delay == Float::INFINITY || (profile_1.one_way().is_none() && profile_2.one_way().is_none())
+    {
+        return delay;
+    }
+    // velocity of the second hitbox relative to the first when they collide
+    let rel_vel = (hitbox_2.vel.value + hitbox_2.vel.accel * delay)
+        - (hitbox_1.vel.value + hitbox_1.vel.accel * delay);
+    let allowed_1 = profile_1
+        .one_way()
+        .map_or(true, |card| Vec2::from(card) * rel_vel < 0.0);
+    let allowed_2 = profile_2
+        .one_way()
+        .map_or(true, |card| Vec2::from(card) * rel_vel > 0.0);
+    if allowed_1 && allowed_2 {
+        delay
+    } else {
+        Float::INFINITY
+    }
+}
+
+// keeps the first entry for each id, which is the earliest when `entries` is
+// sorted by time, so that compound hitboxes are only reported once
+fn retain_earliest<P: HbProfile>(entries: &mut Vec<(P, Float)>) {
+    let mut seen = FnvHashSet::default();
+    entries.retain(|(profile, _)| seen.insert(profile.id()));
+}
+
+fn ordered_pair(id_1: HbId, id_2: HbId) -> (HbId, HbId) {
+    if id_1 < id_2 {
+        (id_1, id_2)
+    } else {
+        (id_2, id_1)
+    }
 }
 
 fn new_event(event: HbEvent, mut id_1: HbId, mut id_2: HbId) -> (HbEvent, HbId, HbId) {
@@ -15,9 +15,10 @@
 mod solvers;
 
 use core;
+use float::{n64, Float};
 use geom::shape::PlacedBounds;
 use geom::*;
-use std::f64;
+use util;
 
 // DurHitbox (and DurHbVel) is almost identical to Hitbox (and HbVel), except
 // it uses a `duration` (amount of time until invalidation of the hitbox)
@@ -28,7 +29,8 @@ use std::f64;
 pub struct DurHbVel {
     pub value: Vec2,
     pub resize: Vec2,
-    pub duration: f64,
+    pub accel: Vec2,
+    pub duration: Float,
 }
 
 impl DurHbVel {
@@ -36,29 +38,36 @@ impl DurHbVel {
         DurHbVel {
             value: Vec2::zero(),
             resize: Vec2::zero(),
-            duration: f64::INFINITY,
+            accel: Vec2::zero(),
+            duration: Float::INFINITY,
         }
     }
 
-    fn is_still(&self) -> bool {
-        self.value == Vec2::zero() && self.resize == Vec2::zero()
+    pub fn is_still(&self) -> bool {
+        self.value == Vec2::zero() && self.resize == Vec2::zero() && !self.is_accelerating()
     }
 
+    pub fn is_accelerating(&self) -> bool {
+        self.accel != Vec2::zero()
+    }
+
+    // reverses the direction of time; acceleration is unaffected
     fn negate(&self) -> DurHbVel {
         DurHbVel {
             value: -self.value,
             resize: -self.resize,
+            accel: self.accel,
             duration: self.duration,
         }
     }
 }
 
 impl PlacedBounds for DurHbVel {
-    fn bounds_center(&self) -> &Vec2 {
-        &self.value
+    fn bounds_center(&self) -> Vec2 {
+        self.value
     }
-    fn bounds_dims(&self) -> &Vec2 {
-        &self.resize
+    fn bounds_dims(&self) -> Vec2 {
+        self.resize
     }
 }
 
@@ -76,42 +85,145 @@ impl DurHitbox {
         }
     }
 
-    pub fn advanced_shape(&self, time: f64) -> PlacedShape {
+    pub fn advanced_shape(&self, time: Float) -> PlacedShape {
         assert!(
             time < core::HIGH_TIME,
             "requires time < {}",
             core::HIGH_TIME
         );
-        self.value.advance(self.vel.value, self.vel.resize, time)
+        let mut result = self
+            .value
+            .advance(self.vel.value, self.vel.resize, None, time);
+        result.pos += self.vel.accel * (0.5 * time * time);
+        result
+    }
+
+    pub fn advance(&mut self, time: Float) {
+        self.value = self.advanced_shape(time);
+        self.vel.value += self.vel.accel * time;
     }
 
-    pub fn bounding_box(&self) -> PlacedShape {
+    pub fn bounding_box(&self) -> Aabb {
         self.bounding_box_for(self.vel.duration)
     }
 
-    pub fn bounding_box_for(&self, duration: f64) -> PlacedShape {
+    pub fn bounding_box_for(&self, duration: Float) -> Aabb {
+        let result = Aabb::from_placed_shape(&self.value);
         if self.vel.is_still() {
-            self.value.as_rect()
+            result
         } else {
             let end_value = self.advanced_shape(duration);
-            self.value.bounding_box(&end_value)
+            let mut result = result.union(&Aabb::from_placed_shape(&end_value));
+            if self.vel.is_accelerating() {
+                // an accelerating edge may reach its extreme partway through
+                for &card in &Card::values() {
+                    let card_accel = Vec2::from(card) * self.vel.accel;
+                    let time = -self.vel.edge(card) / card_accel;
+                    if time > 0.0 && time < duration {
+                        let value = self.advanced_shape(time);
+                        result = result.union(&Aabb::from_placed_shape(&value));
+                    }
+                }
+            }
+            result
         }
     }
 
-    pub fn collide_time(&self, other: &DurHitbox) -> f64 {
+    // the box that stays within the shape's bounding box for the whole
+    // duration, found in the same way as `bounding_box_for`; the result has
+    // `min > max` along an axis if no such region exists along that axis
+    pub fn interior_box(&self) -> Aabb {
+        let intersect = |a: Aabb, b: Aabb| Aabb {
+            min: a.min.max(b.min),
+            max: a.max.min(b.max),
+        };
+        let mut result = Aabb::from_placed_shape(&self.value);
+        if !self.vel.is_still() {
+            let duration = self.vel.duration;
+            result = intersect(
+                result,
+                Aabb::from_placed_shape(&self.advanced_shape(duration)),
+            );
+            if self.vel.is_accelerating() {
+                for &card in &Card::values() {
+                    let card_accel = Vec2::from(card) * self.vel.accel;
+                    let time = -self.vel.edge(card) / card_accel;
+                    if time > 0.0 && time < duration {
+                        let value = self.advanced_shape(time);
+                        result = intersect(result, Aabb::from_placed_shape(&value));
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    pub fn collide_time(&self, other: &DurHitbox) -> Float {
         solvers::collide_time(self, other)
     }
 
-    pub fn separate_time(&self, other: &DurHitbox, padding: f64) -> f64 {
+    // returns the collide time along with the contact point of the advanced
+    // shapes at that time, or `None` if the hitboxes will not collide (or will
+    // only collide too far in the future for the shapes to be advanced)
+    pub fn collide_time_and_point(&self, other: &DurHitbox) -> Option<(Float, Vec2)> {
+        let time = self.collide_time(other);
+        if time < core::HIGH_TIME {
+            let shape_1 = self.advanced_shape(time);
+            let shape_2 = other.advanced_shape(time);
+            Some((time, shape_1.contact_point(&shape_2)))
+        } else {
+            None
+        }
+    }
+
+    pub fn separate_time(&self, other: &DurHitbox, padding: Float) -> Float {
         solvers::separate_time(self, other, padding)
     }
+
+    // returns the delay until the centers of the hitboxes are nearest (within
+    // the shorter of the two durations) and the distance between the centers
+    // at that time
+    pub fn closest_approach(&self, other: &DurHitbox) -> (Float, Float) {
+        let duration = self.vel.duration.min(other.vel.duration);
+        let rel_pos = other.value.pos - self.value.pos;
+        let rel_vel = other.vel.value - self.vel.value;
+        let rel_accel = other.vel.accel - self.vel.accel;
+        let dist_at =
+            |delay: Float| (rel_pos + rel_vel * delay + rel_accel * (0.5 * delay * delay)).len();
+        if rel_accel == Vec2::zero() {
+            let speed_sq = rel_vel.len_sq();
+            let delay = if speed_sq == 0.0 {
+                0.0
+            } else {
+                (-(rel_pos * rel_vel) / speed_sq).max(0.0).min(duration)
+            };
+            return (delay, dist_at(delay));
+        }
+        // half the derivative of the squared distance is a cubic in the delay,
+        // so the nearest point is at one of its roots or at an end of the range
+        let coeffs = [
+            rel_pos * rel_vel,
+            rel_pos * rel_accel + rel_vel * rel_vel,
+            1.5 * (rel_vel * rel_accel),
+            0.5 * (rel_accel * rel_accel),
+        ];
+        let max = duration.min(core::HIGH_TIME);
+        let mut delays = util::poly_roots_within(&coeffs, max);
+        delays.push(0.0);
+        delays.push(max);
+        delays
+            .into_iter()
+            .map(|delay| (delay, dist_at(delay)))
+            .min_by_key(|&(delay, dist)| (n64(dist), n64(delay)))
+            .unwrap()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use core::dur_hitbox::DurHitbox;
+    use float::{consts, Float, EPS};
     use geom::*;
-    use std::f64;
 
     #[test]
     fn test_rect_rect_collision() {
@@ -127,9 +239,58 @@ mod tests {
         assert_eq!(a.separate_time(&b, 0.1), 0.0);
     }
 
+    #[test]
+    fn test_collide_time_and_point() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(-10.0, 0.0), Shape::circle(2.0)));
+        a.vel.value = v2(1.0, 0.0);
+        a.vel.duration = 100.0;
+        let b = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::circle(2.0)));
+        assert_eq!(a.collide_time_and_point(&b), Some((8.0, v2(-1.0, 0.0))));
+        assert_eq!(b.collide_time_and_point(&a), Some((8.0, v2(-1.0, 0.0))));
+
+        let mut a = DurHitbox::new(PlacedShape::new(v2(-11.0, 0.0), Shape::rect(v2(2.0, 2.0))));
+        a.vel.value = v2(2.0, 0.0);
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(12.0, 2.0), Shape::rect(v2(2.0, 4.0))));
+        b.vel.value = v2(-0.5, 0.0);
+        b.vel.resize = v2(1.0, 0.0);
+        b.vel.duration = 100.0;
+        assert_eq!(a.collide_time_and_point(&b), Some((7.0, v2(4.0, 0.5))));
+
+        let mut c = DurHitbox::new(PlacedShape::new(v2(0.0, 5.0), Shape::circle(2.0)));
+        c.vel.value = v2(0.0, 1.0);
+        c.vel.duration = 100.0;
+        assert_eq!(b.collide_time_and_point(&c), None);
+    }
+
+    #[test]
+    fn test_inverted_rect_collision() {
+        let mut arena = DurHitbox::new(PlacedShape::new(
+            v2(0.0, 0.0),
+            Shape::inverted_rect(v2(10.0, 6.0)),
+        ));
+        arena.vel.duration = 100.0;
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::circle(2.0)));
+        a.vel.value = v2(2.0, -1.0);
+        a.vel.duration = 100.0;
+        assert_eq!(arena.collide_time(&a), 2.0);
+        assert_eq!(a.collide_time(&arena), 2.0);
+        assert_eq!(arena.separate_time(&a, 0.1), 0.0);
+
+        a.value.pos = v2(0.0, -3.0);
+        a.vel.value = v2(0.0, 2.0);
+        assert_eq!(arena.collide_time(&a), 0.0);
+        assert!((arena.separate_time(&a, 0.5) - 0.75).abs() < 1e-12);
+        assert!((a.separate_time(&arena, 0.5) - 0.75).abs() < 1e-12);
+
+        let other = arena.clone();
+        assert_eq!(arena.collide_time(&other), 0.0);
+        assert_eq!(arena.separate_time(&other, 0.1), Float::INFINITY);
+    }
+
     #[test]
     fn test_circle_circle_collision() {
-        let sqrt2 = (2.0f64).sqrt();
+        let sqrt2 = (2.0 as Float).sqrt();
         let mut a = DurHitbox::new(PlacedShape::new(v2(-0.1 * sqrt2, 0.0), Shape::circle(2.0)));
         a.vel.value = v2(0.1, 0.0);
         a.vel.duration = 100.0;
@@ -140,10 +301,20 @@ mod tests {
         b.vel.value = v2(-2.0, 1.0);
         b.vel.resize = v2(-0.1, -0.1);
         b.vel.duration = 100.0;
-        assert!((a.collide_time(&b) - sqrt2).abs() < 1e-7);
+        assert!((a.collide_time(&b) - sqrt2).abs() < EPS);
         assert_eq!(a.separate_time(&b, 0.1), 0.0);
     }
 
+    #[test]
+    fn test_circle_circle_grazing_collision() {
+        let a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::circle(2.0)));
+        let mut b = DurHitbox::new(PlacedShape::new(v2(-5.0, 2.0), Shape::circle(2.0)));
+        b.vel.value = v2(1.0, 0.0);
+        b.vel.duration = 100.0;
+        assert_eq!(a.collide_time(&b), 5.0);
+        assert_eq!(b.collide_time(&a), 5.0);
+    }
+
     #[test]
     fn test_rect_circle_collision() {
         let mut a = DurHitbox::new(PlacedShape::new(v2(-11.0, 0.0), Shape::circle(2.0)));
@@ -165,7 +336,7 @@ mod tests {
         b.vel.value = v2(-1., -1.);
         b.vel.duration = 100.0;
         let collide_time = a.collide_time(&b);
-        let expected_time = 4. - 1. / 2f64.sqrt();
+        let expected_time = 4. - 1. / (2 as Float).sqrt();
         assert_eq!(collide_time, expected_time);
     }
 
@@ -184,7 +355,7 @@ mod tests {
 
     #[test]
     fn test_circle_circle_separation() {
-        let sqrt2 = (2.0f64).sqrt();
+        let sqrt2 = (2.0 as Float).sqrt();
         let mut a = DurHitbox::new(PlacedShape::new(v2(2.0, 5.0), Shape::circle(2.0)));
         a.vel.duration = 100.0;
         let mut b = DurHitbox::new(PlacedShape::new(v2(3.0, 4.0), Shape::circle(1.8)));
@@ -197,7 +368,7 @@ mod tests {
 
     #[test]
     fn test_rect_circle_separation() {
-        let sqrt2 = (2.0f64).sqrt();
+        let sqrt2 = (2.0 as Float).sqrt();
         let mut a = DurHitbox::new(PlacedShape::new(v2(4.0, 2.0), Shape::rect(v2(4.0, 6.0))));
         a.vel.duration = 100.0;
         let mut b = DurHitbox::new(PlacedShape::new(v2(3.0, 4.0), Shape::circle(3.8)));
@@ -216,10 +387,21 @@ mod tests {
         b.vel.value = v2(1., -1.);
         b.vel.duration = 100.0;
         let separate_time = a.separate_time(&b, 0.1);
-        let expected_time = 2. + 1.1 / 2f64.sqrt();
+        let expected_time = 2. + 1.1 / (2 as Float).sqrt();
         assert_eq!(separate_time, expected_time);
     }
 
+    #[test]
+    fn test_still_collision() {
+        let a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::circle(2.0)));
+        let mut b = DurHitbox::new(PlacedShape::new(v2(1.5, 1.5), Shape::square(2.0)));
+        assert_eq!(a.collide_time(&b), 0.0);
+        b.value.pos = v2(2.5, 2.5);
+        assert_eq!(a.collide_time(&b), Float::INFINITY);
+        b.value.pos = v2(2.0, 0.0);
+        assert_eq!(a.collide_time(&b), 0.0);
+    }
+
     #[test]
     fn test_no_collision() {
         let mut a = DurHitbox::new(PlacedShape::new(v2(-11.0, 0.0), Shape::rect(v2(2.0, 2.0))));
@@ -228,17 +410,17 @@ mod tests {
         let mut b = DurHitbox::new(PlacedShape::new(v2(12.0, 2.0), Shape::rect(v2(2.0, 4.0))));
         b.vel.value = v2(-1.0, 1.0);
         b.vel.duration = 100.0;
-        assert_eq!(a.collide_time(&b), f64::INFINITY);
+        assert_eq!(a.collide_time(&b), Float::INFINITY);
         assert_eq!(a.separate_time(&b, 0.1), 0.0);
 
         b.value.shape = Shape::circle(2.0);
         b.vel.resize = Vec2::zero();
-        assert_eq!(a.collide_time(&b), f64::INFINITY);
+        assert_eq!(a.collide_time(&b), Float::INFINITY);
         assert_eq!(a.separate_time(&b, 0.1), 0.0);
 
         a.value.shape = Shape::circle(2.0);
         a.vel.resize = Vec2::zero();
-        assert_eq!(a.collide_time(&b), f64::INFINITY);
+        assert_eq!(a.collide_time(&b), Float::INFINITY);
         assert_eq!(a.separate_time(&b, 0.1), 0.0);
     }
 
@@ -250,23 +432,60 @@ mod tests {
         let mut b = DurHitbox::new(PlacedShape::new(v2(5.0, 1.0), Shape::rect(v2(2.0, 4.0))));
         b.vel.value = v2(2.0, 1.0);
         b.vel.duration = 100.0;
-        assert_eq!(a.separate_time(&b, 0.1), f64::INFINITY);
+        assert_eq!(a.separate_time(&b, 0.1), Float::INFINITY);
         assert_eq!(a.collide_time(&b), 0.0);
 
         b.value.shape = Shape::circle(2.0);
         b.vel.resize = Vec2::zero();
-        assert_eq!(a.separate_time(&b, 0.1), f64::INFINITY);
+        assert_eq!(a.separate_time(&b, 0.1), Float::INFINITY);
         assert_eq!(a.collide_time(&b), 0.0);
 
         a.value.shape = Shape::circle(2.0);
         a.vel.resize = Vec2::zero();
-        assert_eq!(a.separate_time(&b, 0.1), f64::INFINITY);
+        assert_eq!(a.separate_time(&b, 0.1), Float::INFINITY);
+        assert_eq!(a.collide_time(&b), 0.0);
+    }
+
+    #[test]
+    fn test_coincident() {
+        let shapes = [Shape::circle(2.0), Shape::square(2.0)];
+        for &shape_a in &shapes {
+            for &shape_b in &shapes {
+                let mut a = DurHitbox::new(PlacedShape::new(v2(3.0, -1.0), shape_a));
+                a.vel.value = v2(1.0, 2.0);
+                a.vel.duration = 100.0;
+                let mut b = DurHitbox::new(PlacedShape::new(v2(3.0, -1.0), shape_b));
+                b.vel.value = v2(1.0, 2.0);
+                b.vel.duration = 100.0;
+                assert_eq!(a.collide_time(&b), 0.0);
+                assert_eq!(a.separate_time(&b, 0.1), Float::INFINITY);
+
+                a.vel.value = Vec2::zero();
+                b.vel.value = Vec2::zero();
+                assert_eq!(a.collide_time(&b), 0.0);
+                assert_eq!(a.separate_time(&b, 0.1), Float::INFINITY);
+            }
+        }
+    }
+
+    #[test]
+    fn test_coincident_diverging() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::circle(2.0)));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::circle(2.0)));
+        b.vel.value = v2(0.0, 1.0);
+        b.vel.duration = 100.0;
         assert_eq!(a.collide_time(&b), 0.0);
+        assert_eq!(a.separate_time(&b, 0.1), 2.1);
+
+        b.vel.value = Vec2::zero();
+        b.vel.resize = v2(-0.5, -0.5);
+        assert_eq!(a.separate_time(&b, 0.1), Float::INFINITY);
     }
 
     #[test]
     fn test_low_duration() {
-        let sqrt2 = (2.0f64).sqrt();
+        let sqrt2 = (2.0 as Float).sqrt();
         let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::circle(2.0)));
         a.vel.duration = 4.0 - sqrt2 + 0.01;
         let mut b = DurHitbox::new(PlacedShape::new(v2(4.0, 4.0), Shape::circle(2.0)));
@@ -274,8 +493,462 @@ mod tests {
         b.vel.duration = 4.0 - sqrt2 + 0.01;
         assert_eq!(a.collide_time(&b), 4.0 - sqrt2);
         a.vel.duration -= 0.02;
-        assert_eq!(a.collide_time(&b), f64::INFINITY);
+        assert_eq!(a.collide_time(&b), Float::INFINITY);
         b.vel.duration -= 0.02;
-        assert_eq!(a.collide_time(&b), f64::INFINITY);
+        assert_eq!(a.collide_time(&b), Float::INFINITY);
+    }
+
+    #[test]
+    fn test_rect_rect_resize_separation() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::rect(v2(6.0, 4.0))));
+        a.vel.resize = v2(-1.0, -1.0);
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(3.0, 0.0), Shape::rect(v2(2.0, 2.0))));
+        b.vel.duration = 100.0;
+        assert!((a.separate_time(&b, 0.1) - 2.2).abs() < EPS);
+        assert!((b.separate_time(&a, 0.1) - 2.2).abs() < EPS);
+        assert_eq!(a.collide_time(&b), 0.0);
+    }
+
+    #[test]
+    fn test_circle_circle_resize_separation() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::circle(4.0)));
+        a.vel.resize = v2(-1.0, -1.0);
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(2.5, 0.0), Shape::circle(2.0)));
+        b.vel.duration = 100.0;
+        assert!((a.separate_time(&b, 0.1) - 1.2).abs() < EPS);
+        assert!((b.separate_time(&a, 0.1) - 1.2).abs() < EPS);
+        assert_eq!(a.collide_time(&b), 0.0);
+    }
+
+    #[test]
+    fn test_rect_circle_resize_separation() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::square(4.0)));
+        a.vel.resize = v2(-1.0, -1.0);
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(2.5, 0.0), Shape::circle(2.0)));
+        b.vel.duration = 100.0;
+        assert!((a.separate_time(&b, 0.1) - 1.2).abs() < 1e-7);
+        assert!((b.separate_time(&a, 0.1) - 1.2).abs() < 1e-7);
+        assert_eq!(a.collide_time(&b), 0.0);
+    }
+
+    #[test]
+    fn test_accel_collision() {
+        let shapes = [
+            (Shape::square(2.0), Shape::rect(v2(10.0, 2.0))),
+            (Shape::circle(2.0), Shape::circle(2.0)),
+            (Shape::circle(2.0), Shape::square(2.0)),
+        ];
+        for &(shape_a, shape_b) in &shapes {
+            let mut a = DurHitbox::new(shape_a.place(v2(0.0, 10.0)));
+            a.vel.accel = v2(0.0, -2.0);
+            a.vel.duration = 100.0;
+            let mut b = DurHitbox::new(shape_b.place(v2(0.0, 0.0)));
+            b.vel.duration = 100.0;
+            let expected_time = (8 as Float).sqrt();
+            assert!((a.collide_time(&b) - expected_time).abs() < EPS);
+            assert!((b.collide_time(&a) - expected_time).abs() < EPS);
+            assert_eq!(a.separate_time(&b, 0.1), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_accel_corner_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(1.6, 10.0), Shape::circle(2.0)));
+        a.vel.accel = v2(0.0, -2.0);
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::square(2.0)));
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - (8.2 as Float).sqrt()).abs() < 1e-9);
+        a.value.pos.x = 3.0;
+        assert_eq!(a.collide_time(&b), Float::INFINITY);
+    }
+
+    #[test]
+    fn test_accel_thrown_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(-4.0, 0.0), Shape::square(0.5)));
+        a.vel.value = v2(2.0, 4.0);
+        a.vel.accel = v2(0.0, -4.0);
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(-1.5, 4.0), Shape::square(1.5)));
+        b.vel.duration = 100.0;
+        assert_eq!(a.collide_time(&b), Float::INFINITY);
+        b.value.pos.y = 0.0;
+        // the arc passes over the box at first, and hits the box on the way
+        // down when the center of `a` reaches y = 1
+        let expected_time = 1.0 + (0.5 as Float).sqrt();
+        assert!((a.collide_time(&b) - expected_time).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_accel_separation() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::circle(2.0)));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(0.0, 0.5), Shape::circle(2.0)));
+        b.vel.accel = v2(0.0, 2.0);
+        b.vel.duration = 100.0;
+        assert!((a.separate_time(&b, 0.1) - (1.6 as Float).sqrt()).abs() < EPS);
+        assert!((b.separate_time(&a, 0.1) - (1.6 as Float).sqrt()).abs() < EPS);
+        assert_eq!(a.collide_time(&b), 0.0);
+    }
+
+    #[test]
+    fn test_segment_circle_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::segment(v2(4.0, 0.0))));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(1.0, 5.0), Shape::circle(2.0)));
+        b.vel.value = v2(0.0, -1.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - 4.0).abs() < EPS);
+        assert!((b.collide_time(&a) - 4.0).abs() < EPS);
+        assert_eq!(a.separate_time(&b, 0.1), 0.0);
+
+        // hits the end of the segment
+        b.value.pos = v2(2.6, 5.0);
+        assert!((a.collide_time(&b) - 4.2).abs() < EPS);
+
+        // moving parallel to the segment
+        b.value.pos = v2(-10.0, 0.5);
+        b.vel.value = v2(1.0, 0.0);
+        assert!((a.collide_time(&b) - (8.0 - (0.75 as Float).sqrt())).abs() < EPS);
+        b.value.pos = v2(-10.0, 1.5);
+        assert_eq!(a.collide_time(&b), Float::INFINITY);
+    }
+
+    #[test]
+    fn test_segment_circle_separation() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::segment(v2(4.0, 0.0))));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(1.0, 0.0), Shape::circle(2.0)));
+        b.vel.value = v2(0.0, -1.0);
+        b.vel.duration = 100.0;
+        assert!((a.separate_time(&b, 0.1) - 1.1).abs() < 1e-9);
+        assert!((b.separate_time(&a, 0.1) - 1.1).abs() < 1e-9);
+        assert_eq!(a.collide_time(&b), 0.0);
+    }
+
+    #[test]
+    fn test_point_segment_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::segment(Vec2::zero())));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(-5.0, 0.0), Shape::circle(2.0)));
+        b.vel.value = v2(1.0, 0.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - 4.0).abs() < 1e-9);
+        let mut b = DurHitbox::new(PlacedShape::new(v2(-5.0, 0.0), Shape::square(2.0)));
+        b.vel.value = v2(1.0, 0.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_segment_rect_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::segment(v2(2.0, 2.0))));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(-5.0, 1.5), Shape::square(2.0)));
+        b.vel.value = v2(1.0, 0.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - 4.5).abs() < EPS);
+        assert!((b.collide_time(&a) - 4.5).abs() < EPS);
+        b.value.pos.y = 3.0;
+        assert_eq!(a.collide_time(&b), Float::INFINITY);
+
+        let mut c = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::segment(v2(0.0, 2.0))));
+        c.vel.duration = 100.0;
+        assert_eq!(a.collide_time(&c), 0.0);
+        assert_eq!(a.separate_time(&c, 0.1), Float::INFINITY);
+        c.value.pos.x = 3.0;
+        c.vel.value = v2(-1.0, 0.0);
+        assert!((a.collide_time(&c) - 2.0).abs() < EPS);
+        c.value.pos.x = 0.5;
+        assert!((c.separate_time(&a, 0.1) - 1.6).abs() < EPS);
+    }
+    #[test]
+    fn test_capsule_circle_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::capsule(v2(4.0, 2.0))));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(0.0, 5.0), Shape::circle(2.0)));
+        b.vel.value = v2(0.0, -1.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - 3.0).abs() < EPS);
+        assert!((b.collide_time(&a) - 3.0).abs() < EPS);
+
+        // hits the rounded end
+        b.value.pos = v2(2.5, 5.0);
+        assert!((a.collide_time(&b) - (5.0 - (1.75 as Float).sqrt())).abs() < EPS);
+    }
+
+    #[test]
+    fn test_capsule_rect_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::capsule(v2(4.0, 2.0))));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(0.0, 5.0), Shape::square(2.0)));
+        b.vel.value = v2(0.0, -1.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - 3.0).abs() < 1e-9);
+
+        // the corner of the rect passes the bounding box of the capsule before
+        // reaching the rounded end
+        b.value.pos = v2(5.0, 5.0);
+        b.vel.value = v2(-1.0, -1.0);
+        assert!((a.collide_time(&b) - 3.0).abs() < 1e-9);
+        assert!((b.collide_time(&a) - 3.0).abs() < 1e-9);
+
+        b.value.pos = v2(0.0, 1.5);
+        b.vel.value = v2(0.0, 1.0);
+        assert_eq!(a.collide_time(&b), 0.0);
+        assert!((a.separate_time(&b, 0.1) - 0.6).abs() < 1e-9);
+        assert!((b.separate_time(&a, 0.1) - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_capsule_capsule_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::capsule(v2(4.0, 2.0))));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(5.0, 0.0), Shape::capsule(v2(2.0, 4.0))));
+        b.vel.value = v2(-1.0, 0.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - 2.0).abs() < 1e-9);
+
+        b.value.pos = v2(0.0, 5.0);
+        b.vel.value = v2(0.0, -1.0);
+        assert!((a.collide_time(&b) - 2.0).abs() < 1e-9);
+
+        b.value.pos = v2(4.0, 4.0);
+        b.vel.value = v2(-1.0, -1.0);
+        assert!((a.collide_time(&b) - (3.0 - (2 as Float).sqrt())).abs() < 1e-9);
+        assert!((b.collide_time(&a) - (3.0 - (2 as Float).sqrt())).abs() < 1e-9);
+
+        b.value.pos = v2(0.5, 0.5);
+        b.vel.value = v2(0.0, 1.0);
+        assert!((a.separate_time(&b, 0.1) - 2.6).abs() < 1e-9);
+        assert!((b.separate_time(&a, 0.1) - 2.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_capsule_segment_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::capsule(v2(4.0, 2.0))));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(5.0, 0.0), Shape::segment(v2(0.0, 4.0))));
+        b.vel.value = v2(-1.0, 0.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - 3.0).abs() < EPS);
+        assert!((b.collide_time(&a) - 3.0).abs() < EPS);
+
+        // a fast segment does not tunnel through the capsule
+        b.value.pos = v2(100.0, 0.0);
+        b.vel.value = v2(-1000.0, 0.0);
+        assert!((a.collide_time(&b) - 0.098).abs() < EPS);
+
+        b.value.pos = v2(1.5, 0.0);
+        b.vel.value = v2(1.0, 0.0);
+        assert!((a.separate_time(&b, 0.1) - 0.6).abs() < EPS);
+        assert!((b.separate_time(&a, 0.1) - 0.6).abs() < EPS);
+    }
+
+    #[test]
+    fn test_triangle_rect_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(
+            v2(0.0, 0.0),
+            Shape::triangle(v2(4.0, 4.0)),
+        ));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(5.0, 5.0), Shape::square(2.0)));
+        b.vel.value = v2(-1.0, -1.0);
+        b.vel.duration = 100.0;
+        // the bounding boxes overlap at time 2.0
+        assert!((a.collide_time(&b) - 4.0).abs() < EPS);
+        assert!((b.collide_time(&a) - 4.0).abs() < EPS);
+
+        b.value.pos = v2(0.0, 5.0);
+        b.vel.value = v2(0.0, -1.0);
+        assert!((a.collide_time(&b) - 3.0).abs() < EPS);
+    }
+
+    #[test]
+    fn test_triangle_circle_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(
+            v2(0.0, 0.0),
+            Shape::triangle(v2(4.0, 4.0)),
+        ));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(5.0, 5.0), Shape::circle(2.0)));
+        b.vel.value = v2(-1.0, -1.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - (5.0 - (0.5 as Float).sqrt())).abs() < EPS);
+        assert!((b.collide_time(&a) - (5.0 - (0.5 as Float).sqrt())).abs() < EPS);
+
+        b.value.pos = v2(5.0, -1.0);
+        b.vel.value = v2(-1.0, 0.0);
+        assert!((a.collide_time(&b) - (4.0 - (2 as Float).sqrt())).abs() < EPS);
+
+        let diag = v2(1.0, 1.0) * (0.5 as Float).sqrt();
+        b.value.pos = diag * 0.5;
+        b.vel.value = diag;
+        assert!((a.separate_time(&b, 0.1) - 0.6).abs() < EPS);
+        assert!((b.separate_time(&a, 0.1) - 0.6).abs() < EPS);
+
+        // sliding along the hypotenuse does not separate until the end
+        b.value.pos = diag;
+        b.vel.value = v2(1.0, -1.0);
+        assert!(a.separate_time(&b, 0.1) > 2.0);
+    }
+
+    #[test]
+    fn test_triangle_triangle_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(
+            v2(0.0, 0.0),
+            Shape::triangle(v2(4.0, 4.0)),
+        ));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(
+            v2(1.0, 1.0),
+            Shape::triangle(v2(-4.0, -4.0)),
+        ));
+        b.vel.value = v2(-1.0, -1.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - 1.0).abs() < EPS);
+
+        b.value.pos = v2(-0.25, -0.25);
+        b.vel.value = v2(1.0, 1.0);
+        let time = (0.5 + 0.1 * (2 as Float).sqrt()) * 0.5;
+        assert!((a.separate_time(&b, 0.1) - time).abs() < EPS);
+        assert!((b.separate_time(&a, 0.1) - time).abs() < EPS);
+    }
+
+    #[test]
+    fn test_triangle_segment_capsule_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(
+            v2(0.0, 0.0),
+            Shape::triangle(v2(4.0, 4.0)),
+        ));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(5.0, 0.0), Shape::segment(v2(0.0, 4.0))));
+        b.vel.value = v2(-1.0, 0.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - 3.0).abs() < 1e-9);
+        assert!((b.collide_time(&a) - 3.0).abs() < 1e-9);
+
+        let mut b = DurHitbox::new(PlacedShape::new(v2(0.0, 5.0), Shape::capsule(v2(4.0, 2.0))));
+        b.vel.value = v2(0.0, -1.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - (4.0 - (2 as Float).sqrt())).abs() < 1e-9);
+        assert!((b.collide_time(&a) - (4.0 - (2 as Float).sqrt())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_closest_approach() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::circle(1.0)));
+        a.vel.value = v2(1.0, 0.0);
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(10.0, 3.0), Shape::circle(1.0)));
+        b.vel.value = v2(-1.0, 0.0);
+        b.vel.duration = 100.0;
+        assert_eq!(a.closest_approach(&b), (5.0, 3.0));
+
+        b.vel.duration = 2.0;
+        assert_eq!(a.closest_approach(&b), (2.0, (45 as Float).sqrt()));
+
+        a.vel.value = v2(-1.0, 0.0);
+        b.vel.value = v2(1.0, 0.0);
+        assert_eq!(a.closest_approach(&b), (0.0, (109 as Float).sqrt()));
+    }
+
+    #[test]
+    fn test_accel_closest_approach() {
+        let a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::circle(1.0)));
+        let mut b = DurHitbox::new(PlacedShape::new(v2(10.0, 3.0), Shape::circle(1.0)));
+        b.vel.accel = v2(-2.0, 0.0);
+        b.vel.duration = 100.0;
+        let (delay, dist) = a.closest_approach(&b);
+        assert!((delay - (10 as Float).sqrt()).abs() < EPS);
+        assert!((dist - 3.0).abs() < EPS);
+        assert_eq!(b.closest_approach(&a), (delay, dist));
+
+        b.vel.duration = 2.0;
+        assert_eq!(a.closest_approach(&b), (2.0, (45 as Float).sqrt()));
+
+        b.vel.value = v2(5.0, 0.0);
+        b.vel.duration = 100.0;
+        let (delay, dist) = a.closest_approach(&b);
+        assert!((delay - (5.0 + (65 as Float).sqrt()) * 0.5).abs() < EPS);
+        assert!((dist - 3.0).abs() < EPS);
+    }
+
+    #[test]
+    fn test_oriented_rect_collision() {
+        let sqrt2 = (2 as Float).sqrt();
+        let diamond = Shape::oriented_rect(v2(2.0, 2.0), consts::FRAC_PI_4);
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), diamond));
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(5.0, 0.0), diamond));
+        b.vel.value = v2(-1.0, 0.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - (5.0 - 2.0 * sqrt2)).abs() < 1e-9);
+        assert!((b.collide_time(&a) - (5.0 - 2.0 * sqrt2)).abs() < 1e-9);
+
+        b.value.shape = Shape::square(2.0);
+        assert!((a.collide_time(&b) - (4.0 - sqrt2)).abs() < 1e-9);
+        assert!((b.collide_time(&a) - (4.0 - sqrt2)).abs() < 1e-9);
+
+        b.value.pos = v2(0.0, 0.0);
+        b.vel.value = v2(0.0, 1.0);
+        assert!((a.separate_time(&b, 0.1) - (1.0 + 1.1 * sqrt2)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_oriented_rect_circle_collision() {
+        let sqrt2 = (2 as Float).sqrt();
+        let diamond = Shape::oriented_rect(v2(2.0, 2.0), consts::FRAC_PI_4);
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), diamond));
+        a.vel.duration = 100.0;
+        // hits the face of the diamond
+        let mut b = DurHitbox::new(PlacedShape::new(v2(3.0, 3.0), Shape::circle(2.0)));
+        b.vel.value = v2(-1.0, -1.0);
+        b.vel.duration = 100.0;
+        assert!((a.collide_time(&b) - (3.0 - sqrt2)).abs() < EPS);
+        assert!((b.collide_time(&a) - (3.0 - sqrt2)).abs() < EPS);
+
+        // hits the corner of the diamond
+        b.value.pos = v2(5.0, 0.0);
+        b.vel.value = v2(-1.0, 0.0);
+        assert!((a.collide_time(&b) - (4.0 - sqrt2)).abs() < EPS);
+
+        b.value.pos = v2(0.0, 0.0);
+        b.vel.value = v2(1.0, 0.0);
+        assert!((a.separate_time(&b, 0.1) - (sqrt2 + 1.1)).abs() < EPS);
+        assert!((b.separate_time(&a, 0.1) - (sqrt2 + 1.1)).abs() < EPS);
+    }
+
+    #[test]
+    fn test_point_collision() {
+        let mut a = DurHitbox::new(PlacedShape::new(v2(0.0, 0.0), Shape::point()));
+        a.vel.value = v2(1.0, 0.0);
+        a.vel.duration = 100.0;
+        let mut b = DurHitbox::new(PlacedShape::new(v2(5.0, 0.0), Shape::square(2.0)));
+        b.vel.duration = 100.0;
+        assert_eq!(a.collide_time(&b), 4.0);
+        assert_eq!(b.collide_time(&a), 4.0);
+
+        b.value.pos = v2(0.0, 0.0);
+        assert_eq!(a.separate_time(&b, 0.1), 1.1);
+        assert_eq!(b.separate_time(&a, 0.1), 1.1);
+
+        // two points only overlap if they coincide exactly
+        let mut b = DurHitbox::new(PlacedShape::new(v2(3.0, 1.0), Shape::point()));
+        b.vel.duration = 100.0;
+        assert_eq!(a.collide_time(&b), Float::INFINITY);
+        b.value.pos = v2(0.0, 0.0);
+        assert!((a.separate_time(&b, 0.1) - 0.1).abs() < EPS);
+    }
+
+    #[test]
+    fn test_unrotated_oriented_rect() {
+        assert_eq!(
+            Shape::oriented_rect(v2(2.0, 3.0), 0.0),
+            Shape::rect(v2(2.0, 3.0))
+        );
     }
 }
@@ -14,52 +14,140 @@
 
 use core;
 use core::dur_hitbox::DurHitbox;
-use geom::shape::PlacedBounds;
+use float::{n64, Float};
+use geom::shape::{PlacedBounds, Sector};
 use geom::*;
-use std::f64;
+use std::cmp::Ordering;
 use util;
 
 // This module contains methods to solve for the collision/separation time
 // of two hitboxes.
 
-pub fn collide_time(a: &DurHitbox, b: &DurHitbox) -> f64 {
+pub fn collide_time(a: &DurHitbox, b: &DurHitbox) -> Float {
     let duration = a.vel.duration.min(b.vel.duration);
-    if a.bounding_box_for(duration)
-        .overlaps(&b.bounding_box_for(duration))
+    // bounding boxes cannot be computed for unbounded durations, so the
+    // pruning step is skipped in that case, unless both hitboxes are still; the
+    // bounding box of an inverted rect does not cover its overlapping region,
+    // so it is not pruned either
+    let is_still = a.vel.is_still() && b.vel.is_still();
+    let is_inverted =
+        a.value.kind() == ShapeKind::InvertedRect || b.value.kind() == ShapeKind::InvertedRect;
+    if (duration >= core::HIGH_TIME && !is_still)
+        || is_inverted
+        || a.bounding_box_for(duration)
+            .intersects(&b.bounding_box_for(duration))
     {
         time_unpadded(a, b, true, duration)
     } else {
-        f64::INFINITY
+        Float::INFINITY
     }
 }
 
-pub fn separate_time(a: &DurHitbox, b: &DurHitbox, padding: f64) -> f64 {
+pub fn separate_time(a: &DurHitbox, b: &DurHitbox, padding: Float) -> Float {
+    // segments have no area, so the other shape is padded instead, and a
+    // capsule is preferred for padding since it stays exact when inflated
+    let (a, b) = (&point_as_circle(a), &point_as_circle(b));
     let (a, b) = match (a.value.kind(), b.value.kind()) {
-        (ShapeKind::Rect, ShapeKind::Circle) => (b, a),
+        (ShapeKind::InvertedRect, _) => (a, b),
+        (_, ShapeKind::InvertedRect) => (b, a),
+        (ShapeKind::Segment, ShapeKind::Segment) => {
+            // neither segment can be padded, so they separate once they are
+            // more than `padding` apart
+            let result = first_sign_change_time(&segment_segment_regions(a, b, padding), false);
+            return if result >= a.vel.duration.min(b.vel.duration) {
+                Float::INFINITY
+            } else {
+                result
+            };
+        }
+        (ShapeKind::Rect, ShapeKind::Circle)
+        | (ShapeKind::Triangle, ShapeKind::Circle)
+        | (ShapeKind::OrientedRect, ShapeKind::Circle)
+        | (ShapeKind::Segment, _)
+        | (_, ShapeKind::Capsule) => (b, a),
         _ => (a, b),
     };
     let mut a = a.clone();
-    a.value.shape = Shape::new(a.value.kind(), a.value.dims() + v2(padding, padding) * 2.0);
+    if a.value.kind() == ShapeKind::Triangle {
+        a.value = a.value.padded_triangle(padding);
+    } else if a.value.kind() == ShapeKind::InvertedRect {
+        // the outside of a rect is padded by shrinking the rect
+        a.value.shape = a
+            .value
+            .shape
+            .with_dims(a.value.dims() - v2(padding, padding) * 2.0);
+    } else {
+        a.value.shape = a
+            .value
+            .shape
+            .with_dims(a.value.dims() + v2(padding, padding) * 2.0);
+    }
     time_unpadded(&a, b, false, a.vel.duration.min(b.vel.duration))
 }
 
-fn time_unpadded(a: &DurHitbox, b: &DurHitbox, for_collide: bool, duration: f64) -> f64 {
-    let result = match (a.value.kind(), b.value.kind()) {
-        (ShapeKind::Rect, ShapeKind::Rect) => rect_rect_time(a, b, for_collide),
-        (ShapeKind::Circle, ShapeKind::Circle) => circle_circle_time(a, b, for_collide),
-        (ShapeKind::Rect, ShapeKind::Circle) => rect_circle_time(a, b, for_collide, duration),
-        (ShapeKind::Circle, ShapeKind::Rect) => rect_circle_time(b, a, for_collide, duration),
-    };
+fn time_unpadded(a: &DurHitbox, b: &DurHitbox, for_collide: bool, duration: Float) -> Float {
+    let result =
+        match (a.value.kind(), b.value.kind()) {
+            // the outsides of two rects always overlap
+            (ShapeKind::InvertedRect, ShapeKind::InvertedRect) => {
+                if for_collide {
+                    0.0
+                } else {
+                    Float::INFINITY
+                }
+            }
+            (ShapeKind::InvertedRect, _) => inverted_rect_time(a, b, for_collide),
+            (_, ShapeKind::InvertedRect) => inverted_rect_time(b, a, for_collide),
+            (ShapeKind::Point, _) | (_, ShapeKind::Point) => time_unpadded(
+                &point_as_circle(a),
+                &point_as_circle(b),
+                for_collide,
+                duration,
+            ),
+            (ShapeKind::Rect, ShapeKind::Rect) => rect_rect_time(a, b, for_collide),
+            (ShapeKind::Circle, ShapeKind::Circle) => circle_circle_time(a, b, for_collide),
+            (ShapeKind::Rect, ShapeKind::Circle) => rect_circle_time(a, b, for_collide, duration),
+            (ShapeKind::Circle, ShapeKind::Rect) => rect_circle_time(b, a, for_collide, duration),
+            (ShapeKind::Capsule, _) => capsule_time(a, b, for_collide),
+            (_, ShapeKind::Capsule) => capsule_time(b, a, for_collide),
+            (ShapeKind::Triangle, ShapeKind::Circle)
+            | (ShapeKind::OrientedRect, ShapeKind::Circle) => convex_circle_time(a, b, for_collide),
+            (ShapeKind::Circle, ShapeKind::Triangle)
+            | (ShapeKind::Circle, ShapeKind::OrientedRect) => convex_circle_time(b, a, for_collide),
+            (ShapeKind::Triangle, _) | (ShapeKind::OrientedRect, _) => {
+                convex_poly_time(a, b, for_collide)
+            }
+            (_, ShapeKind::Triangle) | (_, ShapeKind::OrientedRect) => {
+                convex_poly_time(b, a, for_collide)
+            }
+            (ShapeKind::Segment, ShapeKind::Circle) => segment_circle_time(a, b, for_collide),
+            (ShapeKind::Circle, ShapeKind::Segment) => segment_circle_time(b, a, for_collide),
+            (ShapeKind::Segment, ShapeKind::Rect) => segment_rect_time(a, b, for_collide),
+            (ShapeKind::Rect, ShapeKind::Segment) => segment_rect_time(b, a, for_collide),
+            (ShapeKind::Segment, ShapeKind::Segment) => {
+                first_sign_change_time(&segment_segment_regions(a, b, 0.0), for_collide)
+            }
+        };
     if result >= duration {
-        f64::INFINITY
+        Float::INFINITY
     } else {
         result
     }
 }
 
-fn rect_rect_time(a: &DurHitbox, b: &DurHitbox, for_collide: bool) -> f64 {
-    let mut overlap_start = 0.0f64;
-    let mut overlap_end = f64::INFINITY;
+fn point_as_circle(hitbox: &DurHitbox) -> DurHitbox {
+    DurHitbox {
+        value: hitbox.value.point_as_circle(),
+        vel: hitbox.vel.clone(),
+    }
+}
+
+fn rect_rect_time(a: &DurHitbox, b: &DurHitbox, for_collide: bool) -> Float {
+    if a.vel.is_accelerating() || b.vel.is_accelerating() {
+        return accel_rect_rect_time(a, b, for_collide);
+    }
+    let mut overlap_start: Float = 0.0;
+    let mut overlap_end = Float::INFINITY;
     for &card in &Card::values() {
         let overlap = a.value.card_overlap(&b.value, card);
         let overlap_vel = a.vel.card_overlap(&b.vel, card);
@@ -67,7 +155,7 @@ fn rect_rect_time(a: &DurHitbox, b: &DurHitbox, for_collide: bool) -> f64 {
             if !for_collide {
                 return 0.0;
             } else if overlap_vel <= 0.0 {
-                return f64::INFINITY;
+                return Float::INFINITY;
             } else {
                 overlap_start = overlap_start.max(-overlap / overlap_vel);
             }
@@ -75,7 +163,7 @@ fn rect_rect_time(a: &DurHitbox, b: &DurHitbox, for_collide: bool) -> f64 {
             overlap_end = overlap_end.min(-overlap / overlap_vel);
         }
         if overlap_start >= overlap_end {
-            return if for_collide { f64::INFINITY } else { 0.0 };
+            return if for_collide { Float::INFINITY } else { 0.0 };
         }
     }
     if for_collide {
@@ -85,7 +173,10 @@ fn rect_rect_time(a: &DurHitbox, b: &DurHitbox, for_collide: bool) -> f64 {
     }
 }
 
-fn circle_circle_time(a: &DurHitbox, b: &DurHitbox, for_collide: bool) -> f64 {
+fn circle_circle_time(a: &DurHitbox, b: &DurHitbox, for_collide: bool) -> Float {
+    if a.vel.is_accelerating() || b.vel.is_accelerating() {
+        return accel_circle_circle_time(a, b, for_collide);
+    }
     let sign = if for_collide { 1.0 } else { -1.0 };
 
     let net_rad = (a.value.dims().x + b.value.dims().x) * 0.5;
@@ -99,16 +190,33 @@ fn circle_circle_time(a: &DurHitbox, b: &DurHitbox, for_collide: bool) -> f64 {
     let net_rad_vel = (a.vel.resize.x + b.vel.resize.x) * 0.5;
     let dist_vel = a.vel.value - b.vel.value;
 
+    // the relative geometry is constant (e.g. coincident hitboxes moving
+    // together), so the state found above will never change
+    if net_rad_vel == 0.0 && dist_vel == Vec2::zero() {
+        return Float::INFINITY;
+    }
+
     let coeff_a = sign * (net_rad_vel * net_rad_vel - dist_vel.len_sq());
     let coeff_b = sign * 2.0 * (net_rad * net_rad_vel - dist * dist_vel);
 
+    // a tangent root only grazes the boundary, which counts as a collision but
+    // not as a separation
     match util::quad_root_ascending(coeff_a, coeff_b, coeff_c) {
-        Some(result) if result >= 0.0 => result,
-        _ => f64::INFINITY,
+        Some(result)
+            if result >= 0.0 && (for_collide || coeff_a * 2.0 * result + coeff_b > 0.0) =>
+        {
+            result
+        }
+        _ => Float::INFINITY,
     }
 }
 
-fn rect_circle_time(rect: &DurHitbox, circle: &DurHitbox, for_collide: bool, duration: f64) -> f64 {
+fn rect_circle_time(
+    rect: &DurHitbox,
+    circle: &DurHitbox,
+    for_collide: bool,
+    duration: Float,
+) -> Float {
     if for_collide {
         rect_circle_collide_time(rect, circle, duration)
     } else {
@@ -116,41 +224,41 @@ fn rect_circle_time(rect: &DurHitbox, circle: &DurHitbox, for_collide: bool, dur
     }
 }
 
-fn rect_circle_collide_time(rect: &DurHitbox, circle: &DurHitbox, duration: f64) -> f64 {
+fn rect_circle_collide_time(rect: &DurHitbox, circle: &DurHitbox, duration: Float) -> Float {
     let base_time = rect_rect_time(rect, circle, true);
-    if base_time >= duration {
-        f64::INFINITY
+    if base_time >= duration || base_time >= core::HIGH_TIME {
+        Float::INFINITY
     } else {
         let mut rect = rect.clone();
-        rect.value = rect.advanced_shape(base_time);
+        rect.advance(base_time);
         let mut circle = circle.clone();
-        circle.value = circle.advanced_shape(base_time);
+        circle.advance(base_time);
 
         base_time + rebased_rect_circle_collide_time(&rect, &circle)
     }
 }
 
-fn rect_circle_separate_time(rect: &DurHitbox, circle: &DurHitbox) -> f64 {
+fn rect_circle_separate_time(rect: &DurHitbox, circle: &DurHitbox) -> Float {
     let base_time = rect_rect_time(rect, circle, false);
     if base_time == 0.0 {
         return 0.0;
     }
     if base_time >= core::HIGH_TIME {
-        return f64::INFINITY;
+        return Float::INFINITY;
     }
 
     let mut rect = rect.clone();
-    rect.value = rect.advanced_shape(base_time);
+    rect.advance(base_time);
     rect.vel = rect.vel.negate();
 
     let mut circle = circle.clone();
-    circle.value = circle.advanced_shape(base_time);
+    circle.advance(base_time);
     circle.vel = circle.vel.negate();
 
     (base_time - rebased_rect_circle_collide_time(&rect, &circle)).max(0.0)
 }
 
-fn rebased_rect_circle_collide_time(rect: &DurHitbox, circle: &DurHitbox) -> f64 {
+fn rebased_rect_circle_collide_time(rect: &DurHitbox, circle: &DurHitbox) -> Float {
     let sector = rect.value.sector(circle.value.pos);
     if sector.is_corner() {
         let mut corner = DurHitbox::new(PlacedShape::new(
@@ -158,8 +266,402 @@ fn rebased_rect_circle_collide_time(rect: &DurHitbox, circle: &DurHitbox) -> f64
             Shape::circle(0.0),
         ));
         corner.vel.value = rect.vel.corner(sector);
+        corner.vel.accel = rect.vel.accel;
         circle_circle_time(&corner, circle, true)
     } else {
         0.0
     }
 }
+
+// When either hitbox is accelerating, or a segment is involved, the overlap
+// between the hitboxes is described by polynomials in time rather than linear
+// functions. The overlapping region is described as a union of sub-regions,
+// where each sub-region is the set of times at which all of its polynomials
+// are positive. The roots of the polynomials are used to split time into
+// intervals where the sign of each polynomial is constant, and the start of
+// the first interval where the hitboxes overlap (or the first interval where
+// they do not overlap, when solving for separation) is returned.
+
+// coefficients in increasing order of degree
+type Poly = [Float; 5];
+
+fn accel_rect_rect_time(a: &DurHitbox, b: &DurHitbox, for_collide: bool) -> Float {
+    let mut region = Vec::with_capacity(4);
+    for &card in &Card::values() {
+        let overlap = a.value.card_overlap(&b.value, card);
+        let overlap_vel = a.vel.card_overlap(&b.vel, card);
+        let overlap_accel = Vec2::from(card) * (b.vel.accel - a.vel.accel);
+        region.push([overlap, overlap_vel, overlap_accel * 0.5, 0.0, 0.0]);
+    }
+    first_sign_change_time(&[region], for_collide)
+}
+
+// A shape overlaps an inverted rect while its bounding box extends past any
+// edge of the rect, so each edge gives a separate region.
+fn inverted_rect_time(inverted: &DurHitbox, other: &DurHitbox, for_collide: bool) -> Float {
+    let regions: Vec<Vec<Poly>> = Card::values()
+        .iter()
+        .map(|&card| {
+            let past = other.value.edge(card) - inverted.value.edge(card);
+            let past_vel = other.vel.edge(card) - inverted.vel.edge(card);
+            let past_accel = Vec2::from(card) * (other.vel.accel - inverted.vel.accel);
+            vec![[past, past_vel, past_accel * 0.5, 0.0, 0.0]]
+        })
+        .collect();
+    first_sign_change_time(&regions, for_collide)
+}
+
+fn accel_circle_circle_time(a: &DurHitbox, b: &DurHitbox, for_collide: bool) -> Float {
+    let net_rad = (a.value.dims().x + b.value.dims().x) * 0.5;
+    let net_rad_vel = (a.vel.resize.x + b.vel.resize.x) * 0.5;
+    let (dist, dist_vel, dist_accel) = relative_motion(a, b);
+    let poly = circle_overlap_poly(net_rad, net_rad_vel, dist, dist_vel, dist_accel);
+    first_sign_change_time(&[vec![poly]], for_collide)
+}
+
+fn segment_circle_time(segment: &DurHitbox, circle: &DurHitbox, for_collide: bool) -> Float {
+    first_sign_change_time(&segment_circle_regions(segment, circle), for_collide)
+}
+
+// the segment is treated as a capsule around the center of the circle, which
+// is the union of two circles at the ends of the segment and a strip along the
+// length of the segment
+fn segment_circle_regions(segment: &DurHitbox, circle: &DurHitbox) -> Vec<Vec<Poly>> {
+    let rad = circle.value.dims().x * 0.5;
+    let rad_vel = circle.vel.resize.x * 0.5;
+    let (dist, dist_vel, dist_accel) = relative_motion(circle, segment);
+    let half = segment.value.dims() * 0.5;
+    let mut regions = vec![
+        vec![circle_overlap_poly(
+            rad,
+            rad_vel,
+            dist - half,
+            dist_vel,
+            dist_accel,
+        )],
+        vec![circle_overlap_poly(
+            rad,
+            rad_vel,
+            dist + half,
+            dist_vel,
+            dist_accel,
+        )],
+    ];
+    if let Some(along) = half.normalize() {
+        let perp = v2(-along.y, along.x);
+        let mut strip = slab_polys(rad, rad_vel, perp, dist, dist_vel, dist_accel).to_vec();
+        strip.extend_from_slice(&slab_polys(
+            half.len(),
+            0.0,
+            along,
+            dist,
+            dist_vel,
+            dist_accel,
+        ));
+        regions.push(strip);
+    }
+    regions
+}
+
+fn segment_rect_time(segment: &DurHitbox, rect: &DurHitbox, for_collide: bool) -> Float {
+    first_sign_change_time(&[segment_rect_region(segment, rect)], for_collide)
+}
+
+// uses the separating axis theorem, with the axes of the rect and the axis
+// perpendicular to the segment
+fn segment_rect_region(segment: &DurHitbox, rect: &DurHitbox) -> Vec<Poly> {
+    let (dist, dist_vel, dist_accel) = relative_motion(rect, segment);
+    let seg_dims = segment.value.dims();
+    let rect_dims = rect.value.dims();
+    let rect_resize = rect.vel.resize;
+    let mut axes = vec![v2(1.0, 0.0), v2(0.0, 1.0)];
+    if let Some(along) = seg_dims.normalize() {
+        axes.push(v2(-along.y, along.x));
+    }
+    let mut region = Vec::new();
+    for axis in axes {
+        let abs_axis = axis.abs();
+        let half_extent = (abs_axis * rect_dims + (seg_dims * axis).abs()) * 0.5;
+        let half_extent_vel = abs_axis * rect_resize * 0.5;
+        region.extend_from_slice(&slab_polys(
+            half_extent,
+            half_extent_vel,
+            axis,
+            dist,
+            dist_vel,
+            dist_accel,
+        ));
+    }
+    region
+}
+
+// A capsule overlaps another shape while the core segment of the capsule is
+// within the capsule radius of that shape, so the other shape is inflated by
+// the radius and tested against the core segment.
+fn capsule_time(capsule: &DurHitbox, other: &DurHitbox, for_collide: bool) -> Float {
+    let (core, rad) = capsule_core(capsule);
+    let regions = match other.value.kind() {
+        ShapeKind::Circle | ShapeKind::Point => {
+            let mut circle = other.clone();
+            circle.value.shape = Shape::circle(other.value.dims().x + rad * 2.0);
+            segment_circle_regions(&core, &circle)
+        }
+        ShapeKind::Rect => segment_rounded_rect_regions(&core, other, rad),
+        ShapeKind::Segment => segment_segment_regions(&core, other, rad),
+        ShapeKind::Capsule => {
+            let (other_core, other_rad) = capsule_core(other);
+            segment_segment_regions(&core, &other_core, rad + other_rad)
+        }
+        ShapeKind::Triangle | ShapeKind::OrientedRect => {
+            segment_rounded_convex_regions(&core, other, rad)
+        }
+        ShapeKind::InvertedRect => panic!("inverted rects are solved separately"),
+    };
+    first_sign_change_time(&regions, for_collide)
+}
+
+fn capsule_core(capsule: &DurHitbox) -> (DurHitbox, Float) {
+    let (core, rad) = capsule.value.capsule_core();
+    let mut result = capsule.clone();
+    result.value = core;
+    (result, rad)
+}
+
+// the rect inflated by `rad` with rounded corners is the union of the rect
+// widened by `rad`, the rect heightened by `rad`, and circles at the corners
+fn segment_rounded_rect_regions(
+    segment: &DurHitbox,
+    rect: &DurHitbox,
+    rad: Float,
+) -> Vec<Vec<Poly>> {
+    let mut regions = Vec::new();
+    for &grow in &[v2(rad * 2.0, 0.0), v2(0.0, rad * 2.0)] {
+        let mut grown = rect.clone();
+        grown.value.shape = Shape::rect(rect.value.dims() + grow);
+        regions.push(segment_rect_region(segment, &grown));
+    }
+    for &x in &[Ordering::Less, Ordering::Greater] {
+        for &y in &[Ordering::Less, Ordering::Greater] {
+            let sector = Sector::new(x, y);
+            let mut corner = DurHitbox::new(PlacedShape::new(
+                rect.value.corner(sector),
+                Shape::circle(rad * 2.0),
+            ));
+            corner.vel.value = rect.vel.corner(sector);
+            corner.vel.accel = rect.vel.accel;
+            regions.extend(segment_circle_regions(segment, &corner));
+        }
+    }
+    regions
+}
+
+// the triangle or oriented rect inflated by `rad` is the union of the shape
+// and the areas within `rad` of each of its edges
+fn segment_rounded_convex_regions(
+    segment: &DurHitbox,
+    convex: &DurHitbox,
+    rad: Float,
+) -> Vec<Vec<Poly>> {
+    let mut regions = vec![convex_poly_region(convex, segment)];
+    for edge in convex_edges(convex) {
+        regions.extend(segment_segment_regions(segment, &edge, rad));
+    }
+    regions
+}
+
+// A circle overlaps a triangle or oriented rect while its center is inside the
+// shape or within its radius of one of the edges of the shape.
+fn convex_circle_time(convex: &DurHitbox, circle: &DurHitbox, for_collide: bool) -> Float {
+    // the center is inside the shape while its projection onto each edge normal
+    // is within the projection of the shape
+    let (dist, dist_vel, dist_accel) = relative_motion(circle, convex);
+    let mut inside = Vec::new();
+    for axis in convex.value.convex_axes() {
+        let (lo, hi) = relative_extent(&convex.value, axis);
+        let (pos, vel, accel) = (axis * dist, axis * dist_vel, axis * dist_accel);
+        inside.push([hi - pos, -vel, -accel, 0.0, 0.0]);
+        inside.push([pos - lo, vel, accel, 0.0, 0.0]);
+    }
+    let mut regions = vec![inside];
+    for edge in convex_edges(convex) {
+        regions.extend(segment_circle_regions(&edge, circle));
+    }
+    first_sign_change_time(&regions, for_collide)
+}
+
+// returns the edges of a triangle or oriented rect as segments moving with the
+// shape
+fn convex_edges(convex: &DurHitbox) -> Vec<DurHitbox> {
+    let vertices = convex.value.convex_vertices();
+    (0..vertices.len())
+        .map(|index| {
+            let (start, end) = (vertices[index], vertices[(index + 1) % vertices.len()]);
+            let mut edge = convex.clone();
+            edge.value = PlacedShape::new(start.lerp(end, 0.5), Shape::segment(end - start));
+            edge
+        })
+        .collect()
+}
+
+fn convex_poly_time(convex: &DurHitbox, other: &DurHitbox, for_collide: bool) -> Float {
+    first_sign_change_time(&[convex_poly_region(convex, other)], for_collide)
+}
+
+// uses the separating axis theorem between a triangle or oriented rect and a
+// rect, segment, triangle, or oriented rect, with the axes perpendicular to
+// the edges of both shapes
+fn convex_poly_region(convex: &DurHitbox, other: &DurHitbox) -> Vec<Poly> {
+    let (dist, dist_vel, dist_accel) = relative_motion(other, convex);
+    let mut axes = convex.value.convex_axes().to_vec();
+    let other_axes = match other.value.kind() {
+        ShapeKind::Rect => vec![v2(1.0, 0.0), v2(0.0, 1.0)],
+        ShapeKind::Triangle | ShapeKind::OrientedRect => other.value.convex_axes().to_vec(),
+        ShapeKind::Segment => other
+            .value
+            .dims()
+            .normalize()
+            .map(|along| v2(-along.y, along.x))
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    };
+    for axis in other_axes {
+        if !axes.contains(&axis) {
+            axes.push(axis);
+        }
+    }
+    let mut region = Vec::new();
+    for axis in axes {
+        // projections relative to the position of each shape
+        let (lo, hi) = relative_extent(&convex.value, axis);
+        let (other_lo, other_hi) = relative_extent(&other.value, axis);
+        let other_half_vel = if other.value.kind() == ShapeKind::Rect {
+            axis.abs() * other.vel.resize * 0.5
+        } else {
+            0.0
+        };
+        // the projection of `other` overlaps the projection of the convex shape
+        // while `lo - other_hi < pos < hi - other_lo`
+        let (pos, vel, accel) = (axis * dist, axis * dist_vel, axis * dist_accel);
+        region.push([hi - other_lo - pos, other_half_vel - vel, -accel, 0.0, 0.0]);
+        region.push([pos - lo + other_hi, other_half_vel + vel, accel, 0.0, 0.0]);
+    }
+    region
+}
+
+// returns the range of the projection of a rect, segment, triangle, or oriented
+// rect onto `axis`, relative to the position of the shape
+fn relative_extent(shape: &PlacedShape, axis: Vec2) -> (Float, Float) {
+    if shape.kind().is_convex_poly() {
+        let (min, max) = shape.convex_extent(axis);
+        let center = shape.pos * axis;
+        (min - center, max - center)
+    } else {
+        let half_extent = if shape.kind() == ShapeKind::Segment {
+            (shape.dims() * axis).abs() * 0.5
+        } else {
+            (axis.x.abs() * shape.dims().x + axis.y.abs() * shape.dims().y) * 0.5
+        };
+        (-half_extent, half_extent)
+    }
+}
+
+// two segments are within `rad` of each other if they cross, or if an end of
+// either segment is within `rad` of the other segment
+fn segment_segment_regions(a: &DurHitbox, b: &DurHitbox, rad: Float) -> Vec<Vec<Poly>> {
+    let mut regions = Vec::new();
+    for &(segment, other) in &[(a, b), (b, a)] {
+        let half = other.value.dims() * 0.5;
+        for &end in &[other.value.pos - half, other.value.pos + half] {
+            let mut circle = other.clone();
+            circle.value = PlacedShape::new(end, Shape::circle(rad * 2.0));
+            regions.extend(segment_circle_regions(segment, &circle));
+        }
+    }
+    let (dist, dist_vel, dist_accel) = relative_motion(a, b);
+    let mut crossing = Vec::new();
+    for segment in &[a, b] {
+        if let Some(along) = segment.value.dims().normalize() {
+            let axis = v2(-along.y, along.x);
+            let half_extent = ((a.value.dims() * axis).abs() + (b.value.dims() * axis).abs()) * 0.5;
+            crossing.extend_from_slice(&slab_polys(
+                half_extent,
+                0.0,
+                axis,
+                dist,
+                dist_vel,
+                dist_accel,
+            ));
+        }
+    }
+    if crossing.len() == 4 {
+        regions.push(crossing);
+    }
+    regions
+}
+
+// returns the position, velocity, and half of the acceleration of `a` relative
+// to `b`
+fn relative_motion(a: &DurHitbox, b: &DurHitbox) -> (Vec2, Vec2, Vec2) {
+    (
+        a.value.pos - b.value.pos,
+        a.vel.value - b.vel.value,
+        (a.vel.accel - b.vel.accel) * 0.5,
+    )
+}
+
+// net_rad(t)^2 - |dist(t)|^2, which is positive while the circles overlap
+fn circle_overlap_poly(
+    net_rad: Float,
+    net_rad_vel: Float,
+    dist: Vec2,
+    dist_vel: Vec2,
+    dist_accel: Vec2,
+) -> Poly {
+    [
+        net_rad * net_rad - dist.len_sq(),
+        2.0 * (net_rad * net_rad_vel - dist * dist_vel),
+        net_rad_vel * net_rad_vel - dist_vel.len_sq() - 2.0 * (dist * dist_accel),
+        -2.0 * (dist_vel * dist_accel),
+        -dist_accel.len_sq(),
+    ]
+}
+
+// polynomials that are both positive while the projection of `dist(t)` onto
+// `axis` is strictly between `-half_width(t)` and `half_width(t)`
+fn slab_polys(
+    half_width: Float,
+    half_width_vel: Float,
+    axis: Vec2,
+    dist: Vec2,
+    dist_vel: Vec2,
+    dist_accel: Vec2,
+) -> [Poly; 2] {
+    let (pos, vel, accel) = (axis * dist, axis * dist_vel, axis * dist_accel);
+    [
+        [half_width - pos, half_width_vel - vel, -accel, 0.0, 0.0],
+        [half_width + pos, half_width_vel + vel, accel, 0.0, 0.0],
+    ]
+}
+
+fn first_sign_change_time(regions: &[Vec<Poly>], for_collide: bool) -> Float {
+    let mut times = vec![0.0];
+    for poly in regions.iter().flat_map(|region| region.iter()) {
+        times.extend(util::poly_roots_within(poly, core::HIGH_TIME));
+    }
+    times.sort_by_key(|&time| n64(time));
+    times.dedup();
+    for (index, &time) in times.iter().enumerate() {
+        let next_time = times.get(index + 1).cloned().unwrap_or(time * 2.0 + 1.0);
+        let mid_time = (time + next_time) * 0.5;
+        let overlapping = regions.iter().any(|region| {
+            region
+                .iter()
+                .all(|poly| util::poly_eval(poly, mid_time) > 0.0)
+        });
+        if overlapping == for_collide {
+            return time;
+        }
+    }
+    Float::INFINITY
+}
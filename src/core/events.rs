@@ -13,11 +13,12 @@
 // limitations under the License.
 
 use core::{HbId, HIGH_TIME};
-use float::n64;
+use float::{n64, Float};
+use fnv::FnvHashSet;
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
-use std::f64;
 use std::hash::{Hash, Hasher};
+use std::mem;
 use util::{OneOrTwo, TightSet};
 
 // This module contains Collider events that are queued to occur at given
@@ -27,14 +28,25 @@ const PAIR_BASE: u64 = 0x8000_0000_0000_0000;
 
 #[derive(Copy, Clone)]
 pub struct EventKey {
-    time: f64,
+    time: Float,
+    // the ordered ids of the hitboxes involved in the event if ties are
+    // deterministic, and zero otherwise
+    tie: (HbId, HbId),
     index: u64,
 }
 
 impl EventKey {
-    fn time(&self) -> f64 {
+    fn time(&self) -> Float {
         self.time
     }
+
+    pub fn rebased(self, offset: Float) -> EventKey {
+        EventKey {
+            time: self.time - offset,
+            tie: self.tie,
+            index: self.index,
+        }
+    }
 }
 
 impl PartialEq for EventKey {
@@ -60,7 +72,11 @@ impl PartialOrd for EventKey {
 impl Ord for EventKey {
     fn cmp(&self, other: &Self) -> Ordering {
         if self.time == other.time {
-            self.index.cmp(&other.index)
+            // solitaire events still come before pair events, as they would
+            // when ordering by index alone
+            let is_pair = self.index >= PAIR_BASE;
+            let other_is_pair = other.index >= PAIR_BASE;
+            (is_pair, self.tie, self.index).cmp(&(other_is_pair, other.tie, other.index))
         } else {
             n64(self.time).cmp(&n64(other.time))
         }
@@ -73,11 +89,11 @@ pub trait EventKeysMap {
 
 #[derive(Copy, Clone)]
 pub enum InternalEvent {
-    #[cfg(debug_assertions)]
     PanicSmallHitbox(HbId),
-    #[cfg(debug_assertions)]
     PanicDurationPassed(HbId),
     Reiterate(HbId),
+    Expire(HbId),
+    ExitBounds(HbId),
     Collide(HbId, HbId),
     Separate(HbId, HbId),
 }
@@ -89,36 +105,40 @@ impl InternalEvent {
 
     fn involved_hitbox_ids(self) -> OneOrTwo<HbId> {
         match self {
-            #[cfg(debug_assertions)]
             InternalEvent::PanicSmallHitbox(id) | InternalEvent::PanicDurationPassed(id) => {
                 OneOrTwo::One(id)
             }
-            InternalEvent::Reiterate(id) => OneOrTwo::One(id),
+            InternalEvent::Reiterate(id)
+            | InternalEvent::Expire(id)
+            | InternalEvent::ExitBounds(id) => OneOrTwo::One(id),
             InternalEvent::Collide(a, b) | InternalEvent::Separate(a, b) => OneOrTwo::Two(a, b),
         }
     }
 }
 
+#[derive(Clone)]
 pub struct EventManager {
     events: BTreeMap<EventKey, InternalEvent>,
     next_event_index: u64,
+    deterministic_ties: bool,
 }
 
 impl EventManager {
-    pub fn new() -> EventManager {
+    pub fn new(deterministic_ties: bool) -> EventManager {
         EventManager {
             events: BTreeMap::new(),
             next_event_index: 0,
+            deterministic_ties,
         }
     }
 
     pub fn add_solitaire_event(
         &mut self,
-        time: f64,
+        time: Float,
         event: InternalEvent,
         key_set: &mut TightSet<EventKey>,
     ) {
-        if let Some(key) = self.new_event_key(time, false) {
+        if let Some(key) = self.new_event_key(time, event) {
             assert!(self.events.insert(key, event).is_none());
             assert!(key_set.insert(key));
         }
@@ -126,12 +146,12 @@ impl EventManager {
 
     pub fn add_pair_event(
         &mut self,
-        time: f64,
+        time: Float,
         event: InternalEvent,
         first_key_set: &mut TightSet<EventKey>,
         second_key_set: &mut TightSet<EventKey>,
     ) {
-        if let Some(key) = self.new_event_key(time, true) {
+        if let Some(key) = self.new_event_key(time, event) {
             assert!(self.events.insert(key, event).is_none());
             assert!(first_key_set.insert(key));
             assert!(second_key_set.insert(key));
@@ -153,26 +173,69 @@ impl EventManager {
         key_set.clear();
     }
 
-    fn new_event_key(&mut self, time: f64, for_pair: bool) -> Option<EventKey> {
+    // like `clear_related_events`, but for one of several hitboxes with `removed_ids`
+    // that are being removed at once, which are not present in `map`
+    pub fn clear_removed_events<M: EventKeysMap>(
+        &mut self,
+        key_set: &mut TightSet<EventKey>,
+        removed_ids: &FnvHashSet<HbId>,
+        map: &mut M,
+    ) {
+        for key in key_set.iter() {
+            // an event between two removed hitboxes may already be cleared
+            if let Some(event) = self.events.remove(key) {
+                for id in event.involved_hitbox_ids().iter() {
+                    if !removed_ids.contains(&id) {
+                        assert!(map.event_keys_mut(id).remove(key));
+                    }
+                }
+            }
+        }
+        key_set.clear();
+    }
+
+    fn new_event_key(&mut self, time: Float, event: InternalEvent) -> Option<EventKey> {
         if time >= HIGH_TIME {
             None
         } else {
             let mut index = self.next_event_index;
             self.next_event_index += 1;
             assert!(index < PAIR_BASE);
-            if for_pair {
+            let ids = event.involved_hitbox_ids();
+            if let OneOrTwo::Two(..) = ids {
                 index += PAIR_BASE;
             }
-            let result = EventKey { time, index };
+            let tie = match ids {
+                _ if !self.deterministic_ties => (0, 0),
+                OneOrTwo::One(id) => (id, id),
+                OneOrTwo::Two(id_1, id_2) => (id_1.min(id_2), id_1.max(id_2)),
+            };
+            let result = EventKey { time, tie, index };
             Some(result)
         }
     }
 
-    pub fn peek_time(&self) -> f64 {
-        self.peek_key().map_or(f64::INFINITY, |key| key.time())
+    // subtracts `offset` from the time of every event; since the keys are
+    // ordered by time, the map is rebuilt rather than modified in place
+    pub fn rebase(&mut self, offset: Float) {
+        self.events = mem::take(&mut self.events)
+            .into_iter()
+            .map(|(key, event)| (key.rebased(offset), event))
+            .collect();
     }
 
-    pub fn next<M: EventKeysMap>(&mut self, time: f64, map: &mut M) -> Option<InternalEvent> {
+    pub fn peek_time(&self) -> Float {
+        self.peek_key().map_or(Float::INFINITY, |key| key.time())
+    }
+
+    pub fn peek(&self) -> Option<(Float, InternalEvent)> {
+        self.events
+            .iter()
+            .next()
+            .map(|(key, &event)| (key.time(), event))
+    }
+
+    pub fn next<M: EventKeysMap>(&mut self, time: Float, map: &mut M) -> Option<InternalEvent> {
         if let Some(key) = self.peek_key() {
             if key.time() == time {
                 let event = self.events.remove(&key).unwrap();
@@ -188,6 +251,14 @@ impl EventManager {
         }
     }
 
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Float, InternalEvent)> + '_ {
+        self.events.iter().map(|(key, &event)| (key.time(), event))
+    }
+
     fn peek_key(&self) -> Option<EventKey> {
         self.events.keys().next().map(|&key| key)
     }
@@ -13,13 +13,14 @@
 // limitations under the License.
 
 use core::dur_hitbox::DurHitbox;
-use core::{HbGroup, HbId, Hitbox};
+use core::{HbGroup, HbId, HbProfile, Hitbox};
+use float::{n64, Float};
 use fnv::{FnvHashMap, FnvHashSet};
-use geom::shape::{PlacedBounds, PlacedShape};
+use geom::shape::{PlacedBounds, ShapeKind};
+use geom::{v2, Aabb, Card, Vec2};
 use index_rect::IndexRect;
 use std::cmp;
 use std::collections::hash_map;
-use std::f64;
 use util::TightSet;
 
 // Grid is a sparse 2D grid implemented as a HashMap. This is used as the
@@ -33,6 +34,51 @@ struct GridKey {
     group: HbGroup,
 }
 
+// the groups that a hitbox interacts with, given by either `interact_groups` or
+// `interact_mask`
+#[derive(Copy, Clone)]
+pub enum GroupSet {
+    List(&'static [HbGroup]),
+    Mask(u64),
+}
+
+impl GroupSet {
+    pub fn of<P: HbProfile>(profile: &P) -> GroupSet {
+        match profile.interact_mask() {
+            Some(mask) => GroupSet::Mask(mask),
+            None => GroupSet::List(profile.interact_groups()),
+        }
+    }
+
+    pub fn empty() -> GroupSet {
+        GroupSet::List(&[])
+    }
+
+    pub fn contains(self, group: HbGroup) -> bool {
+        match self {
+            GroupSet::List(groups) => groups.contains(&group),
+            GroupSet::Mask(mask) => group < 64 && mask & (1 << group) != 0,
+        }
+    }
+
+    pub fn is_empty(self) -> bool {
+        match self {
+            GroupSet::List(groups) => groups.is_empty(),
+            GroupSet::Mask(mask) => mask == 0,
+        }
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = HbGroup> {
+        let (list, mask) = match self {
+            GroupSet::List(groups) => (groups, 0),
+            GroupSet::Mask(mask) => (&[][..], mask),
+        };
+        list.iter()
+            .cloned()
+            .chain((0..64).filter(move |&group| mask & (1 << group) != 0))
+    }
+}
+
 #[derive(Copy, Clone)]
 struct GridArea {
     rect: IndexRect,
@@ -45,35 +91,521 @@ impl GridArea {
     }
 }
 
+/// Statistics about how hitboxes are distributed in the grid cells of a
+/// `Collider`, useful for choosing a `cell_width`.
+///
+/// A hitbox is counted in every cell that it may occupy before its next update,
+/// so moving hitboxes are counted in more cells than they currently overlap.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct GridStats {
+    /// The number of cells that contain at least one hitbox.
+    pub occupied_cells: usize,
+    /// The largest number of hitboxes in a single cell.
+    pub max_hitboxes_per_cell: usize,
+    /// The mean number of hitboxes per occupied cell, or `0.0` if no cells are
+    /// occupied.
+    pub mean_hitboxes_per_cell: Float,
+}
+
+#[derive(Clone)]
 pub struct Grid {
     map: FnvHashMap<GridKey, TightSet<HbId>>,
-    cell_width: f64,
+    cell_dims: Vec2,
+    // cell dims of groups that do not use the default `cell_dims`
+    group_cell_dims: FnvHashMap<HbGroup, Vec2>,
+    // whether each changed cell was occupied before it was first changed since
+    // the last call to take_cell_changes, or `None` if changes are not tracked
+    cell_changes: Option<FnvHashMap<GridKey, bool>>,
+    // inverted rects overlap everything outside of a rectangle, so rather than
+    // occupying cells they are kept here along with their group and the box
+    // that their rectangle covers throughout their duration
+    inverted: FnvHashMap<HbId, (HbGroup, Aabb)>,
+    // the number of occupied cells of each group, along with a rect enclosing
+    // every cell of the group that has been occupied since the group was last
+    // empty
+    occupied: FnvHashMap<HbGroup, (usize, IndexRect)>,
 }
 
 impl Grid {
-    pub fn new(cell_width: f64) -> Grid {
+    pub fn new(cell_dims: Vec2) -> Grid {
         Grid {
             map: FnvHashMap::default(),
-            cell_width,
+            cell_dims,
+            group_cell_dims: FnvHashMap::default(),
+            cell_changes: None,
+            inverted: FnvHashMap::default(),
+            occupied: FnvHashMap::default(),
         }
     }
 
-    pub fn cell_period(&self, hitbox: &Hitbox, has_group: bool) -> f64 {
-        if has_group {
-            let speed = hitbox.vel.max_edge();
-            if speed <= 0.0 {
-                f64::INFINITY
+    // assumes that most hitboxes are smaller than a cell and share cells with
+    // few others, so each hitbox adds about one grid entry
+    pub fn reserve(&mut self, hitboxes: usize) {
+        self.map.reserve(hitboxes);
+    }
+
+    pub fn set_cell_change_tracking(&mut self, enabled: bool) {
+        if enabled != self.cell_changes.is_some() {
+            self.cell_changes = if enabled {
+                Some(FnvHashMap::default())
             } else {
-                self.cell_width / speed
-            }
+                None
+            };
+        }
+    }
+
+    pub fn take_cell_changes(&mut self) -> Vec<(HbGroup, i32, i32, bool)> {
+        let map = &self.map;
+        let cell_changes = match self.cell_changes {
+            Some(ref mut cell_changes) => cell_changes,
+            None => return Vec::new(),
+        };
+        let mut result: Vec<(HbGroup, i32, i32, bool)> = cell_changes
+            .drain()
+            .filter_map(|(key, was_occupied)| {
+                let occupied = map.contains_key(&key);
+                if occupied != was_occupied {
+                    Some((key.group, key.coord.0, key.coord.1, occupied))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        result.sort();
+        result
+    }
+
+    pub fn stats(&self) -> GridStats {
+        let mut cell_counts: FnvHashMap<(i32, i32), usize> = FnvHashMap::default();
+        for (key, ids) in &self.map {
+            *cell_counts.entry(key.coord).or_insert(0) += ids.len();
+        }
+        let total: usize = cell_counts.values().sum();
+        let occupied_cells = cell_counts.len();
+        GridStats {
+            occupied_cells,
+            max_hitboxes_per_cell: cell_counts.values().cloned().max().unwrap_or(0),
+            mean_hitboxes_per_cell: if occupied_cells == 0 {
+                0.0
+            } else {
+                total as Float / occupied_cells as Float
+            },
+        }
+    }
+
+    // the cell dims of groups may only be changed while the groups have no
+    // hitboxes in the grid
+    pub fn cell_dims(&self) -> Vec2 {
+        self.cell_dims
+    }
+
+    pub fn cell_of(&self, point: Vec2) -> (i32, i32) {
+        Grid::cell_coord(point, self.cell_dims)
+    }
+
+    // uses the same rounding as the start of `index_bounds`
+    fn cell_coord(point: Vec2, dims: Vec2) -> (i32, i32) {
+        (
+            (point.x / dims.x).floor() as i32,
+            (point.y / dims.y).floor() as i32,
+        )
+    }
+
+    pub fn set_group_cell_dims(&mut self, group: HbGroup, cell_dims: Vec2) {
+        self.group_cell_dims.insert(group, cell_dims);
+    }
+
+    pub fn group_cell_dims(&self, group: HbGroup) -> Vec2 {
+        self.group_cell_dims
+            .get(&group)
+            .cloned()
+            .unwrap_or(self.cell_dims)
+    }
+
+    // returns the smallest cell side among `groups`, or the smallest side of
+    // the default cell dims if `groups` is empty
+    pub fn min_cell_side(&self, groups: GroupSet) -> Float {
+        groups
+            .iter()
+            .map(|group| self.group_cell_dims(group))
+            .fold(None, |min: Option<Float>, dims| {
+                let side = dims.x.min(dims.y);
+                Some(min.map_or(side, |min| min.min(side)))
+            })
+            .unwrap_or(self.cell_dims.x.min(self.cell_dims.y))
+    }
+
+    // the time until the hitbox may leave the cells it is registered in, which
+    // is the shorter of the times to traverse a cell along either axis
+    pub fn cell_period(&self, hitbox: &Hitbox, group: Option<HbGroup>) -> Float {
+        if let Some(group) = group {
+            let dims = self.group_cell_dims(group);
+            let vel = &hitbox.vel;
+            let speed_x = vel
+                .edge(Card::PlusX)
+                .abs()
+                .max(vel.edge(Card::MinusX).abs());
+            let speed_y = vel
+                .edge(Card::PlusY)
+                .abs()
+                .max(vel.edge(Card::MinusY).abs());
+            let time_x = Grid::traversal_time(dims.x, speed_x, vel.accel.x.abs());
+            let time_y = Grid::traversal_time(dims.y, speed_y, vel.accel.y.abs());
+            time_x.min(time_y)
         } else {
-            f64::INFINITY
+            Float::INFINITY
+        }
+    }
+
+    fn traversal_time(width: Float, speed: Float, accel: Float) -> Float {
+        if accel > 0.0 {
+            // solves speed * t + 0.5 * accel * t^2 = width for t
+            width * 2.0 / (speed + (speed * speed + accel * width * 2.0).sqrt())
+        } else if speed <= 0.0 {
+            Float::INFINITY
+        } else {
+            width / speed
+        }
+    }
+
+    pub fn aabb_cellmates(&self, bounds: &Aabb, groups: GroupSet) -> FnvHashSet<HbId> {
+        let mut result = self.overlapping_ids(None, bounds, groups);
+        self.for_each_inverted_mate(None, bounds, groups, |id| {
+            result.insert(id);
+        });
+        result
+    }
+
+    // calls `f` for each inverted rect in `groups` whose covered box does not
+    // strictly contain `bounds`, other than `hitbox_id`
+    fn for_each_inverted_mate(
+        &self,
+        hitbox_id: Option<HbId>,
+        bounds: &Aabb,
+        groups: GroupSet,
+        mut f: impl FnMut(HbId),
+    ) {
+        for (&id, &(group, ref interior)) in &self.inverted {
+            if Some(id) != hitbox_id
+                && groups.contains(group)
+                && !Grid::strictly_contains(interior, bounds)
+            {
+                f(id);
+            }
+        }
+    }
+
+    // calls `f` for each hitbox in `groups` that an inverted rect with the
+    // given covered box may overlap, which includes every hitbox in a cell that
+    // is not strictly inside the box, as well as every other inverted rect
+    fn for_each_inverted_rect_mate(
+        &self,
+        hitbox_id: HbId,
+        interior: &Aabb,
+        groups: GroupSet,
+        mut f: impl FnMut(HbId),
+    ) {
+        let mut found = FnvHashSet::default();
+        for group in groups.iter() {
+            let rect = match self.occupied.get(&group) {
+                Some(&(_, rect)) => rect,
+                None => continue,
+            };
+            let dims = self.group_cell_dims(group);
+            // the cells of `inner` lie strictly inside the box, so only the
+            // rest of the occupied rect is visited rather than the whole map
+            let inner = Grid::index_bounds(interior, dims).shrunk();
+            let mut visit = |coord: (i32, i32)| {
+                let min = v2(coord.0 as Float * dims.x, coord.1 as Float * dims.y);
+                if !Grid::strictly_contains(interior, &Aabb::new(min, min + dims)) {
+                    if let Some(ids) = self.map.get(&GridKey { coord, group }) {
+                        found.extend(ids.iter().cloned());
+                    }
+                }
+            };
+            for x in rect.start().0..rect.end().0 {
+                match inner {
+                    Some(inner) if x >= inner.start().0 && x < inner.end().0 => {
+                        let (start_y, end_y) = (rect.start().1, rect.end().1);
+                        for y in start_y..inner.start().1.max(start_y).min(end_y) {
+                            visit((x, y));
+                        }
+                        for y in inner.end().1.min(end_y).max(start_y)..end_y {
+                            visit((x, y));
+                        }
+                    }
+                    _ => {
+                        for y in rect.start().1..rect.end().1 {
+                            visit((x, y));
+                        }
+                    }
+                }
+            }
+        }
+        for (&id, &(group, _)) in &self.inverted {
+            if id != hitbox_id && groups.contains(group) {
+                found.insert(id);
+            }
+        }
+        for id in found {
+            f(id);
+        }
+    }
+
+    fn strictly_contains(outer: &Aabb, inner: &Aabb) -> bool {
+        inner.min.x > outer.min.x
+            && inner.min.y > outer.min.y
+            && inner.max.x < outer.max.x
+            && inner.max.y < outer.max.y
+    }
+
+    // calls `f` once for each id in the cells overlapping `bounds`, without
+    // allocating a set to remove duplicates; instead, `bounds_of` must return
+    // the bounds that each hitbox was last added to the grid with, and each id
+    // is only reported in the first cell that its area shares with `bounds`
+    pub fn for_each_aabb_cellmate(
+        &self,
+        bounds: &Aabb,
+        groups: GroupSet,
+        mut bounds_of: impl FnMut(HbId) -> Aabb,
+        mut f: impl FnMut(HbId),
+    ) {
+        for group in groups.iter() {
+            self.for_each_group_cellmate(bounds, group, &mut bounds_of, &mut f);
+        }
+        self.for_each_inverted_mate(None, bounds, groups, f);
+    }
+
+    fn for_each_group_cellmate(
+        &self,
+        bounds: &Aabb,
+        group: HbGroup,
+        bounds_of: &mut impl FnMut(HbId) -> Aabb,
+        f: &mut impl FnMut(HbId),
+    ) {
+        let dims = self.group_cell_dims(group);
+        let rect = Grid::index_bounds(bounds, dims);
+        for coord in rect.iter() {
+            if let Some(ids) = self.map.get(&GridKey { coord, group }) {
+                for &id in ids.iter() {
+                    let area = Grid::index_bounds(&bounds_of(id), dims);
+                    if rect.shared_start(area) == coord {
+                        f(id);
+                    }
+                }
+            }
+        }
+    }
+
+    // finds the cellmates of a batch of hitboxes that are already in the grid,
+    // calling `f(index, id)` once for each hitbox `id` that shares a cell with
+    // `hitboxes[index]` in one of its interact groups. Each occupied cell is
+    // visited once for the whole batch, so a cell lookup is shared by all of
+    // the batch hitboxes in that cell. Duplicates are skipped in the same way
+    // as in `for_each_aabb_cellmate`.
+    pub fn batch_cellmates(
+        &self,
+        hitboxes: &[(HbId, HbGroup, GroupSet, Aabb)],
+        mut bounds_of: impl FnMut(HbId) -> Aabb,
+        mut f: impl FnMut(usize, HbId),
+    ) {
+        let mut cells: FnvHashMap<GridKey, Vec<usize>> = FnvHashMap::default();
+        for (index, &(id, group, groups, ref bounds)) in hitboxes.iter().enumerate() {
+            if let Some((_, interior)) = self.inverted.get(&id) {
+                self.for_each_inverted_rect_mate(id, interior, groups, |other_id| {
+                    f(index, other_id)
+                });
+                continue;
+            }
+            self.for_each_inverted_mate(Some(id), bounds, groups, |other_id| f(index, other_id));
+            for coord in Grid::index_bounds(bounds, self.group_cell_dims(group)).iter() {
+                cells
+                    .entry(GridKey { coord, group })
+                    .or_default()
+                    .push(index);
+            }
+        }
+        let mut lookups = Vec::new();
+        for (key, indices) in &cells {
+            let dims = self.group_cell_dims(key.group);
+            lookups.clear();
+            for &index in indices {
+                let (id, _, groups, ref bounds) = hitboxes[index];
+                let rect = Grid::index_bounds(bounds, dims);
+                for other_group in groups.iter() {
+                    if self.group_cell_dims(other_group) != dims {
+                        // cells of a different size do not line up with this
+                        // cell, so they are searched once from the first cell
+                        if key.coord == rect.start() {
+                            self.for_each_group_cellmate(
+                                bounds,
+                                other_group,
+                                &mut bounds_of,
+                                &mut |other_id| {
+                                    if other_id != id {
+                                        f(index, other_id);
+                                    }
+                                },
+                            );
+                        }
+                        continue;
+                    }
+                    let other_ids = match lookups.iter().find(|&&(group, _)| group == other_group) {
+                        Some(&(_, other_ids)) => other_ids,
+                        None => {
+                            let other_key = GridKey {
+                                coord: key.coord,
+                                group: other_group,
+                            };
+                            let other_ids = self.map.get(&other_key);
+                            lookups.push((other_group, other_ids));
+                            other_ids
+                        }
+                    };
+                    for &other_id in other_ids.into_iter().flat_map(|ids| ids.iter()) {
+                        let area = Grid::index_bounds(&bounds_of(other_id), dims);
+                        if other_id != id && rect.shared_start(area) == key.coord {
+                            f(index, other_id);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // a point on a cell boundary is looked up in the cells on both sides of the
+    // boundary, so that hitboxes with an edge on the boundary are found
+    pub fn point_cellmates(&self, point: Vec2, groups: GroupSet) -> FnvHashSet<HbId> {
+        let mut result = FnvHashSet::default();
+        for group in groups.iter() {
+            let dims = self.group_cell_dims(group);
+            let start_x = (point.x / dims.x).ceil() as i32 - 1;
+            let start_y = (point.y / dims.y).ceil() as i32 - 1;
+            let end_x = (point.x / dims.x).floor() as i32 + 1;
+            let end_y = (point.y / dims.y).floor() as i32 + 1;
+            let rect = IndexRect::new((start_x, start_y), (end_x, end_y));
+            self.add_group_ids(&mut result, None, rect, group);
+        }
+        self.for_each_inverted_mate(None, &Aabb::new(point, point), groups, |id| {
+            result.insert(id);
+        });
+        result
+    }
+
+    // returns the ids in the ring of cells at the given Chebyshev distance from
+    // the cell containing `point`, in each group's own cells; hitboxes found
+    // only in rings beyond `ring` are at least `ring * min_cell_side(groups)`
+    // away from `point`
+    pub fn ring_cellmates(&self, point: Vec2, ring: i32, groups: GroupSet) -> FnvHashSet<HbId> {
+        let mut result = FnvHashSet::default();
+        for group in groups.iter() {
+            let center = Grid::cell_coord(point, self.group_cell_dims(group));
+            let mut coords = Vec::new();
+            if ring == 0 {
+                coords.push(center);
+            } else {
+                for offset in -ring..=ring {
+                    coords.push((center.0 + offset, center.1 - ring));
+                    coords.push((center.0 + offset, center.1 + ring));
+                }
+                for offset in (1 - ring)..ring {
+                    coords.push((center.0 - ring, center.1 + offset));
+                    coords.push((center.0 + ring, center.1 + offset));
+                }
+            }
+            for coord in coords {
+                if let Some(ids) = self.map.get(&GridKey { coord, group }) {
+                    result.extend(ids.iter().cloned());
+                }
+            }
         }
+        if ring == 0 {
+            // an inverted rect may be near any cell, so it is returned up front
+            self.for_each_inverted_mate(None, &Aabb::new(point, point), groups, |id| {
+                result.insert(id);
+            });
+        }
+        result
     }
 
-    pub fn shape_cellmates(&self, shape: &PlacedShape, groups: &[HbGroup]) -> FnvHashSet<HbId> {
-        let bounds = self.index_bounds(shape);
-        self.overlapping_ids(None, bounds, groups)
+    // returns a ring around `point` beyond which there are no occupied cells of
+    // `groups`, or `None` if there are no such cells
+    pub fn max_occupied_ring(&self, point: Vec2, groups: GroupSet) -> Option<i32> {
+        let inverted_ring = self
+            .inverted
+            .values()
+            .find(|&&(group, _)| groups.contains(group))
+            .map(|_| 0);
+        groups
+            .iter()
+            .filter_map(|group| {
+                let &(_, rect) = self.occupied.get(&group)?;
+                let center = Grid::cell_coord(point, self.group_cell_dims(group));
+                Some(rect.max_axis_dist(center).min(i32::MAX as i64) as i32)
+            })
+            .chain(inverted_ring)
+            .max()
+    }
+
+    // calls `f` for the cells that the ray from `origin` along the unit vector
+    // `dir` passes through within `max_dist`, in order of the distance at which
+    // the ray enters each cell (across all groups), passing that distance and
+    // the ids in the cell, until `f` returns false; inverted rects that the ray
+    // may overlap are passed first at a distance of zero, and ids may be passed
+    // more than once
+    pub fn for_each_ray_cell(
+        &self,
+        origin: Vec2,
+        dir: Vec2,
+        max_dist: Float,
+        groups: GroupSet,
+        mut f: impl FnMut(Float, &mut dyn Iterator<Item = HbId>) -> bool,
+    ) {
+        let end = origin + dir * max_dist;
+        let bounds = Aabb::new(origin.min(end), origin.max(end));
+        let mut inverted = Vec::new();
+        self.for_each_inverted_mate(None, &bounds, groups, |id| inverted.push(id));
+        if !f(0.0, &mut inverted.into_iter()) {
+            return;
+        }
+        let mut walks: Vec<(HbGroup, RayCells)> = groups
+            .iter()
+            .map(|group| {
+                let cells = RayCells::new(origin, dir, max_dist, self.group_cell_dims(group));
+                (group, cells)
+            })
+            .collect();
+        loop {
+            let (group, cells) = match walks.iter_mut().min_by_key(|(_, cells)| n64(cells.entry)) {
+                Some(walk) => walk,
+                None => return,
+            };
+            let (entry, coord) = match cells.next() {
+                Some(cell) => cell,
+                None => return,
+            };
+            let ids = self.map.get(&GridKey {
+                coord,
+                group: *group,
+            });
+            if !f(
+                entry,
+                &mut ids.into_iter().flat_map(|ids| ids.iter().cloned()),
+            ) {
+                return;
+            }
+        }
+    }
+
+    pub fn cell_ids(&self, coord: (i32, i32)) -> Vec<HbId> {
+        let mut result: Vec<HbId> = self
+            .map
+            .iter()
+            .filter(|&(key, _)| key.coord == coord)
+            .flat_map(|(_, ids)| ids.iter().cloned())
+            .collect();
+        result.sort();
+        result
     }
 
     pub fn update_hitbox(
@@ -82,56 +614,84 @@ impl Grid {
         group: HbGroup,
         old_hitbox: Option<&DurHitbox>,
         new_hitbox: Option<&DurHitbox>,
-        groups: &[HbGroup],
+        groups: GroupSet,
     ) -> Option<FnvHashSet<HbId>> {
         assert!(new_hitbox.is_some() || groups.is_empty());
-        let old_area = old_hitbox.map(|old_hitbox| self.grid_area(old_hitbox, group));
-        let new_area = new_hitbox.map(|new_hitbox| self.grid_area(new_hitbox, group));
+        let in_cells = |hitbox: &&DurHitbox| hitbox.value.kind() != ShapeKind::InvertedRect;
+        let old_area = old_hitbox
+            .filter(in_cells)
+            .map(|old_hitbox| self.grid_area(old_hitbox, group));
+        let new_area = new_hitbox
+            .filter(in_cells)
+            .map(|new_hitbox| self.grid_area(new_hitbox, group));
         self.update_area(hitbox_id, old_area, new_area);
-        new_area.map(|new_area| self.overlapping_ids(Some(hitbox_id), new_area.rect, groups))
+        self.inverted.remove(&hitbox_id);
+        new_hitbox.map(|new_hitbox| {
+            let mut result = FnvHashSet::default();
+            if in_cells(&new_hitbox) {
+                let bounds = new_hitbox.bounding_box();
+                result = self.overlapping_ids(Some(hitbox_id), &bounds, groups);
+                self.for_each_inverted_mate(Some(hitbox_id), &bounds, groups, |id| {
+                    result.insert(id);
+                });
+            } else {
+                let interior = new_hitbox.interior_box();
+                self.inverted.insert(hitbox_id, (group, interior));
+                self.for_each_inverted_rect_mate(hitbox_id, &interior, groups, |id| {
+                    result.insert(id);
+                });
+            }
+            result
+        })
     }
 
     fn grid_area(&self, hitbox: &DurHitbox, group: HbGroup) -> GridArea {
         GridArea {
-            rect: self.index_bounds(&hitbox.bounding_box()),
+            rect: Grid::index_bounds(&hitbox.bounding_box(), self.group_cell_dims(group)),
             group,
         }
     }
 
-    fn index_bounds(&self, bounds: &PlacedShape) -> IndexRect {
-        let start_x = (bounds.min_x() / self.cell_width).floor() as i32;
-        let start_y = (bounds.min_y() / self.cell_width).floor() as i32;
-        let end_x = cmp::max(
-            (bounds.max_x() / self.cell_width).ceil() as i32,
-            start_x + 1,
-        );
-        let end_y = cmp::max(
-            (bounds.max_y() / self.cell_width).ceil() as i32,
-            start_y + 1,
-        );
+    fn index_bounds(bounds: &Aabb, dims: Vec2) -> IndexRect {
+        let (start_x, start_y) = Grid::cell_coord(bounds.min, dims);
+        let end_x = cmp::max((bounds.max.x / dims.x).ceil() as i32, start_x + 1);
+        let end_y = cmp::max((bounds.max.y / dims.y).ceil() as i32, start_y + 1);
         IndexRect::new((start_x, start_y), (end_x, end_y))
     }
 
+    // each group's cells are indexed using that group's cell dims, so the
+    // bounds are converted to cell indices separately for each group
     fn overlapping_ids(
         &self,
         hitbox_id: Option<HbId>,
-        rect: IndexRect,
-        groups: &[HbGroup],
+        bounds: &Aabb,
+        groups: GroupSet,
     ) -> FnvHashSet<HbId> {
         let mut result = FnvHashSet::default();
-        for &group in groups {
-            for coord in rect.iter() {
-                let key = GridKey { coord, group };
-                if let Some(other_ids) = self.map.get(&key) {
-                    for &other_id in other_ids.iter() {
-                        if Some(other_id) != hitbox_id {
-                            result.insert(other_id);
-                        }
+        for group in groups.iter() {
+            let rect = Grid::index_bounds(bounds, self.group_cell_dims(group));
+            self.add_group_ids(&mut result, hitbox_id, rect, group);
+        }
+        result
+    }
+
+    fn add_group_ids(
+        &self,
+        result: &mut FnvHashSet<HbId>,
+        hitbox_id: Option<HbId>,
+        rect: IndexRect,
+        group: HbGroup,
+    ) {
+        for coord in rect.iter() {
+            let key = GridKey { coord, group };
+            if let Some(other_ids) = self.map.get(&key) {
+                for &other_id in other_ids.iter() {
+                    if Some(other_id) != hitbox_id {
+                        result.insert(other_id);
                     }
                 }
             }
         }
-        result
     }
 
     fn update_area(
@@ -152,6 +712,17 @@ impl Grid {
                         assert!(success);
                         if entry.get().is_empty() {
                             entry.remove();
+                            if let hash_map::Entry::Occupied(mut occupied) =
+                                self.occupied.entry(old_area.group)
+                            {
+                                occupied.get_mut().0 -= 1;
+                                if occupied.get().0 == 0 {
+                                    occupied.remove();
+                                }
+                            }
+                            if let Some(ref mut cell_changes) = self.cell_changes {
+                                cell_changes.entry(key).or_insert(true);
+                            }
                         }
                     } else {
                         unreachable!();
@@ -166,7 +737,18 @@ impl Grid {
                     group: new_area.group,
                 };
                 if old_area.map_or(true, |old_area| !old_area.contains(key)) {
-                    let other_ids = self.map.entry(key).or_insert_with(|| TightSet::new());
+                    let other_ids = match self.map.entry(key) {
+                        hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                        hash_map::Entry::Vacant(entry) => {
+                            if let Some(ref mut cell_changes) = self.cell_changes {
+                                cell_changes.entry(key).or_insert(false);
+                            }
+                            let cell = IndexRect::new(coord, (coord.0 + 1, coord.1 + 1));
+                            let occupied = self.occupied.entry(new_area.group).or_insert((0, cell));
+                            *occupied = (occupied.0 + 1, occupied.1.union(cell));
+                            entry.insert(TightSet::new())
+                        }
+                    };
                     let success = other_ids.insert(hitbox_id);
                     assert!(success);
                 }
@@ -174,3 +756,140 @@ impl Grid {
         }
     }
 }
+
+// walks the cells along a ray using a DDA traversal, yielding each cell along
+// with the distance at which the ray enters it
+struct RayCells {
+    coord: (i32, i32),
+    entry: Float,
+    max_dist: Float,
+    step: (i32, i32),
+    next: (Float, Float),
+    delta: (Float, Float),
+}
+
+impl RayCells {
+    fn new(origin: Vec2, dir: Vec2, max_dist: Float, dims: Vec2) -> RayCells {
+        let coord = Grid::cell_coord(origin, dims);
+        let (step_x, next_x, delta_x) = RayCells::axis(origin.x, dir.x, coord.0, dims.x);
+        let (step_y, next_y, delta_y) = RayCells::axis(origin.y, dir.y, coord.1, dims.y);
+        RayCells {
+            coord,
+            entry: 0.0,
+            max_dist,
+            step: (step_x, step_y),
+            next: (next_x, next_y),
+            delta: (delta_x, delta_y),
+        }
+    }
+
+    // returns the index step, the distance to the first cell boundary, and the
+    // distance between cell boundaries along one axis of a ray
+    fn axis(pos: Float, dir: Float, index: i32, width: Float) -> (i32, Float, Float) {
+        if dir > 0.0 {
+            (1, ((index + 1) as Float * width - pos) / dir, width / dir)
+        } else if dir < 0.0 {
+            (-1, (index as Float * width - pos) / dir, -width / dir)
+        } else {
+            (0, Float::INFINITY, Float::INFINITY)
+        }
+    }
+}
+
+impl Iterator for RayCells {
+    type Item = (Float, (i32, i32));
+
+    fn next(&mut self) -> Option<(Float, (i32, i32))> {
+        if self.entry > self.max_dist {
+            return None;
+        }
+        let result = (self.entry, self.coord);
+        if self.next.0 < self.next.1 {
+            self.entry = self.next.0;
+            self.coord.0 += self.step.0;
+            self.next.0 += self.delta.0;
+        } else {
+            self.entry = self.next.1;
+            self.coord.1 += self.step.1;
+            self.next.1 += self.delta.1;
+        }
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::Shape;
+
+    #[test]
+    fn test_reserve() {
+        let mut grid = Grid::new(v2(4.0, 4.0));
+        grid.reserve(100);
+        let capacity = grid.map.capacity();
+        assert!(capacity >= 100);
+        // each hitbox lies within a single cell, adding one grid entry
+        for id in 0..100 {
+            let pos = v2(
+                (id % 10) as Float * 4.0 + 2.0,
+                (id / 10) as Float * 4.0 + 2.0,
+            );
+            let hitbox = DurHitbox::new(Shape::square(2.0).place(pos));
+            grid.update_hitbox(id, 0, None, Some(&hitbox), GroupSet::empty());
+        }
+        assert_eq!(grid.map.len(), 100);
+        assert_eq!(grid.map.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_max_occupied_ring() {
+        let mut grid = Grid::new(v2(4.0, 4.0));
+        let groups = GroupSet::List(&[0]);
+        assert_eq!(grid.max_occupied_ring(v2(0.0, 0.0), groups), None);
+        let near = DurHitbox::new(Shape::square(2.0).place(v2(2.0, 2.0)));
+        let far = DurHitbox::new(Shape::square(2.0).place(v2(-38.0, 2.0)));
+        grid.update_hitbox(0, 0, None, Some(&near), GroupSet::empty());
+        grid.update_hitbox(1, 0, None, Some(&far), GroupSet::empty());
+        assert_eq!(grid.max_occupied_ring(v2(2.0, 2.0), groups), Some(10));
+        assert_eq!(
+            grid.max_occupied_ring(v2(2.0, 2.0), GroupSet::empty()),
+            None
+        );
+        grid.update_hitbox(1, 0, Some(&far), None, GroupSet::empty());
+        grid.update_hitbox(0, 0, Some(&near), None, GroupSet::empty());
+        assert_eq!(grid.max_occupied_ring(v2(2.0, 2.0), groups), None);
+    }
+
+    #[test]
+    fn test_inverted_rect_mates() {
+        let mut grid = Grid::new(v2(4.0, 4.0));
+        let groups = GroupSet::List(&[0]);
+        for &(id, x) in &[(1, 0.0), (2, 18.0), (3, 60.0)] {
+            let hitbox = DurHitbox::new(Shape::square(2.0).place(v2(x, 2.0)));
+            grid.update_hitbox(id, 0, None, Some(&hitbox), GroupSet::empty());
+        }
+        let arena = DurHitbox::new(Shape::inverted_rect(v2(40.0, 40.0)).place(v2(0.0, 0.0)));
+        let mut mates: Vec<HbId> = grid
+            .update_hitbox(0, 0, None, Some(&arena), groups)
+            .unwrap()
+            .into_iter()
+            .collect();
+        mates.sort();
+        assert_eq!(mates, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_ray_cells_stop_early() {
+        let mut grid = Grid::new(v2(4.0, 4.0));
+        let hitbox = DurHitbox::new(Shape::square(2.0).place(v2(10.0, 2.0)));
+        grid.update_hitbox(0, 0, None, Some(&hitbox), GroupSet::empty());
+        let mut entries = Vec::new();
+        let groups = GroupSet::List(&[0]);
+        grid.for_each_ray_cell(v2(2.0, 2.0), v2(1.0, 0.0), 1e6, groups, |entry, ids| {
+            entries.push(entry);
+            ids.next().is_none()
+        });
+        // the inverted rects are passed first, then the cells up to the hit
+        assert_eq!(entries, vec![0.0, 0.0, 2.0, 6.0]);
+    }
+}
@@ -18,20 +18,79 @@ mod events;
 mod grid;
 
 pub use self::collider::*;
+pub use self::grid::GridStats;
 
-use std::f64;
+use float::Float;
+use std::error;
+use std::fmt;
 
 use self::dur_hitbox::{DurHbVel, DurHitbox};
-use geom::shape::PlacedBounds;
+use geom::shape::{anchor_shift, PlacedBounds};
 use geom::*;
 
-const HIGH_TIME: f64 = 1e50;
+#[cfg(not(feature = "f32"))]
+const HIGH_TIME: Float = 1e50;
+
+// small enough that fourth powers of times stay within the range of f32; this
+// limit is documented on `Float`, `set_time`, and `rebase_time`
+#[cfg(feature = "f32")]
+const HIGH_TIME: Float = 1e8;
+
+// the largest angle that a rotating hitbox turns through before it is solved
+// again at its new orientation
+const ROTATION_STEP: Float = 0.05;
+
+/// Errors returned by the fallible `Collider` methods, such as
+/// `try_add_hitbox`, when given invalid data.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ColliderError {
+    /// The width or height of a shape is less than the collider `padding`.
+    ShapeTooSmall,
+    /// The `end_time` of a velocity is before the present time, or is NaN.
+    EndTimeInPast,
+    /// A position, dimension, or velocity is infinite or NaN.
+    NonFinite,
+    /// A circle has a resize velocity that does not maintain its aspect ratio,
+    /// or a segment, capsule, triangle, or oriented rect has a non-zero resize
+    /// velocity.
+    InvalidResize,
+    /// A capsule or triangle has a non-zero angular velocity, or a rotating
+    /// hitbox has a non-zero resize velocity.
+    InvalidRotation,
+    /// A hitbox with the same `HbId` is already present.
+    IdInUse,
+    /// No hitbox with the given `HbId` is present.
+    IdNotFound,
+}
+
+impl fmt::Display for ColliderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match *self {
+            ColliderError::ShapeTooSmall => "shape width/height must be at least padding",
+            ColliderError::EndTimeInPast => "end time must exceed present time",
+            ColliderError::NonFinite => "hitbox values must be finite",
+            ColliderError::InvalidResize => {
+                "circle resize velocity must maintain aspect ratio, and segments, capsules, \
+                 triangles, and oriented rects must not resize"
+            }
+            ColliderError::InvalidRotation => {
+                "capsules and triangles must not rotate, and rotating hitboxes must not resize"
+            }
+            ColliderError::IdInUse => "hitbox id is already in use",
+            ColliderError::IdNotFound => "hitbox id not found",
+        };
+        f.write_str(message)
+    }
+}
+
+impl error::Error for ColliderError {}
 
 /// Type used as a handle for referencing hitboxes in a `Collider` instance.
 pub type HbId = u64;
 
 /// Velocity information describing how a hitbox shape is changing over time.
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct HbVel {
     /// The movement velocity of the hitbox.
     pub value: Vec2,
@@ -42,7 +101,8 @@ pub struct HbVel {
     /// times, if a resize velocity is set that decreases the dimensions of the
     /// shape over time, then the user is responsible for ensuring that the
     /// shape will not decrease below this threshold. Collider may panic if this
-    /// is violated.
+    /// is violated. Segments, capsules, triangles, and oriented rects must not
+    /// have a resize velocity.
     pub resize: Vec2,
 
     /// An upper-bound on the time at which the hitbox will be updated by the
@@ -55,7 +115,38 @@ pub struct HbVel {
     /// Collider will panic if the end time is exceeded without update, at least
     /// in unoptimized builds.  It is ultimately the user's responsibility to
     /// ensure that end times are not exceeded.
-    pub end_time: f64,
+    pub end_time: Float,
+
+    /// The rotation speed of the hitbox, in radians per unit time.
+    ///
+    /// The hitbox rotates counter-clockwise about its center, so a rectangle
+    /// becomes an `OrientedRect` and a segment turns its dims as time passes.
+    /// When solving for collide and separate times, a rotating hitbox is
+    /// treated as an oriented rect at its current orientation, enlarged to
+    /// cover its motion while it turns through a small angle, and it is solved
+    /// again after each such turn. So `Collide` events may occur slightly early
+    /// and `Separate` events may occur slightly late. Circles, points, and
+    /// inverted rects ignore this value, while capsules and triangles must not
+    /// rotate, and a rotating hitbox must not resize.
+    /// Default is `0.0`.
+    pub angular: Float,
+
+    /// The constant acceleration of the hitbox, such as gravity.
+    ///
+    /// The position of the hitbox after `t` time has elapsed is
+    /// `pos + value * t + 0.5 * accel * t^2`, and `value` is updated
+    /// accordingly as time passes. Collide and separate times are still solved
+    /// exactly, but accelerating hitboxes are more expensive to solve and will
+    /// be moved within the grid more often. Default is zero.
+    pub accel: Vec2,
+
+    /// The edge of the hitbox that stays in place as the hitbox is resized.
+    ///
+    /// By default (`None`), the hitbox is resized about its center. If set to
+    /// `Some(card)`, then the edge of the hitbox in the `card` direction stays
+    /// fixed while the opposite edge moves by the full resize velocity (for
+    /// example, a wall growing up out of the ground would use `Card::MinusY`).
+    pub resize_anchor: Option<Card>,
 }
 
 impl HbVel {
@@ -65,17 +156,49 @@ impl HbVel {
         HbVel {
             value,
             resize: Vec2::zero(),
-            end_time: f64::INFINITY,
+            end_time: Float::INFINITY,
+            angular: 0.0,
+            accel: Vec2::zero(),
+            resize_anchor: None,
         }
     }
 
     /// Creates an `HbVel` with the given `value` and `end_time`.
     #[inline]
-    pub fn moving_until(value: Vec2, end_time: f64) -> HbVel {
+    pub fn moving_until(value: Vec2, end_time: Float) -> HbVel {
         HbVel {
             value,
             resize: Vec2::zero(),
             end_time,
+            angular: 0.0,
+            accel: Vec2::zero(),
+            resize_anchor: None,
+        }
+    }
+
+    /// Creates an `HbVel` with the given `value` and `angular` velocity.
+    #[inline]
+    pub fn rotating(value: Vec2, angular: Float) -> HbVel {
+        HbVel {
+            value,
+            resize: Vec2::zero(),
+            end_time: Float::INFINITY,
+            angular,
+            accel: Vec2::zero(),
+            resize_anchor: None,
+        }
+    }
+
+    /// Creates an `HbVel` with the given `value` and `accel`.
+    #[inline]
+    pub fn accelerating(value: Vec2, accel: Vec2) -> HbVel {
+        HbVel {
+            value,
+            resize: Vec2::zero(),
+            end_time: Float::INFINITY,
+            angular: 0.0,
+            accel,
+            resize_anchor: None,
         }
     }
 
@@ -85,21 +208,35 @@ impl HbVel {
         HbVel {
             value: Vec2::zero(),
             resize: Vec2::zero(),
-            end_time: f64::INFINITY,
+            end_time: Float::INFINITY,
+            angular: 0.0,
+            accel: Vec2::zero(),
+            resize_anchor: None,
         }
     }
 
     /// Creates a stationary `HbVel` with the given `end_time`.
     #[inline]
-    pub fn still_until(end_time: f64) -> HbVel {
+    pub fn still_until(end_time: Float) -> HbVel {
         HbVel {
             value: Vec2::zero(),
             resize: Vec2::zero(),
             end_time,
+            angular: 0.0,
+            accel: Vec2::zero(),
+            resize_anchor: None,
         }
     }
 }
 
+impl HbVel {
+    // the velocity of the center of the hitbox, which differs from `value` when
+    // the hitbox is resized about an anchored edge
+    fn center_value(&self) -> Vec2 {
+        self.value + anchor_shift(self.resize, self.resize_anchor)
+    }
+}
+
 impl From<Vec2> for HbVel {
     fn from(value: Vec2) -> HbVel {
         HbVel::moving(value)
@@ -107,16 +244,17 @@ impl From<Vec2> for HbVel {
 }
 
 impl PlacedBounds for HbVel {
-    fn bounds_center(&self) -> &Vec2 {
-        &self.value
+    fn bounds_center(&self) -> Vec2 {
+        self.center_value()
     }
-    fn bounds_dims(&self) -> &Vec2 {
-        &self.resize
+    fn bounds_dims(&self) -> Vec2 {
+        self.resize
     }
 }
 
 /// Represents a moving shape for continuous collision testing.
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Hitbox {
     /// The placed shape at the given point in time.
     ///
@@ -129,7 +267,7 @@ pub struct Hitbox {
     pub vel: HbVel,
 }
 
-//TODO invoke hitbox.validate() in more places so that inconsistencies are still found in optimized builds, just found later
+//TODO invoke hitbox.try_validate() in more places so that inconsistencies are still found in optimized builds, just found later
 
 impl Hitbox {
     /// Constructs a new hitbox with the given `value` and `vel`.
@@ -138,33 +276,124 @@ impl Hitbox {
         Hitbox { value, vel }
     }
 
-    fn advanced_shape(&self, time: f64) -> PlacedShape {
-        assert!(time < HIGH_TIME, "requires time < {}", HIGH_TIME);
-        self.value.advance(self.vel.value, self.vel.resize, time)
+    /// Returns the time at which this hitbox and `other` will collide, or
+    /// infinity if they will not collide before the `end_time` of either
+    /// velocity.
+    ///
+    /// Both hitboxes are taken to be at their given positions at time `now`,
+    /// which must not exceed the `end_time` of either velocity. This predicts
+    /// collisions without adding the hitboxes to a `Collider`. If the hitboxes
+    /// are already overlapping, then `now` is returned.
+    pub fn collide_time(&self, other: &Hitbox, now: Float) -> Float {
+        let (a, b) = self.dur_hitbox_pair(other, now);
+        now + a.collide_time(&b)
+    }
+
+    /// Returns the time at which this hitbox and `other` will be separated by
+    /// more than `padding`, or infinity if they will not separate before the
+    /// `end_time` of either velocity.
+    ///
+    /// See `collide_time` for a description of `now`. If the hitboxes are
+    /// already separated, then `now` is returned. Panics if `padding` is not
+    /// positive.
+    pub fn separate_time(&self, other: &Hitbox, now: Float, padding: Float) -> Float {
+        assert!(padding > 0.0, "requires padding > 0.0");
+        let (a, b) = self.dur_hitbox_pair(other, now);
+        now + a.separate_time(&b, padding)
     }
 
-    fn validate(&self, min_size: f64, present_time: f64) {
+    fn dur_hitbox_pair(&self, other: &Hitbox, now: Float) -> (DurHitbox, DurHitbox) {
         assert!(
-            !self.vel.end_time.is_nan() && self.vel.end_time >= present_time,
-            "end time must exceed present time"
+            now <= self.vel.end_time && now <= other.vel.end_time,
+            "requires now <= end_time"
         );
-        if self.value.kind() == ShapeKind::Circle {
-            assert_eq!(
-                self.vel.resize.x, self.vel.resize.y,
-                "circle resize velocity must maintain aspect ratio"
-            );
-        }
-        assert!(
-            self.value.dims().x >= min_size && self.value.dims().y >= min_size,
-            "shape width/height must be at least {}",
-            min_size
+        (self.to_dur_hitbox(now), other.to_dur_hitbox(now))
+    }
+
+    fn advanced_shape(&self, time: Float) -> PlacedShape {
+        assert!(time < HIGH_TIME, "requires time < {}", HIGH_TIME);
+        let mut result = self.value.advance(
+            self.vel.value,
+            self.vel.resize,
+            self.vel.resize_anchor,
+            time,
         );
+        result.pos += self.vel.accel * (0.5 * time * time);
+        result.shape = result.shape.rotated(self.vel.angular * time);
+        result
+    }
+
+    fn advance(&mut self, time: Float) {
+        self.value = self.advanced_shape(time);
+        self.vel.value += self.vel.accel * time;
     }
 
-    fn time_until_too_small(&self, min_size: f64) -> f64 {
+    fn try_validate(&self, min_size: Float, present_time: Float) -> Result<(), ColliderError> {
+        let vecs = [
+            self.value.pos,
+            self.value.dims(),
+            self.vel.value,
+            self.vel.resize,
+            self.vel.accel,
+        ];
+        if !self.vel.angular.is_finite()
+            || vecs.iter().any(|v| !v.x.is_finite() || !v.y.is_finite())
+        {
+            return Err(ColliderError::NonFinite);
+        }
+        if self.vel.end_time.is_nan() || self.vel.end_time < present_time {
+            return Err(ColliderError::EndTimeInPast);
+        }
+        if self.vel.angular != 0.0 {
+            match self.value.kind() {
+                ShapeKind::Capsule | ShapeKind::Triangle => {
+                    return Err(ColliderError::InvalidRotation)
+                }
+                _ if self.value.shape.rotates() && self.vel.resize != Vec2::zero() => {
+                    return Err(ColliderError::InvalidRotation)
+                }
+                _ => (),
+            }
+        }
+        match self.value.kind() {
+            ShapeKind::Circle if self.vel.resize.x != self.vel.resize.y => {
+                Err(ColliderError::InvalidResize)
+            }
+            ShapeKind::Segment
+            | ShapeKind::Capsule
+            | ShapeKind::Triangle
+            | ShapeKind::OrientedRect
+            | ShapeKind::Point
+                if self.vel.resize != Vec2::zero() =>
+            {
+                Err(ColliderError::InvalidResize)
+            }
+            ShapeKind::Segment | ShapeKind::Point => Ok(()),
+            ShapeKind::Triangle
+                if self.value.dims().x.abs() < min_size || self.value.dims().y.abs() < min_size =>
+            {
+                Err(ColliderError::ShapeTooSmall)
+            }
+            ShapeKind::Triangle => Ok(()),
+            _ if self.value.dims().x < min_size || self.value.dims().y < min_size => {
+                Err(ColliderError::ShapeTooSmall)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn time_until_too_small(&self, min_size: Float) -> Float {
+        // segments, triangles, and points are not allowed to resize
+        if self.value.kind().allows_negative_dims() || self.value.kind() == ShapeKind::Point {
+            return Float::INFINITY;
+        }
+        // invalidated hitboxes may already be smaller than `min_size`
+        if self.vel.resize.x >= 0.0 && self.vel.resize.y >= 0.0 {
+            return Float::INFINITY;
+        }
         let min_size = min_size * 0.9;
         assert!(self.value.dims().x > min_size && self.value.dims().y > min_size);
-        let mut time = f64::INFINITY;
+        let mut time = Float::INFINITY;
         if self.vel.resize.x < 0.0 {
             time = time.min((min_size - self.value.dims().x) / self.vel.resize.x);
         }
@@ -174,17 +403,66 @@ impl Hitbox {
         time
     }
 
-    fn to_dur_hitbox(&self, time: f64) -> DurHitbox {
+    // the time until the hitbox turns through `ROTATION_STEP`, after which it
+    // must be solved again at its new orientation
+    fn rotation_period(&self) -> Float {
+        if self.vel.angular != 0.0 && self.value.shape.rotates() {
+            ROTATION_STEP / self.vel.angular.abs()
+        } else {
+            Float::INFINITY
+        }
+    }
+
+    fn to_dur_hitbox(&self, time: Float) -> DurHitbox {
         assert!(time <= self.vel.end_time);
+        if self.vel.angular != 0.0 && self.value.shape.rotates() {
+            return self.to_rotating_dur_hitbox(time);
+        }
         DurHitbox {
             value: self.value,
             vel: DurHbVel {
-                value: self.vel.value,
+                value: self.vel.center_value(),
                 resize: self.vel.resize,
+                accel: self.vel.accel,
                 duration: self.vel.end_time - time,
             },
         }
     }
+
+    // While the hitbox turns through an angle `sweep`, each point of it moves
+    // along an arc of length at most `radius * sweep`, so the oriented rect at
+    // the current orientation grown by that much on each side encloses it.
+    // Rotating hitboxes do not resize. The circumscribing circle is used when
+    // the sweep is large, which only happens outside of a `Collider`, where
+    // the duration is not limited by `rotation_period`.
+    fn to_rotating_dur_hitbox(&self, time: Float) -> DurHitbox {
+        let duration = self.vel.end_time - time;
+        let sweep = self.vel.angular.abs() * duration;
+        let dims = self.value.dims();
+        let radius = dims.len() * 0.5;
+        let shape = if sweep > 1.0 {
+            Shape::circle(radius * 2.0)
+        } else {
+            let (dir, dims) = match self.value.kind() {
+                ShapeKind::Segment => (
+                    dims.normalize().unwrap_or(v2(1.0, 0.0)),
+                    v2(dims.len(), 0.0),
+                ),
+                _ => (self.value.shape.orientation(), dims),
+            };
+            let margin = radius * sweep * 2.0;
+            Shape::oriented_rect(dims + v2(margin, margin), dir.y.atan2(dir.x))
+        };
+        DurHitbox {
+            value: shape.place(self.value.pos),
+            vel: DurHbVel {
+                value: self.vel.center_value(),
+                resize: Vec2::zero(),
+                accel: self.vel.accel,
+                duration,
+            },
+        }
+    }
 }
 
 /// A group id that may be used as a first measure to efficiently filter out
@@ -230,9 +508,109 @@ pub trait HbProfile: Copy {
         &DEFAULT_GROUPS
     }
 
+    /// Returns a bitmask of the groups that this hitbox can interact with, as
+    /// an alternative to `interact_groups`. Default is `None`.
+    ///
+    /// If a mask is given, then it is used instead of `interact_groups`, and
+    /// the hitbox interacts with each group `g` for which the bit `1 << g` is
+    /// set (so a mask of `0` interacts with no groups). This allows the groups
+    /// to be chosen at runtime without a static array. Only groups less than
+    /// 64 can be given this way.
+    fn interact_mask(&self) -> Option<u64> {
+        None
+    }
+
     /// Returns true if the pair of hitboxes should be checked for collisions.
     ///
     /// This method should be commutative. This method should be consistent with
-    /// `group` and `interact_groups`, although possibly more restrictive.
+    /// `group` and `interact_groups` (or `interact_mask`), although possibly
+    /// more restrictive.
     fn can_interact(&self, other: &Self) -> bool;
+
+    /// Returns a key describing the result of `can_interact` for this hitbox.
+    /// Default is `0`, which disables caching.
+    ///
+    /// If two profiles have the same nonzero key and the same group, then
+    /// `can_interact` must give the same results for both of them. `Collider`
+    /// caches the results of `can_interact` for pairs of nonzero keys, which is
+    /// useful if `can_interact` is expensive. If the interaction behavior of a
+    /// profile changes, then it should be given a new key.
+    fn interact_key(&self) -> u64 {
+        0
+    }
+
+    /// Returns the team of the hitbox. Default is `None`.
+    ///
+    /// Hitboxes that share the same non-`None` team never collide with each
+    /// other, and `can_interact` is not called for them. This is a cheaper
+    /// filter than `can_interact` for large teams of hitboxes that should not
+    /// interact with each other, such as the bullets fired by one player.
+    /// Queries are not affected by teams.
+    fn team(&self) -> Option<u32> {
+        None
+    }
+
+    /// Returns true if `other` should block the movement of this hitbox when
+    /// resolving overlaps. Default is `true`.
+    ///
+    /// This is independent of `can_interact`, which determines whether events
+    /// are generated at all. A hitbox that acts as a trigger may return `false`
+    /// here so that it is still detected but never pushes other hitboxes
+    /// around. Collision response helpers such as
+    /// `Collider::resolve_overlap_masked` consult this method.
+    fn is_solid(&self, _other: &Self) -> bool {
+        true
+    }
+
+    /// Returns how collisions involving this hitbox are detected. Default is
+    /// `DetectionMode::Continuous`.
+    ///
+    /// If either hitbox in a pair uses `DetectionMode::Discrete`, then the
+    /// pair is treated as discrete.
+    fn detection_mode(&self) -> DetectionMode {
+        DetectionMode::Continuous
+    }
+
+    /// Returns the direction from which other hitboxes may collide with this
+    /// hitbox, or `None` if collisions are allowed from every direction.
+    /// Default is `None`.
+    ///
+    /// This is intended for one-way platforms that can be jumped up through
+    /// but landed on, using `Some(Card::PlusY)`. A `Collide` event is only
+    /// reported if, at the time the collision begins, the velocity of the
+    /// other hitbox relative to this one points against the given direction.
+    /// Collisions that are suppressed this way are not retried while the
+    /// hitboxes remain overlapping, unless one of them is updated. Once a
+    /// collision is reported, the `Separate` event is unaffected. Predictions
+    /// of collisions such as `Collider::collisions_within`,
+    /// `Collider::projected_stop` and `Collider::query_sweep` follow the same
+    /// rule, while the other queries ignore it.
+    fn one_way(&self) -> Option<Card> {
+        None
+    }
+}
+
+/// Describes how collisions are detected for a pair of hitboxes.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum DetectionMode {
+    /// Collide and Separate events are scheduled at the exact time they occur.
+    Continuous,
+
+    /// Overlaps are only checked when one of the hitboxes is updated, either by
+    /// the user or internally at reiteration points. Collide and Separate
+    /// events are reported at the first such check after the transition.
+    ///
+    /// This trades precision for fewer scheduled events, and is intended for
+    /// large numbers of non-critical hitboxes such as item pickups. A fast
+    /// moving hitbox may pass through a discrete hitbox without any events
+    /// being reported.
+    Discrete,
+}
+
+fn is_same_team<P: HbProfile>(a: &P, b: &P) -> bool {
+    a.team().is_some() && a.team() == b.team()
+}
+
+fn is_discrete_pair<P: HbProfile>(a: &P, b: &P) -> bool {
+    a.detection_mode() == DetectionMode::Discrete || b.detection_mode() == DetectionMode::Discrete
 }
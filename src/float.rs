@@ -13,21 +13,53 @@
 // limitations under the License.
 
 use std::cmp::Ordering;
-use std::f64;
 
-// N64 wraps a non-NaN f64 value and implements Ord.
+/// The floating point type used for all geometry and time values.
+///
+/// This is `f64` by default, or `f32` when the `f32` cargo feature is
+/// enabled. Note that `f32` has much less precision, so collision times and
+/// positions are less exact, and scenes should stay closer to the origin.
+///
+/// The simulation time must stay below `1e50`, or below `1e8` with the `f32`
+/// feature (see `Collider::set_time` and `Collider::rebase_time`).
+#[cfg(not(feature = "f32"))]
+pub type Float = f64;
 
-pub fn n64(val: f64) -> N64 {
+/// The floating point type used for all geometry and time values.
+///
+/// This is `f64` by default, or `f32` when the `f32` cargo feature is
+/// enabled. Note that `f32` has much less precision, so collision times and
+/// positions are less exact, and scenes should stay closer to the origin.
+///
+/// The simulation time must stay below `1e50`, or below `1e8` with the `f32`
+/// feature (see `Collider::set_time` and `Collider::rebase_time`).
+#[cfg(feature = "f32")]
+pub type Float = f32;
+
+#[cfg(not(feature = "f32"))]
+pub(crate) use std::f64::consts;
+
+#[cfg(feature = "f32")]
+pub(crate) use std::f32::consts;
+
+// tolerance for approximate comparisons in tests, scaled with the precision of
+// Float
+#[cfg(test)]
+pub const EPS: Float = Float::EPSILON * 1024.0;
+
+// N64 wraps a non-NaN Float value and implements Ord.
+
+pub fn n64(val: Float) -> N64 {
     N64::new(val)
 }
 
 #[derive(PartialEq, PartialOrd, Copy, Clone, Default)]
 pub struct N64 {
-    val: f64,
+    val: Float,
 }
 
 impl N64 {
-    fn new(val: f64) -> N64 {
+    fn new(val: Float) -> N64 {
         assert!(!val.is_nan(), "unexpected NaN");
         N64 { val }
     }
@@ -0,0 +1,89 @@
+// Copyright 2016-2018 Matthew D. Michelotti
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use geom::{v2, PlacedShape, Shape, Vec2};
+
+/// An axis-aligned bounding box, given by its minimum and maximum corners.
+///
+/// Unlike a rectangular `PlacedShape`, an `Aabb` carries no `ShapeKind`, and
+/// is intended for coarse spatial lookups such as broadphase culling.
+#[derive(PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Aabb {
+    /// The corner with the lowest x and y coordinates.
+    pub min: Vec2,
+    /// The corner with the highest x and y coordinates.
+    pub max: Vec2,
+}
+
+impl Aabb {
+    /// Constructs a new `Aabb` with the given corners. Panics if `min` exceeds
+    /// `max` in either coordinate.
+    pub fn new(min: Vec2, max: Vec2) -> Aabb {
+        assert!(min.x <= max.x && min.y <= max.y, "requires min <= max");
+        Aabb { min, max }
+    }
+
+    /// Returns the smallest `Aabb` enclosing the given `shape`.
+    pub fn from_placed_shape(shape: &PlacedShape) -> Aabb {
+        // not using `Aabb::new`, since the solvers may advance a shrinking
+        // shape past zero size
+        Aabb {
+            min: v2(shape.min_x(), shape.min_y()),
+            max: v2(shape.max_x(), shape.max_y()),
+        }
+    }
+
+    /// Returns the center of the `Aabb`.
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Returns the width and height of the `Aabb`.
+    pub fn dims(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    /// Returns `true` if this `Aabb` and `other` overlap. Boxes that only
+    /// touch are considered overlapping.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    /// Returns `true` if `point` lies within the `Aabb`.
+    ///
+    /// Points exactly on the boundary are contained.
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+    }
+
+    /// Returns the smallest `Aabb` enclosing both this `Aabb` and `other`.
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Returns a rectangle `PlacedShape` covering the same area.
+    pub fn to_rect(&self) -> PlacedShape {
+        Shape::rect(self.dims()).place(self.center())
+    }
+}
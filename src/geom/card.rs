@@ -17,6 +17,7 @@ use std::ops::{Index, IndexMut};
 
 /// Represents the four cardinal directions in 2D space.
 #[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Card {
     /// Negative X direction.
     MinusX,
@@ -52,6 +53,7 @@ impl Card {
 /// A map from `Card` to `bool`, typically used to specify allowed normal vector
 /// directions.
 #[derive(PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CardMask {
     flags: [bool; 4],
 }
@@ -69,6 +71,21 @@ impl CardMask {
         CardMask { flags: [true; 4] }
     }
 
+    /// Returns an iterator over each `Card` whose value is `true`.
+    pub fn iter(&self) -> impl Iterator<Item = Card> {
+        let mask = *self;
+        let values = Card::values();
+        (0..values.len())
+            .map(move |index| values[index])
+            .filter(move |&card| mask[card])
+    }
+
+    /// Returns the number of `Card` values that are `true`.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.flags.iter().filter(|&&flag| flag).count()
+    }
+
     pub(crate) fn flip(self) -> CardMask {
         let mut result = CardMask::empty();
         result[Card::PlusX] = self[Card::MinusX];
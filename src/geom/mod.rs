@@ -14,10 +14,14 @@
 
 //! Module containing geometry primitives.
 
+mod aabb;
 mod card;
+mod motion;
 pub(crate) mod shape;
 mod vec;
 
+pub use self::aabb::Aabb;
 pub use self::card::*;
+pub use self::motion::*;
 pub use self::shape::{PlacedShape, Shape, ShapeKind};
 pub use self::vec::*;
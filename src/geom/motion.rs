@@ -0,0 +1,151 @@
+// Copyright 2016-2018 Matthew D. Michelotti
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use float::Float;
+use geom::{DirVec2, Vec2};
+
+// This module contains free functions for computing motion in response to
+// collisions, to be layered on top of the detection done by Collider.
+
+/// Computes the magnitude of the impulse along `normal` that brings the
+/// relative approach velocity of two bodies to zero.
+///
+/// `rel_vel` is the velocity of body A minus the velocity of body B, and
+/// `normal` points from body B towards body A (as returned by
+/// `a.normal_from(&b)`). `inv_mass_a` and `inv_mass_b` are the inverse masses
+/// of the bodies, where `0.0` denotes an immovable body. The impulse should be
+/// applied by adding `impulse * inv_mass_a * normal.dir()` to the velocity of A
+/// and subtracting `impulse * inv_mass_b * normal.dir()` from the velocity of
+/// B.
+///
+/// Returns `0.0` if the bodies are not approaching each other along `normal`,
+/// or if both bodies are immovable.
+pub fn stopping_impulse(
+    rel_vel: Vec2,
+    normal: DirVec2,
+    inv_mass_a: Float,
+    inv_mass_b: Float,
+) -> Float {
+    let approach_speed = -(rel_vel * normal.dir());
+    let inv_mass = inv_mass_a + inv_mass_b;
+    if approach_speed <= 0.0 || inv_mass <= 0.0 {
+        0.0
+    } else {
+        approach_speed / inv_mass
+    }
+}
+
+/// Computes the velocity with magnitude `speed` that a projectile fired from
+/// `shooter` needs in order to intercept a target at position `target` moving
+/// with constant velocity `target_vel`.
+///
+/// The earliest possible interception is chosen. Returns `None` if the target
+/// cannot be intercepted (e.g. it is moving away faster than `speed`), if
+/// `speed` is not positive, or if `shooter` is already at `target`.
+pub fn intercept_velocity(
+    shooter: Vec2,
+    target: Vec2,
+    target_vel: Vec2,
+    speed: Float,
+) -> Option<Vec2> {
+    let offset = target - shooter;
+    if speed <= 0.0 || offset == Vec2::zero() {
+        return None;
+    }
+    // solve |offset + target_vel * t| = speed * t for the smallest t > 0
+    let a = target_vel.len_sq() - speed * speed;
+    let b = 2.0 * (offset * target_vel);
+    let c = offset.len_sq();
+    let time = if a == 0.0 {
+        if b < 0.0 {
+            -c / b
+        } else {
+            return None;
+        }
+    } else {
+        let determinant = b * b - 4.0 * a * c;
+        if determinant < 0.0 {
+            return None;
+        }
+        let sqrt = determinant.sqrt();
+        let (root_1, root_2) = ((-b - sqrt) / (2.0 * a), (-b + sqrt) / (2.0 * a));
+        let (root_1, root_2) = (root_1.min(root_2), root_1.max(root_2));
+        if root_1 > 0.0 {
+            root_1
+        } else if root_2 > 0.0 {
+            root_2
+        } else {
+            return None;
+        }
+    };
+    Some((offset + target_vel * time) * (1.0 / time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::v2;
+
+    #[test]
+    fn test_stopping_impulse() {
+        let normal = DirVec2::new(v2(0.0, 1.0), 0.5);
+        let vel_a = v2(3.0, -4.0);
+        let vel_b = v2(0.0, 2.0);
+        let impulse = stopping_impulse(vel_a - vel_b, normal, 1.0, 0.5);
+        assert_eq!(impulse, 4.0);
+        let vel_a = vel_a + impulse * 1.0 * normal.dir();
+        let vel_b = vel_b - impulse * 0.5 * normal.dir();
+        assert_eq!(vel_a, v2(3.0, 0.0));
+        assert_eq!(vel_b, v2(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_intercept_stationary() {
+        let vel = intercept_velocity(v2(1.0, 1.0), v2(4.0, 5.0), Vec2::zero(), 2.0).unwrap();
+        assert!((vel - v2(1.2, 1.6)).len() < 1e-10);
+    }
+
+    #[test]
+    fn test_intercept_crossing() {
+        let vel = intercept_velocity(v2(0.0, 0.0), v2(3.0, -4.0), v2(0.0, 1.0), 1.0).unwrap();
+        assert!((vel - v2(0.96, -0.28)).len() < 1e-10);
+        let vel = intercept_velocity(v2(0.0, 0.0), v2(-10.0, 10.0), v2(2.0, 0.0), 2.0).unwrap();
+        assert!((vel - v2(0.0, 2.0)).len() < 1e-10);
+    }
+
+    #[test]
+    fn test_intercept_impossible() {
+        assert_eq!(
+            intercept_velocity(v2(0.0, 0.0), v2(5.0, 0.0), v2(3.0, 0.0), 2.0),
+            None
+        );
+        assert_eq!(
+            intercept_velocity(v2(0.0, 0.0), v2(5.0, 0.0), v2(2.0, 0.0), 2.0),
+            None
+        );
+        assert_eq!(
+            intercept_velocity(v2(0.0, 0.0), v2(5.0, 0.0), Vec2::zero(), 0.0),
+            None
+        );
+        assert!(intercept_velocity(v2(0.0, 0.0), v2(5.0, 0.0), v2(-2.0, 0.0), 2.0).is_some());
+    }
+
+    #[test]
+    fn test_stopping_impulse_immovable() {
+        let normal = DirVec2::new(v2(-1.0, 0.0), 0.5);
+        assert_eq!(stopping_impulse(v2(2.0, 1.0), normal, 0.5, 0.0), 4.0);
+        assert_eq!(stopping_impulse(v2(-2.0, 1.0), normal, 0.5, 0.0), 0.0);
+        assert_eq!(stopping_impulse(v2(2.0, 1.0), normal, 0.0, 0.0), 0.0);
+    }
+}
@@ -12,10 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use float::consts::PI;
+use float::Float;
 use std::cmp::Ordering;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::ops::Deref;
+use std::{array, iter};
 
 use core::{HbVel, Hitbox};
-use float::n64;
 use geom::{v2, Card, CardMask, DirVec2, Vec2};
 
 mod normals;
@@ -24,30 +29,119 @@ mod tests;
 
 /// Enumeration of kinds of shapes used by Collider.
 #[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ShapeKind {
     /// Circle.  Requires width and height to match.
     Circle,
     /// Axis-aligned rectangle.
     Rect,
+    /// Line segment, centered on its position. The width and height are the
+    /// vector from one end of the segment to the other, and are allowed to be
+    /// negative.
+    Segment,
+    /// Axis-aligned capsule (stadium), which is a rectangle with semicircles on
+    /// its two shorter ends. The radius of the semicircles is half of the
+    /// smaller of the width and height.
+    Capsule,
+    /// Right triangle with axis-aligned legs, filling half of its bounding
+    /// box. The corner of the bounding box at `pos + dims * 0.5` is cut off by
+    /// the hypotenuse, so the width and height are allowed to be negative to
+    /// select one of four orientations.
+    Triangle,
+    /// Rectangle rotated by an angle about its center. The width and height are
+    /// measured along the rotated axes. The rotation only changes over time if
+    /// the hitbox has an angular velocity (see `HbVel::angular`).
+    OrientedRect,
+    /// Point, with a width and height of zero. Behaves as a circle with a
+    /// diameter of zero, and is exempt from the minimum hitbox size.
+    Point,
+    /// The region outside of an axis-aligned rectangle, which acts as a hole.
+    /// Another shape overlaps an inverted rect when any part of it lies
+    /// outside of (or on the boundary of) the rectangle, so a hitbox inside of
+    /// the rectangle collides with it when crossing the boundary outward.
+    InvertedRect,
 }
 
 /// Represents a shape, without any position.
 ///
 /// Each shape has a `width` and `height`, which are allowed to be negative.
 #[derive(PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawShape"))]
 pub struct Shape {
     kind: ShapeKind,
     dims: Vec2,
+    // unit vector along the width of an `OrientedRect`, and `(1, 0)` otherwise
+    dir: Vec2,
+}
+
+// unvalidated form of `Shape`, so that deserialization upholds the same
+// invariants as `Shape::new`
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct RawShape {
+    kind: ShapeKind,
+    dims: Vec2,
+    #[serde(default = "axis_dir")]
+    dir: Vec2,
+}
+
+#[cfg(feature = "serde")]
+fn axis_dir() -> Vec2 {
+    v2(1.0, 0.0)
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawShape> for Shape {
+    type Error = &'static str;
+
+    fn try_from(raw: RawShape) -> Result<Shape, &'static str> {
+        if !raw.kind.allows_negative_dims() && (raw.dims.x < 0.0 || raw.dims.y < 0.0) {
+            Err("dims must be non-negative")
+        } else if raw.kind == ShapeKind::Circle && raw.dims.x != raw.dims.y {
+            Err("circle width must equal height")
+        } else if raw.kind == ShapeKind::Point && raw.dims != Vec2::zero() {
+            Err("point dims must be zero")
+        } else if raw.kind == ShapeKind::OrientedRect {
+            let dir = raw.dir.normalize().ok_or("orientation must be non-zero")?;
+            Ok(Shape {
+                kind: raw.kind,
+                dims: raw.dims,
+                dir,
+            })
+        } else {
+            Ok(Shape::with_any_dims(raw.kind, raw.dims))
+        }
+    }
+}
+
+impl ShapeKind {
+    // segments and triangles use the signs of their dims for orientation
+    pub(crate) fn allows_negative_dims(self) -> bool {
+        self == ShapeKind::Segment || self == ShapeKind::Triangle
+    }
+
+    // triangles and oriented rects are convex polygons described by their
+    // vertices, rather than by their bounding boxes
+    pub(crate) fn is_convex_poly(self) -> bool {
+        self == ShapeKind::Triangle || self == ShapeKind::OrientedRect
+    }
 }
 
 impl Shape {
     /// Constructs a new shape with the given `kind` and `dims` (width and
     /// height dimensions).
     ///
-    /// Dimensions must be non-negative. If `kind` is `Circle`, then the width
-    /// and height must match.
+    /// Dimensions must be non-negative, unless `kind` is `Segment` or
+    /// `Triangle`. If `kind` is `Circle`, then the width and height must match,
+    /// and if `kind` is `Point`, then they must be zero. If `kind` is
+    /// `OrientedRect`, then the shape is not rotated (use
+    /// `Shape::oriented_rect` to specify a rotation).
     pub fn new(kind: ShapeKind, dims: Vec2) -> Shape {
-        assert!(dims.x >= 0.0 && dims.y >= 0.0, "dims must be non-negative");
+        assert!(
+            kind.allows_negative_dims() || (dims.x >= 0.0 && dims.y >= 0.0),
+            "dims must be non-negative"
+        );
         Shape::with_any_dims(kind, dims)
     }
 
@@ -55,16 +149,32 @@ impl Shape {
     fn with_any_dims(kind: ShapeKind, dims: Vec2) -> Shape {
         if kind == ShapeKind::Circle {
             assert_eq!(dims.x, dims.y, "circle width must equal height");
+        } else if kind == ShapeKind::Point {
+            assert!(dims == Vec2::zero(), "point dims must be zero");
+        }
+        Shape {
+            kind,
+            dims,
+            dir: v2(1.0, 0.0),
         }
-        Shape { kind, dims }
     }
 
     /// Constructs a new circle shape, using `diam` as the width and height.
     #[inline]
-    pub fn circle(diam: f64) -> Shape {
+    pub fn circle(diam: Float) -> Shape {
         Shape::new(ShapeKind::Circle, v2(diam, diam))
     }
 
+    /// Constructs a new point shape, which has a width and height of zero.
+    ///
+    /// Points are useful as markers, and are not subject to the minimum hitbox
+    /// size. They behave as circles with a diameter of zero, so two points only
+    /// overlap if they coincide exactly.
+    #[inline]
+    pub fn point() -> Shape {
+        Shape::new(ShapeKind::Point, Vec2::zero())
+    }
+
     /// Constructs a new axis-aligned rectangle shape with the given `dims`
     /// (width and height dimensions).
     #[inline]
@@ -74,10 +184,75 @@ impl Shape {
 
     /// Constructs a new axis-aligned square shape with the given `width`.
     #[inline]
-    pub fn square(width: f64) -> Shape {
+    pub fn square(width: Float) -> Shape {
         Shape::new(ShapeKind::Rect, v2(width, width))
     }
 
+    /// Constructs a new line segment shape, where `end` is the vector from one
+    /// end of the segment to the other.
+    ///
+    /// When placed at `pos`, the segment runs from `pos - end * 0.5` to
+    /// `pos + end * 0.5`. A zero-length segment is treated as a point.
+    #[inline]
+    pub fn segment(end: Vec2) -> Shape {
+        Shape::new(ShapeKind::Segment, end)
+    }
+
+    /// Constructs a new axis-aligned capsule shape with the given `dims` (width
+    /// and height dimensions).
+    ///
+    /// The ends along the longer dimension are rounded with a radius of half of
+    /// the shorter dimension, so a capsule with a matching width and height is
+    /// a circle.
+    #[inline]
+    pub fn capsule(dims: Vec2) -> Shape {
+        Shape::new(ShapeKind::Capsule, dims)
+    }
+
+    /// Constructs a new right triangle shape with axis-aligned legs, filling
+    /// half of a bounding box with the width and height of `dims`.
+    ///
+    /// When placed at `pos`, the right angle is at `pos - dims * 0.5`, and the
+    /// hypotenuse cuts off the corner of the bounding box at `pos + dims * 0.5`.
+    /// For example, `Shape::triangle(v2(-2.0, 1.0))` is a slope rising to the
+    /// right, with its right angle at the bottom-right corner.
+    #[inline]
+    pub fn triangle(dims: Vec2) -> Shape {
+        Shape::new(ShapeKind::Triangle, dims)
+    }
+
+    /// Constructs a new inverted rectangle shape, which is the region outside
+    /// of an axis-aligned rectangle with the given `dims` (width and height
+    /// dimensions).
+    ///
+    /// This is useful for keeping hitboxes inside of an arena without placing a
+    /// wall hitbox along each edge. The bounds of the shape (e.g. `min_x`) are
+    /// those of the rectangle. Since hitboxes are only tested against nearby
+    /// hitboxes, a hitbox that lies entirely outside of the rectangle might not
+    /// be found to overlap it.
+    #[inline]
+    pub fn inverted_rect(dims: Vec2) -> Shape {
+        Shape::new(ShapeKind::InvertedRect, dims)
+    }
+
+    /// Constructs a new rectangle shape with the given `dims` (width and height
+    /// dimensions), rotated counter-clockwise by `angle` radians about its
+    /// center.
+    ///
+    /// The width is measured along the direction of `angle`, and the height is
+    /// measured perpendicular to it. If `angle` is zero, then this is
+    /// equivalent to `Shape::rect(dims)`. A hitbox with an `HbVel::angular`
+    /// velocity rotates onward from this angle. Oriented rects are not allowed
+    /// to resize.
+    pub fn oriented_rect(dims: Vec2, angle: Float) -> Shape {
+        if angle == 0.0 {
+            return Shape::rect(dims);
+        }
+        let mut result = Shape::new(ShapeKind::OrientedRect, dims);
+        result.dir = v2(angle.cos(), angle.sin());
+        result
+    }
+
     /// Returns the kind of shape.
     #[inline]
     pub fn kind(&self) -> ShapeKind {
@@ -90,19 +265,107 @@ impl Shape {
         self.dims
     }
 
+    /// Returns the unit vector along the width of the shape.
+    ///
+    /// This is `(1, 0)` for every kind of shape other than `OrientedRect`.
+    #[inline]
+    pub fn orientation(&self) -> Vec2 {
+        self.dir
+    }
+
+    /// Returns the area of the shape.
+    ///
+    /// Segments and points have an area of zero, and inverted rects have an
+    /// infinite area.
+    pub fn area(&self) -> Float {
+        let dims = self.dims;
+        match self.kind {
+            ShapeKind::Circle => PI * dims.x * dims.x * 0.25,
+            ShapeKind::Rect | ShapeKind::OrientedRect => dims.x * dims.y,
+            ShapeKind::Capsule => {
+                let (short, long) = (dims.x.min(dims.y), dims.x.max(dims.y));
+                PI * short * short * 0.25 + (long - short) * short
+            }
+            ShapeKind::Triangle => (dims.x * dims.y).abs() * 0.5,
+            ShapeKind::Segment | ShapeKind::Point => 0.0,
+            ShapeKind::InvertedRect => Float::INFINITY,
+        }
+    }
+
+    /// Returns the length of the boundary of the shape.
+    ///
+    /// The perimeter of a segment is twice its length, as if it were a
+    /// rectangle with zero height. Points have a perimeter of zero.
+    pub fn perimeter(&self) -> Float {
+        let dims = self.dims;
+        match self.kind {
+            ShapeKind::Circle => PI * dims.x,
+            ShapeKind::Rect | ShapeKind::OrientedRect | ShapeKind::InvertedRect => {
+                (dims.x + dims.y) * 2.0
+            }
+            ShapeKind::Capsule => {
+                let (short, long) = (dims.x.min(dims.y), dims.x.max(dims.y));
+                PI * short + (long - short) * 2.0
+            }
+            ShapeKind::Triangle => dims.x.abs() + dims.y.abs() + dims.len(),
+            ShapeKind::Segment => dims.len() * 2.0,
+            ShapeKind::Point => 0.0,
+        }
+    }
+
     /// Shorthand for `PlacedShape::new(pos, self)`.
     #[inline]
     pub fn place(self, pos: Vec2) -> PlacedShape {
         PlacedShape::new(pos, self)
     }
 
-    pub(crate) fn advance(&self, resize_vel: Vec2, elapsed: f64) -> Shape {
-        Shape::with_any_dims(self.kind, self.dims + resize_vel * elapsed)
+    pub(crate) fn advance(&self, resize_vel: Vec2, elapsed: Float) -> Shape {
+        self.with_dims(self.dims + resize_vel * elapsed)
+    }
+
+    // returns the shape rotated counter-clockwise by `angle` about its center,
+    // where a rect becomes an oriented rect and a segment turns its dims, while
+    // circles, points, and inverted rects are unchanged
+    pub(crate) fn rotated(&self, angle: Float) -> Shape {
+        match self.kind {
+            _ if angle == 0.0 => *self,
+            ShapeKind::Rect | ShapeKind::OrientedRect => Shape {
+                kind: ShapeKind::OrientedRect,
+                dims: self.dims,
+                dir: self.dir.rotate(angle).normalize().unwrap(),
+            },
+            ShapeKind::Segment => Shape::with_any_dims(ShapeKind::Segment, self.dims.rotate(angle)),
+            ShapeKind::Capsule | ShapeKind::Triangle => {
+                panic!("capsules and triangles cannot rotate")
+            }
+            ShapeKind::Circle | ShapeKind::Point | ShapeKind::InvertedRect => *self,
+        }
+    }
+
+    // whether a hitbox of this kind is rotated by `HbVel::angular`
+    pub(crate) fn rotates(&self) -> bool {
+        matches!(
+            self.kind,
+            ShapeKind::Rect | ShapeKind::OrientedRect | ShapeKind::Segment
+        )
+    }
+
+    // returns a shape of the same kind and orientation with the given dims,
+    // where a point with non-zero dims becomes a circle
+    pub(crate) fn with_dims(&self, dims: Vec2) -> Shape {
+        if self.kind == ShapeKind::Point && dims != Vec2::zero() {
+            return Shape::with_any_dims(ShapeKind::Circle, dims);
+        }
+        Shape {
+            dir: self.dir,
+            ..Shape::with_any_dims(self.kind, dims)
+        }
     }
 }
 
 /// Represents a shape with a position.
 #[derive(PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PlacedShape {
     /// The position of the center of the shape.
     pub pos: Vec2,
@@ -129,23 +392,42 @@ impl PlacedShape {
         self.shape.dims()
     }
 
+    // points behave as circles with a diameter of zero
+    pub(crate) fn point_as_circle(&self) -> PlacedShape {
+        if self.kind() == ShapeKind::Point {
+            Shape::circle(0.0).place(self.pos)
+        } else {
+            *self
+        }
+    }
+
+    /// Returns true if the two shapes are of the same kind, and their
+    /// positions, dims, and orientations are equal within `eps` (see
+    /// `Vec2::approx_eq`).
+    pub fn approx_eq(&self, other: &PlacedShape, eps: Float) -> bool {
+        self.kind() == other.kind()
+            && self.pos.approx_eq(other.pos, eps)
+            && self.dims().approx_eq(other.dims(), eps)
+            && self.shape.dir.approx_eq(other.shape.dir, eps)
+    }
+
     /// Returns the lowest x coordinate of the `PlacedShape`.
-    pub fn min_x(&self) -> f64 {
+    pub fn min_x(&self) -> Float {
         self.bounds_left()
     }
 
     /// Returns the lowest y coordinate of the `PlacedShape`.
-    pub fn min_y(&self) -> f64 {
+    pub fn min_y(&self) -> Float {
         self.bounds_bottom()
     }
 
     /// Returns the highest x coordinate of the `PlacedShape`.
-    pub fn max_x(&self) -> f64 {
+    pub fn max_x(&self) -> Float {
         self.bounds_right()
     }
 
     /// Returns the highest y coordinate of the `PlacedShape`.
-    pub fn max_y(&self) -> f64 {
+    pub fn max_y(&self) -> Float {
         self.bounds_top()
     }
 
@@ -155,6 +437,76 @@ impl PlacedShape {
         self.normal_from(other).len() >= 0.0
     }
 
+    /// Returns `true` if `point` lies within the shape.
+    ///
+    /// Points exactly on the boundary of the shape are contained.
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        match self.kind() {
+            ShapeKind::Circle | ShapeKind::Point => {
+                let radius = self.dims().x * 0.5;
+                self.pos.dist_sq(&point) <= radius * radius
+            }
+            ShapeKind::Rect => {
+                point.x >= self.min_x()
+                    && point.x <= self.max_x()
+                    && point.y >= self.min_y()
+                    && point.y <= self.max_y()
+            }
+            ShapeKind::Segment => normals::segment_closest_point(self, point) == point,
+            ShapeKind::Capsule => {
+                let (core, radius) = self.capsule_core();
+                normals::segment_closest_point(&core, point).dist_sq(&point) <= radius * radius
+            }
+            ShapeKind::Triangle | ShapeKind::OrientedRect => {
+                normals::convex_edge_dist(self, point) <= 0.0
+            }
+            ShapeKind::InvertedRect => {
+                point.x <= self.min_x()
+                    || point.x >= self.max_x()
+                    || point.y <= self.min_y()
+                    || point.y >= self.max_y()
+            }
+        }
+    }
+
+    /// Returns the distance from `point` to the nearest point of the shape.
+    ///
+    /// If `point` is inside the shape, then the distance is negative, and its
+    /// magnitude is the distance from `point` to the boundary of the shape.
+    pub fn distance_to_point(&self, point: Vec2) -> Float {
+        match self.kind() {
+            ShapeKind::Circle | ShapeKind::Point => self.pos.dist(&point) - self.dims().x * 0.5,
+            ShapeKind::Rect => {
+                let offset = point - self.pos;
+                let outside = v2(
+                    offset.x.abs() - self.dims().x * 0.5,
+                    offset.y.abs() - self.dims().y * 0.5,
+                );
+                if outside.x > 0.0 || outside.y > 0.0 {
+                    v2(outside.x.max(0.0), outside.y.max(0.0)).len()
+                } else {
+                    outside.x.max(outside.y)
+                }
+            }
+            ShapeKind::Segment => normals::segment_closest_point(self, point).dist(&point),
+            ShapeKind::Capsule => {
+                let (core, radius) = self.capsule_core();
+                normals::segment_closest_point(&core, point).dist(&point) - radius
+            }
+            ShapeKind::Triangle | ShapeKind::OrientedRect => {
+                let dist = normals::convex_edge_dist(self, point);
+                if dist <= 0.0 {
+                    dist
+                } else {
+                    normals::poly_closest_point(self, point).dist(&point)
+                }
+            }
+            ShapeKind::InvertedRect => -Shape::rect(self.dims())
+                .place(self.pos)
+                .distance_to_point(point),
+        }
+    }
+
     /// Returns a normal vector that points in the direction from `other` to
     /// `self`.
     ///
@@ -166,25 +518,96 @@ impl PlacedShape {
     /// `other`.
     ///
     /// (As a minor caveat, when computing the normal between two `Rect` shapes,
-    /// the direction will always be axis-aligned.)
+    /// the direction will always be axis-aligned. Similarly, when a `Segment`
+    /// is paired with a `Rect` or another `Segment`, the direction will be
+    /// axis-aligned or along/perpendicular to one of the segments, and the
+    /// negative length for non-overlapping shapes may underestimate the
+    /// distance. The same applies to a `Capsule` whose straight core overlaps a
+    /// `Rect`, `Segment`, or the core of another `Capsule`, and to a `Triangle`
+    /// or `OrientedRect` paired with a `Rect`, `Segment`, `Triangle`, or
+    /// `OrientedRect`, where the direction is perpendicular to an edge of one
+    /// of the shapes. When an `InvertedRect` is involved, the direction is
+    /// determined by how far the bounding box of the other shape extends past
+    /// the edges of the rectangle.)
     pub fn normal_from(&self, other: &PlacedShape) -> DirVec2 {
         match (self.kind(), other.kind()) {
+            (ShapeKind::InvertedRect, _) => normals::inverted_rect_normal(self, other),
+            (_, ShapeKind::InvertedRect) => normals::inverted_rect_normal(other, self).flip(),
+            (ShapeKind::Point, _) | (_, ShapeKind::Point) => {
+                self.point_as_circle().normal_from(&other.point_as_circle())
+            }
+            (ShapeKind::Capsule, _) => normals::capsule_normal(self, other),
+            (_, ShapeKind::Capsule) => normals::capsule_normal(other, self).flip(),
+            (ShapeKind::Triangle, ShapeKind::Circle)
+            | (ShapeKind::OrientedRect, ShapeKind::Circle) => {
+                normals::poly_circle_normal(self, other)
+            }
+            (ShapeKind::Circle, ShapeKind::Triangle)
+            | (ShapeKind::Circle, ShapeKind::OrientedRect) => {
+                normals::poly_circle_normal(other, self).flip()
+            }
             (ShapeKind::Rect, ShapeKind::Rect) => normals::rect_rect_normal(self, other),
             (ShapeKind::Rect, ShapeKind::Circle) => normals::rect_circle_normal(self, other),
             (ShapeKind::Circle, ShapeKind::Rect) => normals::rect_circle_normal(other, self).flip(),
             (ShapeKind::Circle, ShapeKind::Circle) => normals::circle_circle_normal(self, other),
+            (ShapeKind::Segment, ShapeKind::Circle) => normals::segment_circle_normal(self, other),
+            (ShapeKind::Circle, ShapeKind::Segment) => {
+                normals::segment_circle_normal(other, self).flip()
+            }
+            (ShapeKind::Segment, _)
+            | (_, ShapeKind::Segment)
+            | (ShapeKind::Triangle, _)
+            | (_, ShapeKind::Triangle)
+            | (ShapeKind::OrientedRect, _)
+            | (_, ShapeKind::OrientedRect) => normals::segment_poly_normal(self, other),
         }
     }
 
+    /// Returns the distance between the two shapes, which is negative if they
+    /// are overlapping.
+    ///
+    /// This is the negated length of `normal_from`, so the magnitude of a
+    /// negative distance is the overlap depth. For example, the distance
+    /// between two circles is the distance between their centers minus their
+    /// radii. The same caveats as `normal_from` apply, so the distance between
+    /// non-overlapping shapes may be underestimated for some combinations of
+    /// shapes.
+    pub fn distance(&self, other: &PlacedShape) -> Float {
+        -self.normal_from(other).len()
+    }
+
     /// Returns a normal vector like `normal_from`, but only certain normal
     /// directions are permitted.
     ///
     /// A normal vector with a cardinal component that is not present in the
     /// `mask` will not be returned, and the next-in-line normal vector will be
-    /// used instead. This function panics if `mask` is empty, or if both shapes
-    /// are circles and `mask` is anything but full.
+    /// used instead. If both shapes are circles, the normal is instead
+    /// projected onto the permitted cardinal direction that is nearest to the
+    /// true normal. This function panics if `mask` is empty, or if either
+    /// shape is a segment, capsule, triangle, oriented rect, or inverted rect
+    /// and `mask` is anything but full. Points are treated as circles.
     pub fn masked_normal_from(&self, other: &PlacedShape, mask: CardMask) -> DirVec2 {
         match (self.kind(), other.kind()) {
+            (ShapeKind::Point, _) | (_, ShapeKind::Point) => self
+                .point_as_circle()
+                .masked_normal_from(&other.point_as_circle(), mask),
+            (ShapeKind::Segment, _)
+            | (_, ShapeKind::Segment)
+            | (ShapeKind::Capsule, _)
+            | (_, ShapeKind::Capsule)
+            | (ShapeKind::Triangle, _)
+            | (_, ShapeKind::Triangle)
+            | (ShapeKind::OrientedRect, _)
+            | (_, ShapeKind::OrientedRect)
+            | (ShapeKind::InvertedRect, _)
+            | (_, ShapeKind::InvertedRect) => {
+                assert!(
+                    mask == CardMask::full(),
+                    "CardMask for segment, capsule, triangle, oriented rect, or inverted rect \
+                     normal must be full"
+                );
+                self.normal_from(other)
+            }
             (ShapeKind::Rect, ShapeKind::Rect) => {
                 normals::masked_rect_rect_normal(self, other, mask)
             }
@@ -206,9 +629,57 @@ impl PlacedShape {
     /// shapes.
     pub fn contact_point(&self, other: &PlacedShape) -> Vec2 {
         match (self.kind(), other.kind()) {
+            (ShapeKind::InvertedRect, _) => normals::inverted_rect_contact(self, other),
+            (_, ShapeKind::InvertedRect) => normals::inverted_rect_contact(other, self),
+            (ShapeKind::Point, _) | (_, ShapeKind::Point) => self
+                .point_as_circle()
+                .contact_point(&other.point_as_circle()),
             (ShapeKind::Rect, ShapeKind::Rect) => normals::rect_rect_contact(self, other),
             (ShapeKind::Circle, _) => normals::circle_any_contact(self, other),
-            (ShapeKind::Rect, ShapeKind::Circle) => normals::circle_any_contact(other, self),
+            (ShapeKind::Capsule, _) => normals::capsule_any_contact(self, other),
+            (_, ShapeKind::Capsule) => normals::capsule_any_contact(other, self),
+            (_, ShapeKind::Circle) => normals::circle_any_contact(other, self),
+            (ShapeKind::Triangle, _)
+            | (_, ShapeKind::Triangle)
+            | (ShapeKind::OrientedRect, _)
+            | (_, ShapeKind::OrientedRect) => normals::poly_any_contact(self, other),
+            (ShapeKind::Segment, _) => normals::segment_any_contact(self, other),
+            (ShapeKind::Rect, ShapeKind::Segment) => normals::segment_any_contact(other, self),
+        }
+    }
+
+    /// Returns the endpoints of the segment where two rectangles touch, or
+    /// `None` if either shape is not a `Rect` or the shapes are not
+    /// overlapping.
+    ///
+    /// The segment runs through the middle of the overlap between the shapes,
+    /// perpendicular to `normal_from`. For rectangles resting against each
+    /// other, these are the two ends of the shared edge.
+    pub fn contact_segment(&self, other: &PlacedShape) -> Option<(Vec2, Vec2)> {
+        match (self.kind(), other.kind()) {
+            (ShapeKind::Rect, ShapeKind::Rect) => normals::rect_rect_contact_segment(self, other),
+            _ => None,
+        }
+    }
+
+    /// Returns the distance from `origin` along the direction `dir` to the
+    /// first point of the shape, or `None` if the shape is not hit within
+    /// `max_dist`.
+    ///
+    /// Returns `Some(0.0)` if `origin` is inside the shape. Panics if `dir` is
+    /// the zero vector.
+    pub fn ray_dist(&self, origin: Vec2, dir: Vec2, max_dist: Float) -> Option<Float> {
+        let dir = dir.normalize().expect("ray direction must be non-zero");
+        match self.kind() {
+            ShapeKind::Circle | ShapeKind::Point => {
+                normals::circle_ray_dist(self, origin, dir, max_dist)
+            }
+            ShapeKind::Rect
+            | ShapeKind::Segment
+            | ShapeKind::Triangle
+            | ShapeKind::OrientedRect => normals::poly_ray_dist(self, origin, dir, max_dist),
+            ShapeKind::Capsule => normals::capsule_ray_dist(self, origin, dir, max_dist),
+            ShapeKind::InvertedRect => normals::inverted_rect_ray_dist(self, origin, dir, max_dist),
         }
     }
 
@@ -220,7 +691,7 @@ impl PlacedShape {
 
     /// Shorthand for `Hitbox::new(self, HbVel::moving_until(vel, end_time))`.
     #[inline]
-    pub fn moving_until(self, vel: Vec2, end_time: f64) -> Hitbox {
+    pub fn moving_until(self, vel: Vec2, end_time: Float) -> Hitbox {
         Hitbox::new(self, HbVel::moving_until(vel, end_time))
     }
 
@@ -232,7 +703,7 @@ impl PlacedShape {
 
     /// Shorthand for `Hitbox::new(self, HbVel::still_until(end_time))`.
     #[inline]
-    pub fn still_until(self, end_time: f64) -> Hitbox {
+    pub fn still_until(self, end_time: Float) -> Hitbox {
         Hitbox::new(self, HbVel::still_until(end_time))
     }
 
@@ -242,47 +713,154 @@ impl PlacedShape {
         Sector::new(x, y)
     }
 
-    pub(crate) fn as_rect(&self) -> PlacedShape {
-        PlacedShape::new(self.pos, Shape::rect(self.shape.dims()))
+    // returns the straight segment at the center of a capsule and the radius
+    // of the capsule, so that the capsule is every point within that radius of
+    // the segment
+    pub(crate) fn capsule_core(&self) -> (PlacedShape, Float) {
+        let dims = self.dims();
+        let end = if dims.x >= dims.y {
+            v2(dims.x - dims.y, 0.0)
+        } else {
+            v2(0.0, dims.y - dims.x)
+        };
+        (
+            PlacedShape::new(self.pos, Shape::segment(end)),
+            dims.x.min(dims.y) * 0.5,
+        )
+    }
+
+    // returns the three vertices of a triangle, starting with the right angle
+    pub(crate) fn triangle_vertices(&self) -> [Vec2; 3] {
+        let half = self.dims() * 0.5;
+        [
+            self.pos - half,
+            self.pos + v2(half.x, -half.y),
+            self.pos + v2(-half.x, half.y),
+        ]
+    }
+
+    // returns the unit vector perpendicular to the hypotenuse of a triangle,
+    // pointing away from the triangle
+    pub(crate) fn triangle_hypot_normal(&self) -> Vec2 {
+        let dims = self.dims();
+        v2(
+            dims.x.signum() * dims.y.abs(),
+            dims.y.signum() * dims.x.abs(),
+        )
+        .normalize()
+        .unwrap_or(v2(1.0, 0.0))
+    }
+
+    // returns the four vertices of an oriented rect in counter-clockwise order
+    pub(crate) fn oriented_rect_vertices(&self) -> [Vec2; 4] {
+        let dir = self.shape.dir;
+        let half_width = dir * (self.dims().x * 0.5);
+        let half_height = v2(-dir.y, dir.x) * (self.dims().y * 0.5);
+        [
+            self.pos - half_width - half_height,
+            self.pos + half_width - half_height,
+            self.pos + half_width + half_height,
+            self.pos - half_width + half_height,
+        ]
+    }
+
+    // returns the vertices of a triangle or oriented rect
+    pub(crate) fn convex_vertices(&self) -> ConvexList<Vec2> {
+        match self.kind() {
+            ShapeKind::Triangle => ConvexList::new(&self.triangle_vertices()),
+            ShapeKind::OrientedRect => ConvexList::new(&self.oriented_rect_vertices()),
+            _ => panic!("expected triangle or oriented rect"),
+        }
     }
 
-    pub(crate) fn bounding_box(&self, other: &PlacedShape) -> PlacedShape {
-        let right = self.max_x().max(other.max_x());
-        let top = self.max_y().max(other.max_y());
-        let left = self.min_x().min(other.min_x());
-        let bottom = self.min_y().min(other.min_y());
+    // returns the unit vectors perpendicular to the edges of a triangle or
+    // oriented rect, omitting opposite directions of parallel edges
+    pub(crate) fn convex_axes(&self) -> ConvexList<Vec2> {
+        match self.kind() {
+            ShapeKind::Triangle => {
+                ConvexList::new(&[v2(1.0, 0.0), v2(0.0, 1.0), self.triangle_hypot_normal()])
+            }
+            ShapeKind::OrientedRect => {
+                let dir = self.shape.dir;
+                ConvexList::new(&[dir, v2(-dir.y, dir.x)])
+            }
+            _ => panic!("expected triangle or oriented rect"),
+        }
+    }
 
-        let shape = Shape::rect(v2(right - left, top - bottom));
-        let pos = v2(left + shape.dims().x * 0.5, bottom + shape.dims().y * 0.5);
-        PlacedShape::new(pos, shape)
+    // returns the range of the projection of a triangle or oriented rect onto
+    // `dir`
+    pub(crate) fn convex_extent(&self, dir: Vec2) -> (Float, Float) {
+        self.convex_vertices()
+            .iter()
+            .map(|&vertex| vertex * dir)
+            .fold(
+                (Float::INFINITY, Float::NEG_INFINITY),
+                |(min, max), proj| (min.min(proj), max.max(proj)),
+            )
+    }
+
+    // returns a triangle with each edge moved outwards by `padding`, which
+    // has the same angles and incenter as this triangle
+    pub(crate) fn padded_triangle(&self, padding: Float) -> PlacedShape {
+        let dims = self.dims();
+        let legs = dims.abs();
+        let in_radius = (legs.x + legs.y - legs.len()) * 0.5;
+        let signs = v2(dims.x.signum(), dims.y.signum());
+        let in_center = self.pos - dims * 0.5 + signs * in_radius;
+        let new_dims = dims * ((in_radius + padding) / in_radius);
+        let right_angle = in_center - signs * (in_radius + padding);
+        PlacedShape::new(right_angle + new_dims * 0.5, Shape::triangle(new_dims))
     }
 
-    pub(crate) fn advance(&self, vel: Vec2, resize_vel: Vec2, elapsed: f64) -> PlacedShape {
+    pub(crate) fn as_rect(&self) -> PlacedShape {
+        PlacedShape::new(self.pos, Shape::rect(self.bounds_dims()))
+    }
+
+    pub(crate) fn advance(
+        &self,
+        vel: Vec2,
+        resize_vel: Vec2,
+        anchor: Option<Card>,
+        elapsed: Float,
+    ) -> PlacedShape {
         PlacedShape::new(
-            self.pos + vel * elapsed,
+            self.pos + (vel + anchor_shift(resize_vel, anchor)) * elapsed,
             self.shape.advance(resize_vel, elapsed),
         )
     }
 }
 
+// the change in position needed to keep the `anchor` edge of a shape in place
+// as its dims change by `resize`
+pub(crate) fn anchor_shift(resize: Vec2, anchor: Option<Card>) -> Vec2 {
+    match anchor {
+        None => Vec2::zero(),
+        Some(Card::MinusX) => v2(resize.x * 0.5, 0.0),
+        Some(Card::PlusX) => v2(resize.x * -0.5, 0.0),
+        Some(Card::MinusY) => v2(0.0, resize.y * 0.5),
+        Some(Card::PlusY) => v2(0.0, resize.y * -0.5),
+    }
+}
+
 pub(crate) trait PlacedBounds {
-    fn bounds_center(&self) -> &Vec2;
-    fn bounds_dims(&self) -> &Vec2;
+    fn bounds_center(&self) -> Vec2;
+    fn bounds_dims(&self) -> Vec2;
 
-    fn bounds_bottom(&self) -> f64 {
+    fn bounds_bottom(&self) -> Float {
         self.bounds_center().y - self.bounds_dims().y * 0.5
     }
-    fn bounds_left(&self) -> f64 {
+    fn bounds_left(&self) -> Float {
         self.bounds_center().x - self.bounds_dims().x * 0.5
     }
-    fn bounds_top(&self) -> f64 {
+    fn bounds_top(&self) -> Float {
         self.bounds_center().y + self.bounds_dims().y * 0.5
     }
-    fn bounds_right(&self) -> f64 {
+    fn bounds_right(&self) -> Float {
         self.bounds_center().x + self.bounds_dims().x * 0.5
     }
 
-    fn edge(&self, card: Card) -> f64 {
+    fn edge(&self, card: Card) -> Float {
         match card {
             Card::MinusY => -self.bounds_bottom(),
             Card::MinusX => -self.bounds_left(),
@@ -291,15 +869,7 @@ pub(crate) trait PlacedBounds {
         }
     }
 
-    fn max_edge(&self) -> f64 {
-        Card::values()
-            .iter()
-            .map(|&card| self.edge(card).abs())
-            .max_by_key(|&edge| n64(edge))
-            .unwrap()
-    }
-
-    fn card_overlap(&self, src: &Self, card: Card) -> f64 {
+    fn card_overlap(&self, src: &Self, card: Card) -> Float {
         src.edge(card) + self.edge(card.flip())
     }
 
@@ -319,15 +889,26 @@ pub(crate) trait PlacedBounds {
 }
 
 impl PlacedBounds for PlacedShape {
-    fn bounds_center(&self) -> &Vec2 {
-        &self.pos
-    }
-    fn bounds_dims(&self) -> &Vec2 {
-        &self.shape.dims
+    fn bounds_center(&self) -> Vec2 {
+        self.pos
+    }
+    fn bounds_dims(&self) -> Vec2 {
+        if self.kind() == ShapeKind::OrientedRect {
+            let dir = self.shape.dir.abs();
+            let dims = self.shape.dims;
+            v2(
+                dir.x * dims.x + dir.y * dims.y,
+                dir.y * dims.x + dir.x * dims.y,
+            )
+        } else if self.kind().allows_negative_dims() {
+            self.shape.dims.abs()
+        } else {
+            self.shape.dims
+        }
     }
 }
 
-fn interval_sector(left: f64, right: f64, val: f64) -> Ordering {
+fn interval_sector(left: Float, right: Float, val: Float) -> Ordering {
     if val < left {
         Ordering::Less
     } else if val > right {
@@ -371,3 +952,53 @@ impl Sector {
         }
     }
 }
+
+// a list of up to four vertices, edges, or axes of a polygon-like shape, which
+// avoids allocating for these small lists
+#[derive(Copy, Clone)]
+pub(crate) struct ConvexList<T: Copy> {
+    items: [T; 4],
+    len: usize,
+}
+
+impl<T: Copy> ConvexList<T> {
+    pub fn new(items: &[T]) -> ConvexList<T> {
+        assert!(!items.is_empty() && items.len() <= 4);
+        let mut result = ConvexList {
+            items: [items[0]; 4],
+            len: items.len(),
+        };
+        result.items[..items.len()].copy_from_slice(items);
+        result
+    }
+
+    // maps each pair of consecutive vertices, wrapping around from the last
+    // vertex to the first, into an item for the edge between them
+    pub fn map_edges<U: Copy>(&self, mut f: impl FnMut(T, T) -> U) -> ConvexList<U> {
+        let mut items = [f(self.items[0], self.items[1 % self.len]); 4];
+        for (index, item) in items.iter_mut().enumerate().take(self.len).skip(1) {
+            *item = f(self.items[index], self.items[(index + 1) % self.len]);
+        }
+        ConvexList {
+            items,
+            len: self.len,
+        }
+    }
+}
+
+impl<T: Copy> Deref for ConvexList<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items[..self.len]
+    }
+}
+
+impl<T: Copy> IntoIterator for ConvexList<T> {
+    type Item = T;
+    type IntoIter = iter::Take<array::IntoIter<T, 4>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterator::into_iter(self.items).take(self.len)
+    }
+}
@@ -12,8 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use float::n64;
-use geom::shape::{PlacedBounds, Sector};
+use float::{n64, Float};
+use geom::shape::{ConvexList, PlacedBounds, Sector};
 use geom::*;
 
 // This module contains methods to solve for the normal vector
@@ -66,11 +66,19 @@ pub fn masked_circle_circle_normal(
     src: &PlacedShape,
     mask: CardMask,
 ) -> DirVec2 {
-    assert!(
-        mask == CardMask::full(),
-        "CardMask for circle-circle normal must be full"
-    );
-    circle_circle_normal(dst, src)
+    let normal = circle_circle_normal(dst, src);
+    if mask == CardMask::full() {
+        return normal;
+    }
+    // projects the normal onto the permitted card nearest to its direction
+    let (card, dot) = Card::values()
+        .iter()
+        .cloned()
+        .filter(|&card| mask[card])
+        .map(|card| (card, Vec2::from(card) * normal.dir()))
+        .max_by_key(|&(_, dot)| n64(dot))
+        .unwrap_or_else(|| panic!("CardMask must be non-empty"));
+    DirVec2::new(card.into(), normal.len() * dot)
 }
 
 pub fn masked_rect_circle_normal(dst: &PlacedShape, src: &PlacedShape, mask: CardMask) -> DirVec2 {
@@ -105,6 +113,525 @@ pub fn rect_rect_contact(a: &PlacedShape, b: &PlacedShape) -> Vec2 {
     )
 }
 
-fn rect_rect_contact_1d(a_min: f64, a_max: f64, b_min: f64, b_max: f64) -> f64 {
+fn rect_rect_contact_1d(a_min: Float, a_max: Float, b_min: Float, b_max: Float) -> Float {
     0.5 * (a_min.max(b_min) + b_max.min(a_max))
 }
+
+// the overlap of the rects is thinnest along the normal, so the contact segment
+// runs across the overlap perpendicular to the normal, through its middle
+pub fn rect_rect_contact_segment(a: &PlacedShape, b: &PlacedShape) -> Option<(Vec2, Vec2)> {
+    let normal = rect_rect_normal(a, b);
+    if normal.len() < 0.0 {
+        return None;
+    }
+    let min = v2(a.min_x().max(b.min_x()), a.min_y().max(b.min_y()));
+    let max = v2(a.max_x().min(b.max_x()), a.max_y().min(b.max_y()));
+    let mid = (min + max) * 0.5;
+    if normal.dir().x != 0.0 {
+        Some((v2(mid.x, min.y), v2(mid.x, max.y)))
+    } else {
+        Some((v2(min.x, mid.y), v2(max.x, mid.y)))
+    }
+}
+
+pub fn segment_ends(segment: &PlacedShape) -> (Vec2, Vec2) {
+    let half = segment.dims() * 0.5;
+    (segment.pos - half, segment.pos + half)
+}
+
+// returns the unit vector perpendicular to the segment, or None if the segment
+// has zero length
+pub fn segment_perp(segment: &PlacedShape) -> Option<Vec2> {
+    v2(-segment.dims().y, segment.dims().x).normalize()
+}
+
+pub fn segment_closest_point(segment: &PlacedShape, point: Vec2) -> Vec2 {
+    let half = segment.dims() * 0.5;
+    let len_sq = half.len_sq();
+    if len_sq == 0.0 {
+        segment.pos
+    } else {
+        let ratio = ((point - segment.pos) * half / len_sq).clamp(-1.0, 1.0);
+        segment.pos + half * ratio
+    }
+}
+
+pub fn segment_circle_normal(dst: &PlacedShape, src: &PlacedShape) -> DirVec2 {
+    let closest = segment_closest_point(dst, src.pos);
+    let mut dir = closest - src.pos;
+    let dist = dir.len();
+    if dist == 0.0 {
+        dir = segment_perp(dst).unwrap_or(v2(1.0, 0.0));
+    }
+    DirVec2::new(dir, src.dims().x * 0.5 - dist)
+}
+
+// normal between a rect, segment, triangle, or oriented rect and a segment,
+// triangle, or oriented rect (in either order), or between a triangle or
+// oriented rect and a circle containing none of its boundary, computed using
+// the separating axis theorem
+pub fn segment_poly_normal(dst: &PlacedShape, src: &PlacedShape) -> DirVec2 {
+    let mut dirs: Vec<Vec2> = Card::values().iter().map(|&card| card.into()).collect();
+    for shape in &[dst, src] {
+        match shape.kind() {
+            ShapeKind::Segment => {
+                if let Some(perp) = segment_perp(shape) {
+                    let along = v2(perp.y, -perp.x);
+                    dirs.extend_from_slice(&[perp, -perp, along, -along]);
+                }
+            }
+            ShapeKind::Triangle | ShapeKind::OrientedRect => {
+                for axis in shape.convex_axes() {
+                    dirs.extend_from_slice(&[axis, -axis]);
+                }
+            }
+            _ => (),
+        }
+    }
+    let (dir, overlap) = dirs
+        .into_iter()
+        .map(|dir| {
+            let (_, src_max) = extent(src, dir);
+            let (dst_min, _) = extent(dst, dir);
+            (dir, src_max - dst_min)
+        })
+        .min_by_key(|&(_, overlap)| n64(overlap))
+        .unwrap();
+    DirVec2::new(dir, overlap)
+}
+
+// the range of the projection of a shape (other than a capsule) onto `dir`
+fn extent(shape: &PlacedShape, dir: Vec2) -> (Float, Float) {
+    let center = shape.pos * dir;
+    let half_extent = match shape.kind() {
+        ShapeKind::Segment => (shape.dims() * dir).abs() * 0.5,
+        ShapeKind::Circle | ShapeKind::Point => shape.dims().x * 0.5,
+        ShapeKind::Triangle | ShapeKind::OrientedRect => return shape.convex_extent(dir),
+        _ => (dir.x.abs() * shape.dims().x + dir.y.abs() * shape.dims().y) * 0.5,
+    };
+    (center - half_extent, center + half_extent)
+}
+
+// normal between an inverted rect and any shape, found from how far the
+// bounding box of the other shape extends past each edge of the rect, since a
+// shape lies inside of a rect if and only if its bounding box does
+pub fn inverted_rect_normal(dst: &PlacedShape, src: &PlacedShape) -> DirVec2 {
+    if src.kind() == ShapeKind::InvertedRect {
+        // the outsides of two rects always overlap
+        return DirVec2::new(v2(1.0, 0.0), Float::INFINITY);
+    }
+    let past = |card: Card| src.edge(card) - dst.edge(card);
+    let axis_past = |plus: Card, minus: Card| {
+        let (plus_past, minus_past) = (past(plus), past(minus));
+        if plus_past >= minus_past {
+            plus_past.max(0.0)
+        } else {
+            -minus_past.max(0.0)
+        }
+    };
+    // the rect must be moved along each axis that the shape extends past
+    let overlap = v2(
+        axis_past(Card::PlusX, Card::MinusX),
+        axis_past(Card::PlusY, Card::MinusY),
+    );
+    if overlap != Vec2::zero() {
+        DirVec2::new(overlap, overlap.len())
+    } else {
+        let card = Card::values()
+            .iter()
+            .cloned()
+            .max_by_key(|&card| n64(past(card)))
+            .unwrap();
+        DirVec2::new(card.into(), past(card))
+    }
+}
+
+// contact point between an inverted rect and any shape, which lies midway
+// between the edges of the rect and the bounding box of the shape on each side
+// that the normal points towards
+pub fn inverted_rect_contact(inverted: &PlacedShape, other: &PlacedShape) -> Vec2 {
+    let normal = inverted_rect_normal(inverted, other);
+    let mut result = other.pos;
+    for &card in &Card::values() {
+        let dir = Vec2::from(card);
+        if normal.dir() * dir > 0.0 {
+            let mid = (inverted.edge(card) + other.edge(card)) * 0.5;
+            if dir.x != 0.0 {
+                result.x = dir.x * mid;
+            } else {
+                result.y = dir.y * mid;
+            }
+        }
+    }
+    result
+}
+
+// distance along a ray to the boundary of an inverted rect, which is zero if
+// the ray starts outside of the rect
+pub fn inverted_rect_ray_dist(
+    shape: &PlacedShape,
+    origin: Vec2,
+    dir: Vec2,
+    max_dist: Float,
+) -> Option<Float> {
+    if shape.contains_point(origin) {
+        return Some(0.0);
+    }
+    let dist = Card::values()
+        .iter()
+        .filter_map(|&card| {
+            let speed = Vec2::from(card) * dir;
+            if speed > 0.0 {
+                Some((shape.edge(card) - Vec2::from(card) * origin) / speed)
+            } else {
+                None
+            }
+        })
+        .fold(Float::INFINITY, Float::min);
+    if dist <= max_dist {
+        Some(dist)
+    } else {
+        None
+    }
+}
+
+// normal between a rect, segment, triangle, or oriented rect and a circle
+pub fn poly_circle_normal(dst: &PlacedShape, src: &PlacedShape) -> DirVec2 {
+    let closest = poly_closest_point(dst, src.pos);
+    if closest == src.pos {
+        segment_poly_normal(dst, src)
+    } else {
+        DirVec2::new(
+            closest - src.pos,
+            src.dims().x * 0.5 - closest.dist(&src.pos),
+        )
+    }
+}
+
+// returns the outward unit normals of the edges of a triangle or oriented
+// rect, each paired with the projection of that edge onto its normal
+fn convex_edges(shape: &PlacedShape) -> ConvexList<(Vec2, Float)> {
+    let vertices = shape.convex_vertices();
+    let count = vertices.len() as Float;
+    let centroid = vertices
+        .iter()
+        .fold(Vec2::zero(), |sum, &vertex| sum + vertex)
+        * (1.0 / count);
+    vertices.map_edges(|start, end| {
+        let mut normal = v2(end.y - start.y, start.x - end.x)
+            .normalize()
+            .unwrap_or(v2(1.0, 0.0));
+        // the centroid of the shape is on the inner side of every edge
+        if normal * (start - centroid) < 0.0 {
+            normal = -normal;
+        }
+        (normal, normal * start)
+    })
+}
+
+// returns the largest signed distance from `point` to the lines through the
+// edges of a triangle or oriented rect, which is non-positive if and only if
+// the shape contains `point`, and is the negated distance to the boundary in
+// that case
+pub fn convex_edge_dist(shape: &PlacedShape, point: Vec2) -> Float {
+    convex_edges(shape)
+        .iter()
+        .map(|&(normal, offset)| normal * point - offset)
+        .fold(Float::NEG_INFINITY, Float::max)
+}
+
+// returns the edges of a rect, segment, triangle, or oriented rect as segments
+fn poly_edges(shape: &PlacedShape) -> ConvexList<PlacedShape> {
+    if shape.kind() == ShapeKind::Segment {
+        return ConvexList::new(&[*shape]);
+    }
+    let vertices = match shape.kind() {
+        ShapeKind::Triangle | ShapeKind::OrientedRect => shape.convex_vertices(),
+        _ => ConvexList::new(&[
+            v2(shape.min_x(), shape.min_y()),
+            v2(shape.max_x(), shape.min_y()),
+            v2(shape.max_x(), shape.max_y()),
+            v2(shape.min_x(), shape.max_y()),
+        ]),
+    };
+    vertices
+        .map_edges(|start, end| PlacedShape::new(start.lerp(end, 0.5), Shape::segment(end - start)))
+}
+
+// contact point between a triangle or oriented rect and a rect, segment,
+// triangle, or oriented rect, which is
+// the average of the points where the edges of each shape enter and leave the
+// other shape, or the midpoint of the closest points if they do not intersect
+pub fn poly_any_contact(a: &PlacedShape, b: &PlacedShape) -> Vec2 {
+    let mut points = Vec::new();
+    for &(shape, other) in &[(a, b), (b, a)] {
+        for edge in poly_edges(shape) {
+            let (start, end) = segment_ends(&edge);
+            if let Some((min_ratio, max_ratio)) = clip_segment(start, end, other) {
+                points.push(start.lerp(end, min_ratio));
+                points.push(start.lerp(end, max_ratio));
+            }
+        }
+    }
+    if points.is_empty() {
+        let (a, b) = poly_closest_points(a, b);
+        (a + b) * 0.5
+    } else {
+        let count = points.len() as Float;
+        points
+            .into_iter()
+            .fold(Vec2::zero(), |sum, point| sum + point)
+            * (1.0 / count)
+    }
+}
+
+// contact point between a segment and a rect or segment
+pub fn segment_any_contact(segment: &PlacedShape, other: &PlacedShape) -> Vec2 {
+    let (start, end) = segment_ends(segment);
+    if let Some((min_ratio, max_ratio)) = clip_segment(start, end, other) {
+        return start.lerp(end, (min_ratio + max_ratio) * 0.5);
+    }
+    let (a, b) = poly_closest_points(segment, other);
+    (a + b) * 0.5
+}
+
+// returns the closest points on two shapes that are each a rect, segment,
+// triangle, or oriented rect, which are assumed not to intersect
+fn poly_closest_points(shape: &PlacedShape, other: &PlacedShape) -> (Vec2, Vec2) {
+    // the closest points between the shapes include a vertex of one shape
+    let mut pairs = Vec::new();
+    for vertex in poly_vertices(other) {
+        pairs.push((poly_closest_point(shape, vertex), vertex));
+    }
+    for vertex in poly_vertices(shape) {
+        pairs.push((vertex, poly_closest_point(other, vertex)));
+    }
+    pairs
+        .into_iter()
+        .min_by_key(|&(a, b)| n64((a - b).len_sq()))
+        .unwrap()
+}
+
+// normal between a capsule and any shape, found by inflating the normal between
+// the core segment of the capsule and the other shape by the capsule radius
+pub fn capsule_normal(dst: &PlacedShape, src: &PlacedShape) -> DirVec2 {
+    let (core, radius) = dst.capsule_core();
+    let normal = match src.kind() {
+        ShapeKind::Circle => segment_circle_normal(&core, src),
+        ShapeKind::Capsule => {
+            let (src_core, src_radius) = src.capsule_core();
+            let normal = segment_poly_dist_normal(&core, &src_core);
+            DirVec2::new(normal.dir(), normal.len() + src_radius)
+        }
+        _ => segment_poly_dist_normal(&core, src),
+    };
+    DirVec2::new(normal.dir(), normal.len() + radius)
+}
+
+// like `segment_poly_normal`, but uses the exact distance between the shapes
+// when they do not overlap
+fn segment_poly_dist_normal(dst: &PlacedShape, src: &PlacedShape) -> DirVec2 {
+    let normal = segment_poly_normal(dst, src);
+    if normal.len() >= 0.0 {
+        return normal;
+    }
+    let (dst_point, src_point) = poly_closest_points(dst, src);
+    if dst_point == src_point {
+        normal
+    } else {
+        DirVec2::new(dst_point - src_point, -dst_point.dist(&src_point))
+    }
+}
+
+// contact point between a capsule and any shape, treating the capsule as a
+// circle centered on the nearest point of its core segment
+pub fn capsule_any_contact(capsule: &PlacedShape, other: &PlacedShape) -> Vec2 {
+    let (core, radius) = capsule.capsule_core();
+    let target = match other.kind() {
+        ShapeKind::Circle => other.pos,
+        ShapeKind::Capsule => segment_any_contact(&core, &other.capsule_core().0),
+        _ => segment_any_contact(&core, other),
+    };
+    let center = segment_closest_point(&core, target);
+    let normal = capsule.normal_from(other);
+    center + normal.dir() * (normal.len() * 0.5 - radius)
+}
+
+fn poly_vertices(shape: &PlacedShape) -> ConvexList<Vec2> {
+    if shape.kind() == ShapeKind::Segment {
+        let (start, end) = segment_ends(shape);
+        ConvexList::new(&[start, end])
+    } else if shape.kind().is_convex_poly() {
+        shape.convex_vertices()
+    } else {
+        ConvexList::new(&[
+            v2(shape.min_x(), shape.min_y()),
+            v2(shape.min_x(), shape.max_y()),
+            v2(shape.max_x(), shape.min_y()),
+            v2(shape.max_x(), shape.max_y()),
+        ])
+    }
+}
+
+pub fn poly_closest_point(shape: &PlacedShape, point: Vec2) -> Vec2 {
+    if shape.kind() == ShapeKind::Segment {
+        segment_closest_point(shape, point)
+    } else if shape.kind().is_convex_poly() {
+        if convex_edge_dist(shape, point) <= 0.0 {
+            return point;
+        }
+        poly_edges(shape)
+            .iter()
+            .map(|edge| segment_closest_point(edge, point))
+            .min_by_key(|closest| n64(closest.dist_sq(&point)))
+            .unwrap()
+    } else {
+        v2(
+            point.x.clamp(shape.min_x(), shape.max_x()),
+            point.y.clamp(shape.min_y(), shape.max_y()),
+        )
+    }
+}
+
+// returns the distance along the unit vector `dir` from `origin` to the
+// circle, or None if it is farther than `max_dist`
+pub fn circle_ray_dist(
+    circle: &PlacedShape,
+    origin: Vec2,
+    dir: Vec2,
+    max_dist: Float,
+) -> Option<Float> {
+    let offset = origin - circle.pos;
+    let radius = circle.dims().x * 0.5;
+    let half_b = offset * dir;
+    let c = offset.len_sq() - radius * radius;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+    let discrim = half_b * half_b - c;
+    if half_b >= 0.0 || discrim < 0.0 {
+        return None;
+    }
+    let dist = -half_b - discrim.sqrt();
+    if dist <= max_dist {
+        Some(dist.max(0.0))
+    } else {
+        None
+    }
+}
+
+// returns the distance along the unit vector `dir` from `origin` to the rect,
+// segment, triangle, or oriented rect, or None if it is farther than `max_dist`
+pub fn poly_ray_dist(
+    shape: &PlacedShape,
+    origin: Vec2,
+    dir: Vec2,
+    max_dist: Float,
+) -> Option<Float> {
+    clip_segment(origin, origin + dir * max_dist, shape).map(|(ratio, _)| ratio * max_dist)
+}
+
+// returns the distance along the unit vector `dir` from `origin` to the
+// capsule, or None if it is farther than `max_dist`
+pub fn capsule_ray_dist(
+    capsule: &PlacedShape,
+    origin: Vec2,
+    dir: Vec2,
+    max_dist: Float,
+) -> Option<Float> {
+    let (core, radius) = capsule.capsule_core();
+    let (start, end) = segment_ends(&core);
+    let dims = capsule.dims();
+    let middle_dims = if dims.x >= dims.y {
+        v2(dims.x - dims.y, dims.y)
+    } else {
+        v2(dims.x, dims.y - dims.x)
+    };
+    let ends = [start, end];
+    let circles = ends
+        .iter()
+        .map(|&end| PlacedShape::new(end, Shape::circle(radius * 2.0)))
+        .map(|circle| circle_ray_dist(&circle, origin, dir, max_dist));
+    let middle = PlacedShape::new(capsule.pos, Shape::rect(middle_dims));
+    circles
+        .chain(Some(poly_ray_dist(&middle, origin, dir, max_dist)))
+        .flatten()
+        .min_by_key(|&dist| n64(dist))
+}
+
+// returns the range of ratios along the segment from `start` to `end` that lie
+// within `other` (a rect, segment, triangle, or oriented rect), or None if they
+// do not intersect
+fn clip_segment(start: Vec2, end: Vec2, other: &PlacedShape) -> Option<(Float, Float)> {
+    let delta = end - start;
+    if delta == Vec2::zero() {
+        return if poly_closest_point(other, start) == start {
+            Some((0.0, 0.0))
+        } else {
+            None
+        };
+    }
+    let (mut min_ratio, mut max_ratio) = ((0.0 as Float), (1.0 as Float));
+    if other.kind() == ShapeKind::Segment {
+        let (other_start, other_end) = segment_ends(other);
+        let other_delta = other_end - other_start;
+        let offset = other_start - start;
+        let denom = cross(delta, other_delta);
+        if denom != 0.0 {
+            let ratio = cross(offset, other_delta) / denom;
+            let other_ratio = cross(offset, delta) / denom;
+            if (0.0..=1.0).contains(&ratio) && (0.0..=1.0).contains(&other_ratio) {
+                return Some((ratio, ratio));
+            }
+            return None;
+        } else if cross(offset, delta) != 0.0 {
+            return None;
+        }
+        // collinear
+        let len_sq = delta.len_sq();
+        let ratio_1 = offset * delta / len_sq;
+        let ratio_2 = (other_end - start) * delta / len_sq;
+        min_ratio = min_ratio.max(ratio_1.min(ratio_2));
+        max_ratio = max_ratio.min(ratio_1.max(ratio_2));
+    } else if other.kind().is_convex_poly() {
+        for (normal, offset) in convex_edges(other) {
+            let pos = normal * start - offset;
+            let vel = normal * delta;
+            if vel == 0.0 {
+                if pos > 0.0 {
+                    return None;
+                }
+            } else if vel > 0.0 {
+                max_ratio = max_ratio.min(-pos / vel);
+            } else {
+                min_ratio = min_ratio.max(-pos / vel);
+            }
+        }
+    } else {
+        let slabs = [
+            (start.x, delta.x, other.min_x(), other.max_x()),
+            (start.y, delta.y, other.min_y(), other.max_y()),
+        ];
+        for &(pos, vel, min, max) in &slabs {
+            if vel == 0.0 {
+                if pos < min || pos > max {
+                    return None;
+                }
+            } else {
+                let ratio_1 = (min - pos) / vel;
+                let ratio_2 = (max - pos) / vel;
+                min_ratio = min_ratio.max(ratio_1.min(ratio_2));
+                max_ratio = max_ratio.min(ratio_1.max(ratio_2));
+            }
+        }
+    }
+    if min_ratio <= max_ratio {
+        Some((min_ratio, max_ratio))
+    } else {
+        None
+    }
+}
+
+fn cross(a: Vec2, b: Vec2) -> Float {
+    a.x * b.y - a.y * b.x
+}
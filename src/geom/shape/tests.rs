@@ -12,13 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use float::{consts, Float, EPS};
 use geom::*;
 
 #[test]
 fn test_circle_advance() {
     let shape_1 = Shape::circle(2.0).place(v2(3.0, 5.0));
     assert_eq!(
-        shape_1.advance(v2(1.0, 2.0), v2(-0.25, -0.25), 2.0),
+        shape_1.advance(v2(1.0, 2.0), v2(-0.25, -0.25), None, 2.0),
         Shape::circle(1.5).place(v2(5.0, 9.0))
     );
 }
@@ -27,16 +28,29 @@ fn test_circle_advance() {
 fn test_rect_advance() {
     let shape_1 = Shape::rect(v2(2.0, 5.0)).place(v2(3.0, 5.0));
     assert_eq!(
-        shape_1.advance(v2(1.0, 2.0), v2(-0.25, 1.0), 2.0),
+        shape_1.advance(v2(1.0, 2.0), v2(-0.25, 1.0), None, 2.0),
         Shape::rect(v2(1.5, 7.0)).place(v2(5.0, 9.0))
     );
 }
 
+#[test]
+fn test_anchored_advance() {
+    let shape_1 = Shape::rect(v2(2.0, 4.0)).place(v2(3.0, 5.0));
+    assert_eq!(
+        shape_1.advance(v2(0.0, 0.0), v2(1.0, -1.0), Some(Card::MinusX), 2.0),
+        Shape::rect(v2(4.0, 2.0)).place(v2(4.0, 5.0))
+    );
+    assert_eq!(
+        shape_1.advance(v2(1.0, 0.0), v2(1.0, -1.0), Some(Card::PlusY), 2.0),
+        Shape::rect(v2(4.0, 2.0)).place(v2(5.0, 6.0))
+    );
+}
+
 #[test]
 #[should_panic]
 fn test_illegal_circle_advance() {
     let shape = Shape::circle(2.0).place(v2(3.0, 5.0));
-    shape.advance(v2(1.0, 2.0), v2(-0.25, -0.24), 2.0);
+    shape.advance(v2(1.0, 2.0), v2(-0.25, -0.24), None, 2.0);
 }
 
 #[test]
@@ -67,7 +81,7 @@ fn test_circle_circle_normal() {
     let dst = Shape::circle(3.0).place(v2(2.0, 0.0));
     assert_eq!(
         dst.normal_from(&src),
-        DirVec2::new(v2(1.0, -1.0), 2.5 - (2.0f64).sqrt())
+        DirVec2::new(v2(1.0, -1.0), 2.5 - (2.0 as Float).sqrt())
     );
 }
 
@@ -87,22 +101,22 @@ fn test_rect_circle_normal() {
     let dst = Shape::circle(2.5).place(v2(-2.0, -2.0));
     assert_eq!(
         dst.normal_from(&src),
-        DirVec2::new(v2(-1.0, -1.0), 1.25 - (2.0f64).sqrt())
+        DirVec2::new(v2(-1.0, -1.0), 1.25 - (2.0 as Float).sqrt())
     );
     let dst = Shape::circle(2.5).place(v2(2.0, -2.0));
     assert_eq!(
         dst.normal_from(&src),
-        DirVec2::new(v2(1.0, -1.0), 1.25 - (2.0f64).sqrt())
+        DirVec2::new(v2(1.0, -1.0), 1.25 - (2.0 as Float).sqrt())
     );
     let dst = Shape::circle(2.5).place(v2(-2.0, 2.0));
     assert_eq!(
         dst.normal_from(&src),
-        DirVec2::new(v2(-1.0, 1.0), 1.25 - (2.0f64).sqrt())
+        DirVec2::new(v2(-1.0, 1.0), 1.25 - (2.0 as Float).sqrt())
     );
     let dst = Shape::circle(2.5).place(v2(2.0, 2.0));
     assert_eq!(
         dst.normal_from(&src),
-        DirVec2::new(v2(1.0, 1.0), 1.25 - (2.0f64).sqrt())
+        DirVec2::new(v2(1.0, 1.0), 1.25 - (2.0 as Float).sqrt())
     );
 }
 
@@ -129,16 +143,16 @@ fn test_masked_rect_circle_normal() {
     let mut mask = CardMask::full();
     assert_eq!(
         dst.masked_normal_from(&src, mask),
-        DirVec2::new(v2(-1.0, 1.0), 1.25 - (2.0f64).sqrt())
+        DirVec2::new(v2(-1.0, 1.0), 1.25 - (2.0 as Float).sqrt())
     );
     mask[Card::PlusX] = false;
     assert_eq!(
         src.masked_normal_from(&dst, mask.flip()),
-        DirVec2::new(v2(1.0, -1.0), 1.25 - (2.0f64).sqrt())
+        DirVec2::new(v2(1.0, -1.0), 1.25 - (2.0 as Float).sqrt())
     );
     assert_eq!(
         dst.masked_normal_from(&src, mask),
-        DirVec2::new(v2(-1.0, 1.0), 1.25 - (2.0f64).sqrt())
+        DirVec2::new(v2(-1.0, 1.0), 1.25 - (2.0 as Float).sqrt())
     );
     mask[Card::PlusY] = false;
     assert_eq!(
@@ -170,3 +184,623 @@ fn test_circle_rect_contact() {
     assert_eq!(a.contact_point(&b), v2(4.0, 15.0));
     assert_eq!(b.contact_point(&a), v2(4.0, 15.0));
 }
+
+#[test]
+fn test_normal_nearest_card() {
+    let src = Shape::square(2.0).place(v2(0.0, 0.0));
+    let dst = Shape::square(2.0).place(v2(0.5, 1.5));
+    assert_eq!(dst.normal_from(&src).nearest_card(), Card::PlusY);
+    let dst = Shape::circle(2.0).place(v2(-1.5, 0.8));
+    assert_eq!(dst.normal_from(&src).nearest_card(), Card::MinusX);
+    let dst = Shape::circle(2.0).place(v2(0.2, -3.0));
+    assert_eq!(dst.normal_from(&src).nearest_card(), Card::MinusY);
+    assert_eq!(DirVec2::new(v2(1.0, 1.0), 2.0).nearest_card(), Card::PlusX);
+    assert_eq!(
+        DirVec2::new(v2(-1.0, 1.0), -2.0).nearest_card(),
+        Card::MinusX
+    );
+}
+
+#[test]
+fn test_segment_edges() {
+    let shape = Shape::segment(v2(4.0, -6.0)).place(v2(3.0, 5.0));
+    assert_eq!(shape.min_x(), 1.0);
+    assert_eq!(shape.min_y(), 2.0);
+    assert_eq!(shape.max_x(), 5.0);
+    assert_eq!(shape.max_y(), 8.0);
+}
+
+#[test]
+fn test_segment_circle_normal() {
+    let segment = Shape::segment(v2(4.0, 0.0)).place(v2(0.0, 0.0));
+    let circle = Shape::circle(2.0).place(v2(1.0, 0.5));
+    assert_eq!(
+        segment.normal_from(&circle),
+        DirVec2::new(v2(0.0, -1.0), 0.5)
+    );
+    assert_eq!(
+        circle.normal_from(&segment),
+        DirVec2::new(v2(0.0, 1.0), 0.5)
+    );
+    let circle = Shape::circle(2.0).place(v2(4.0, 0.0));
+    assert_eq!(
+        circle.normal_from(&segment),
+        DirVec2::new(v2(1.0, 0.0), -1.0)
+    );
+
+    let point = Shape::segment(v2(0.0, 0.0)).place(v2(0.0, 0.0));
+    let circle = Shape::circle(2.0).place(v2(0.5, 0.0));
+    assert_eq!(point.normal_from(&circle), DirVec2::new(v2(-1.0, 0.0), 0.5));
+}
+
+#[test]
+fn test_segment_rect_normal() {
+    let segment = Shape::segment(v2(2.0, 2.0)).place(v2(0.0, 0.0));
+    let rect = Shape::square(2.0).place(v2(1.5, 0.0));
+    let normal = segment.normal_from(&rect);
+    let expected_dir = DirVec2::new(v2(-1.0, 1.0), 0.0).dir();
+    assert!((normal.dir() - expected_dir).len() < EPS);
+    assert!((normal.len() - ((2.0 as Float).sqrt() - 1.5 / (2.0 as Float).sqrt())).abs() < EPS);
+    let flipped = rect.normal_from(&segment);
+    assert!((flipped.dir() + expected_dir).len() < EPS);
+    assert!((flipped.len() - normal.len()).abs() < EPS);
+
+    let rect = Shape::square(2.0).place(v2(1.5, -3.0));
+    assert!(!segment.overlaps(&rect));
+}
+
+#[test]
+fn test_segment_segment_normal() {
+    let a = Shape::segment(v2(2.0, 0.0)).place(v2(0.0, 0.0));
+    let b = Shape::segment(v2(0.0, 2.0)).place(v2(0.5, 2.0));
+    assert_eq!(a.normal_from(&b), DirVec2::new(v2(0.0, -1.0), -1.0));
+    let b = Shape::segment(v2(0.0, 2.0)).place(v2(0.5, 0.5));
+    assert!(a.overlaps(&b));
+}
+
+#[test]
+fn test_masked_circle_circle_normal() {
+    let src = Shape::circle(2.0).place(v2(0.0, 0.0));
+    let dst = Shape::circle(2.0).place(v2(0.6, 0.8));
+    let mut mask = CardMask::full();
+    let normal = dst.masked_normal_from(&src, mask);
+    assert!(normal.dir().approx_eq(v2(0.6, 0.8), 1e-9));
+    assert!((normal.len() - 1.0).abs() < 1e-9);
+    mask[Card::PlusY] = false;
+    let normal = dst.masked_normal_from(&src, mask);
+    assert_eq!(normal.dir(), v2(1.0, 0.0));
+    assert!((normal.len() - 0.6).abs() < 1e-9);
+    let normal = dst.masked_normal_from(&src, CardMask::from(Card::MinusY));
+    assert_eq!(normal.dir(), v2(0.0, -1.0));
+    assert!((normal.len() + 0.8).abs() < 1e-9);
+}
+
+#[test]
+#[should_panic]
+fn test_masked_circle_circle_normal_empty_mask() {
+    let src = Shape::circle(2.0).place(v2(0.0, 0.0));
+    let dst = Shape::circle(2.0).place(v2(0.6, 0.8));
+    dst.masked_normal_from(&src, CardMask::empty());
+}
+
+#[test]
+#[should_panic]
+fn test_masked_segment_normal() {
+    let a = Shape::segment(v2(2.0, 0.0)).place(v2(0.0, 0.0));
+    let b = Shape::square(2.0).place(v2(0.5, 0.5));
+    a.masked_normal_from(&b, CardMask::from(Card::PlusY));
+}
+
+#[test]
+fn test_segment_contact() {
+    let segment = Shape::segment(v2(2.0, 2.0)).place(v2(0.0, 0.0));
+    let rect = Shape::square(2.0).place(v2(1.5, 0.0));
+    assert_eq!(segment.contact_point(&rect), v2(0.75, 0.75));
+    assert_eq!(rect.contact_point(&segment), v2(0.75, 0.75));
+
+    let a = Shape::segment(v2(2.0, 0.0)).place(v2(0.0, 0.0));
+    let b = Shape::segment(v2(0.0, 2.0)).place(v2(0.5, 0.5));
+    assert_eq!(a.contact_point(&b), v2(0.5, 0.0));
+    let b = Shape::segment(v2(0.0, 2.0)).place(v2(0.5, 2.0));
+    assert_eq!(a.contact_point(&b), v2(0.5, 0.5));
+
+    let circle = Shape::circle(2.0).place(v2(1.0, 0.5));
+    assert_eq!(a.contact_point(&circle), v2(1.0, -0.25));
+}
+
+#[test]
+fn test_ray_dist() {
+    let circle = Shape::circle(2.0).place(v2(5.0, 1.0));
+    assert_eq!(circle.ray_dist(v2(0.0, 1.0), v2(2.0, 0.0), 10.0), Some(4.0));
+    assert_eq!(circle.ray_dist(v2(0.0, 1.0), v2(1.0, 0.0), 3.0), None);
+    assert_eq!(circle.ray_dist(v2(0.0, 1.0), v2(-1.0, 0.0), 10.0), None);
+    assert_eq!(circle.ray_dist(v2(0.0, 3.0), v2(1.0, 0.0), 10.0), None);
+    assert_eq!(circle.ray_dist(v2(5.5, 1.0), v2(1.0, 0.0), 10.0), Some(0.0));
+
+    let rect = Shape::rect(v2(2.0, 4.0)).place(v2(0.0, 0.0));
+    assert_eq!(
+        rect.ray_dist(v2(-4.0, -4.0), v2(1.0, 1.0), 10.0),
+        Some(3.0 * (2 as Float).sqrt())
+    );
+    assert_eq!(rect.ray_dist(v2(0.0, 10.0), v2(0.0, -1.0), 10.0), Some(8.0));
+    assert_eq!(rect.ray_dist(v2(0.0, 10.0), v2(0.0, -1.0), 7.0), None);
+    assert_eq!(rect.ray_dist(v2(3.0, 10.0), v2(0.0, -1.0), 20.0), None);
+    assert_eq!(rect.ray_dist(v2(0.5, 0.5), v2(0.0, -1.0), 1.0), Some(0.0));
+
+    let segment = Shape::segment(v2(0.0, 4.0)).place(v2(2.0, 0.0));
+    assert_eq!(segment.ray_dist(v2(0.0, 1.0), v2(1.0, 0.0), 5.0), Some(2.0));
+    assert_eq!(segment.ray_dist(v2(0.0, 3.0), v2(1.0, 0.0), 5.0), None);
+}
+
+#[test]
+#[should_panic]
+fn test_ray_dist_zero_dir() {
+    Shape::circle(2.0)
+        .place(v2(0.0, 0.0))
+        .ray_dist(v2(0.0, 0.0), v2(0.0, 0.0), 1.0);
+}
+
+#[test]
+fn test_contains_point() {
+    let circle = Shape::circle(2.0).place(v2(1.0, 1.0));
+    assert!(circle.contains_point(v2(1.5, 1.5)));
+    assert!(circle.contains_point(v2(2.0, 1.0)));
+    assert!(!circle.contains_point(v2(1.8, 1.8)));
+
+    let rect = Shape::rect(v2(2.0, 4.0)).place(v2(0.0, 0.0));
+    assert!(rect.contains_point(v2(0.5, -1.5)));
+    assert!(rect.contains_point(v2(1.0, 2.0)));
+    assert!(rect.contains_point(v2(-1.0, 0.0)));
+    assert!(!rect.contains_point(v2(1.1, 0.0)));
+
+    let segment = Shape::segment(v2(4.0, 0.0)).place(v2(0.0, 1.0));
+    assert!(segment.contains_point(v2(1.0, 1.0)));
+    assert!(segment.contains_point(v2(2.0, 1.0)));
+    assert!(!segment.contains_point(v2(1.0, 1.5)));
+    assert!(!segment.contains_point(v2(3.0, 1.0)));
+}
+
+#[test]
+fn test_capsule_normal() {
+    let capsule = Shape::capsule(v2(4.0, 2.0)).place(v2(0.0, 0.0));
+
+    let circle = Shape::circle(2.0).place(v2(0.0, 1.5));
+    assert_eq!(
+        capsule.normal_from(&circle),
+        DirVec2::new(v2(0.0, -1.0), 0.5)
+    );
+    assert_eq!(
+        circle.normal_from(&capsule),
+        DirVec2::new(v2(0.0, 1.0), 0.5)
+    );
+
+    let rect = Shape::square(2.0).place(v2(2.5, 0.0));
+    assert_eq!(capsule.normal_from(&rect), DirVec2::new(v2(-1.0, 0.0), 0.5));
+
+    // near the rounded end, where the bounding boxes overlap more
+    let rect = Shape::square(2.0).place(v2(2.5, 1.5));
+    let normal = capsule.normal_from(&rect);
+    assert!((normal.dir() - v2(-1.0, -1.0) * (0.5 as Float).sqrt()).len() < EPS);
+    assert!((normal.len() - (1.0 - (0.5 as Float).sqrt())).abs() < EPS);
+    let rect = Shape::square(2.0).place(v2(2.9, 1.9));
+    assert!(!capsule.overlaps(&rect));
+    assert!(!rect.overlaps(&capsule));
+
+    let other = Shape::capsule(v2(2.0, 4.0)).place(v2(3.0, 0.0));
+    assert_eq!(
+        capsule.normal_from(&other),
+        DirVec2::new(v2(-1.0, 0.0), 0.0)
+    );
+    let other = Shape::capsule(v2(2.0, 4.0)).place(v2(0.0, 2.0));
+    assert_eq!(
+        capsule.normal_from(&other),
+        DirVec2::new(v2(0.0, -1.0), 1.0)
+    );
+}
+
+#[test]
+fn test_capsule_queries() {
+    let capsule = Shape::capsule(v2(4.0, 2.0)).place(v2(0.0, 0.0));
+    assert!(capsule.contains_point(v2(1.5, 0.5)));
+    assert!(capsule.contains_point(v2(0.0, 1.0)));
+    assert!(!capsule.contains_point(v2(1.9, 0.9)));
+
+    assert_eq!(
+        capsule.ray_dist(v2(-5.0, 0.0), v2(1.0, 0.0), 10.0),
+        Some(3.0)
+    );
+    assert_eq!(
+        capsule.ray_dist(v2(0.0, 5.0), v2(0.0, -1.0), 10.0),
+        Some(4.0)
+    );
+    let dist = capsule.ray_dist(v2(1.5, 5.0), v2(0.0, -1.0), 10.0).unwrap();
+    assert!((dist - (5.0 - (0.75 as Float).sqrt())).abs() < EPS);
+    let dist = capsule.ray_dist(v2(1.9, 5.0), v2(0.0, -1.0), 10.0).unwrap();
+    assert!((dist - (5.0 - (0.19 as Float).sqrt())).abs() < EPS);
+    assert_eq!(capsule.ray_dist(v2(2.1, 5.0), v2(0.0, -1.0), 10.0), None);
+
+    let circle = Shape::circle(2.0).place(v2(0.0, 1.5));
+    assert_eq!(capsule.contact_point(&circle), v2(0.0, 0.75));
+    assert_eq!(circle.contact_point(&capsule), v2(0.0, 0.75));
+}
+
+#[test]
+#[should_panic]
+fn test_masked_capsule_normal() {
+    let capsule = Shape::capsule(v2(4.0, 2.0)).place(v2(0.0, 0.0));
+    let rect = Shape::square(2.0).place(v2(2.5, 0.0));
+    capsule.masked_normal_from(&rect, CardMask::from(Card::MinusX));
+}
+
+#[test]
+fn test_distance_to_point() {
+    let circle = Shape::circle(2.0).place(v2(1.0, 1.0));
+    assert_eq!(circle.distance_to_point(v2(4.0, 5.0)), 4.0);
+    assert_eq!(circle.distance_to_point(v2(1.5, 1.0)), -0.5);
+
+    let rect = Shape::rect(v2(2.0, 4.0)).place(v2(0.0, 0.0));
+    assert_eq!(rect.distance_to_point(v2(4.0, 6.0)), 5.0);
+    assert_eq!(rect.distance_to_point(v2(0.0, 3.0)), 1.0);
+    assert_eq!(rect.distance_to_point(v2(0.5, 1.0)), -0.5);
+    assert_eq!(rect.distance_to_point(v2(1.0, 0.0)), 0.0);
+
+    let segment = Shape::segment(v2(4.0, 0.0)).place(v2(0.0, 0.0));
+    assert_eq!(segment.distance_to_point(v2(1.0, 2.0)), 2.0);
+    assert_eq!(segment.distance_to_point(v2(5.0, 0.0)), 3.0);
+
+    let capsule = Shape::capsule(v2(4.0, 2.0)).place(v2(0.0, 0.0));
+    assert_eq!(capsule.distance_to_point(v2(0.0, 3.0)), 2.0);
+    assert_eq!(capsule.distance_to_point(v2(4.0, 4.0)), 4.0);
+    assert_eq!(capsule.distance_to_point(v2(1.0, 0.5)), -0.5);
+}
+
+#[test]
+fn test_triangle_normal() {
+    let triangle = Shape::triangle(v2(4.0, 4.0)).place(v2(0.0, 0.0));
+    let diag = v2(1.0, 1.0) * (0.5 as Float).sqrt();
+
+    let circle = Shape::circle(2.0).place(v2(1.0, 1.0));
+    let normal = triangle.normal_from(&circle);
+    assert!((normal.dir() + diag).len() < EPS);
+    assert!((normal.len() - (1.0 - (2 as Float).sqrt())).abs() < EPS);
+    let normal = circle.normal_from(&triangle);
+    assert!((normal.dir() - diag).len() < EPS);
+
+    // the center of the circle is inside the triangle
+    let circle = Shape::circle(2.0).place(v2(-1.0, -1.5));
+    assert_eq!(
+        triangle.normal_from(&circle),
+        DirVec2::new(v2(0.0, 1.0), 1.5)
+    );
+
+    let rect = Shape::square(2.0).place(v2(1.0, 1.0));
+    let normal = triangle.normal_from(&rect);
+    assert!((normal.dir() + diag).len() < EPS);
+    assert!(normal.len().abs() < EPS);
+    let rect = Shape::square(2.0).place(v2(-2.5, 0.0));
+    assert_eq!(triangle.normal_from(&rect), DirVec2::new(v2(1.0, 0.0), 0.5));
+
+    let other = Shape::triangle(v2(-4.0, -4.0)).place(v2(-0.5, -0.5));
+    let normal = triangle.normal_from(&other);
+    assert!((normal.dir() + diag).len() < EPS);
+    assert!((normal.len() - (0.5 as Float).sqrt()).abs() < EPS);
+}
+
+#[test]
+fn test_triangle_queries() {
+    let triangle = Shape::triangle(v2(4.0, 4.0)).place(v2(0.0, 0.0));
+    assert_eq!(triangle.min_x(), -2.0);
+    assert_eq!(triangle.max_y(), 2.0);
+    assert!(triangle.contains_point(v2(0.0, 0.0)));
+    assert!(triangle.contains_point(v2(-2.0, -2.0)));
+    assert!(!triangle.contains_point(v2(0.1, 0.1)));
+    assert!(!triangle.contains_point(v2(-2.1, 0.0)));
+
+    assert!((triangle.distance_to_point(v2(1.0, 1.0)) - (2 as Float).sqrt()).abs() < 1e-9);
+    assert_eq!(triangle.distance_to_point(v2(-1.0, -1.0)), -1.0);
+    assert!((triangle.distance_to_point(v2(3.0, -3.0)) - (2 as Float).sqrt()).abs() < 1e-9);
+
+    let dist = triangle
+        .ray_dist(v2(5.0, 5.0), v2(-1.0, -1.0), 20.0)
+        .unwrap();
+    assert!((dist - (50 as Float).sqrt()).abs() < 1e-9);
+    assert_eq!(
+        triangle.ray_dist(v2(-5.0, 0.0), v2(1.0, 0.0), 20.0),
+        Some(3.0)
+    );
+    assert_eq!(triangle.ray_dist(v2(5.0, 5.0), v2(1.0, -1.0), 20.0), None);
+
+    // a slope rising to the right
+    let slope = Shape::triangle(v2(-4.0, 2.0)).place(v2(0.0, 0.0));
+    assert!(slope.contains_point(v2(1.5, 0.5)));
+    assert!(!slope.contains_point(v2(-1.5, 0.5)));
+    let contact = slope.contact_point(&Shape::square(2.0).place(v2(2.0, 1.5)));
+    assert!(slope.contains_point(contact));
+}
+
+#[test]
+fn test_padded_triangle() {
+    let triangle = Shape::triangle(v2(-4.0, 2.0)).place(v2(1.0, 1.0));
+    let padded = triangle.padded_triangle(0.1);
+    for &vertex in &triangle.triangle_vertices() {
+        assert!((padded.distance_to_point(vertex) + 0.1).abs() < EPS);
+    }
+}
+
+#[test]
+fn test_contact_segment() {
+    let floor = Shape::rect(v2(10.0, 2.0)).place(v2(0.0, 0.0));
+    let crate_shape = Shape::square(2.0).place(v2(3.0, 2.0));
+    assert_eq!(
+        floor.contact_segment(&crate_shape),
+        Some((v2(2.0, 1.0), v2(4.0, 1.0)))
+    );
+
+    // hanging over the edge of the floor
+    let crate_shape = Shape::square(2.0).place(v2(5.5, 1.75));
+    assert_eq!(
+        crate_shape.contact_segment(&floor),
+        Some((v2(4.5, 0.875), v2(5.0, 0.875)))
+    );
+
+    let wall = Shape::rect(v2(2.0, 10.0)).place(v2(6.0, 4.0));
+    assert_eq!(
+        floor.contact_segment(&wall),
+        Some((v2(5.0, -1.0), v2(5.0, 1.0)))
+    );
+
+    let crate_shape = Shape::square(2.0).place(v2(3.0, 2.5));
+    assert_eq!(floor.contact_segment(&crate_shape), None);
+    let circle = Shape::circle(2.0).place(v2(3.0, 2.0));
+    assert_eq!(floor.contact_segment(&circle), None);
+}
+
+#[test]
+fn test_dir_vec_components() {
+    let normal = DirVec2::new(v2(3.0, -4.0), 2.0);
+    assert!((normal.x() - 1.2).abs() < 1e-9);
+    assert!((normal.y() + 1.6).abs() < 1e-9);
+    let normal = normal.with_len(-5.0);
+    assert_eq!(normal.dir(), v2(0.6, -0.8));
+    assert_eq!(normal.len(), -5.0);
+    assert!((normal.x() + 3.0).abs() < 1e-9);
+    assert_eq!(Vec2::from(normal), v2(normal.x(), normal.y()));
+}
+
+#[test]
+fn test_card_mask_iter() {
+    let mut mask = CardMask::empty();
+    assert_eq!(mask.count(), 0);
+    assert_eq!(mask.iter().next(), None);
+    mask[Card::PlusY] = true;
+    mask[Card::MinusX] = true;
+    assert_eq!(mask.count(), 2);
+    assert_eq!(
+        mask.iter().collect::<Vec<_>>(),
+        vec![Card::MinusX, Card::PlusY]
+    );
+    assert_eq!(CardMask::full().count(), 4);
+    assert_eq!(
+        CardMask::full().iter().collect::<Vec<_>>(),
+        Card::values().to_vec()
+    );
+}
+
+#[test]
+fn test_vec_component_helpers() {
+    let a = v2(1.0, -4.0);
+    let b = v2(-2.0, 3.0);
+    assert_eq!(a.min(b), v2(-2.0, -4.0));
+    assert_eq!(a.max(b), v2(1.0, 3.0));
+    assert_eq!(a.abs(), v2(1.0, 4.0));
+    assert_eq!(a.clamp(v2(0.0, -1.0), v2(0.5, 1.0)), v2(0.5, -1.0));
+    assert_eq!(a.with_x(7.0), v2(7.0, -4.0));
+    assert_eq!(a.with_y(7.0), v2(1.0, 7.0));
+}
+
+#[test]
+fn test_oriented_rect_queries() {
+    let sqrt2 = (2 as Float).sqrt();
+    let diamond = Shape::oriented_rect(v2(2.0, 2.0), consts::FRAC_PI_4).place(v2(1.0, 0.0));
+    assert_eq!(diamond.kind(), ShapeKind::OrientedRect);
+    assert!((diamond.min_x() - (1.0 - sqrt2)).abs() < EPS);
+    assert!((diamond.max_y() - sqrt2).abs() < EPS);
+    assert!(diamond.contains_point(v2(1.0, 1.3)));
+    assert!(!diamond.contains_point(v2(1.8, 0.8)));
+    assert!((diamond.distance_to_point(v2(3.0, 2.0)) - (2.0 * sqrt2 - 1.0)).abs() < EPS);
+
+    let circle = Shape::circle(2.0).place(v2(3.0, 2.0));
+    let normal = diamond.normal_from(&circle);
+    assert!((normal.len() - (2.0 - 2.0 * sqrt2)).abs() < EPS);
+    assert!((normal.dir() - v2(-1.0, -1.0) * (0.5 as Float).sqrt()).len() < EPS);
+
+    let other = Shape::oriented_rect(v2(2.0, 2.0), consts::FRAC_PI_4).place(v2(3.5, 0.0));
+    let normal = diamond.normal_from(&other);
+    assert!((normal.len() - (2.0 - 2.5 / sqrt2)).abs() < EPS);
+    assert!(normal.dir().x < 0.0);
+
+    let dist = diamond.ray_dist(v2(5.0, 0.0), v2(-1.0, 0.0), 10.0).unwrap();
+    assert!((dist - (4.0 - sqrt2)).abs() < EPS);
+}
+
+#[test]
+fn test_point_queries() {
+    let point = Shape::point().place(v2(1.0, 2.0));
+    assert_eq!(point.kind(), ShapeKind::Point);
+    assert_eq!(point.dims(), v2(0.0, 0.0));
+    assert_eq!(point.min_x(), 1.0);
+    assert_eq!(point.max_y(), 2.0);
+    assert!(point.contains_point(v2(1.0, 2.0)));
+    assert!(!point.contains_point(v2(1.0, 2.1)));
+    assert_eq!(point.distance_to_point(v2(4.0, 6.0)), 5.0);
+    assert_eq!(point.ray_dist(v2(1.0, 0.0), v2(0.0, 1.0), 10.0), Some(2.0));
+
+    let rect = Shape::rect(v2(4.0, 2.0)).place(v2(0.0, 2.0));
+    assert!(point.overlaps(&rect));
+    let normal = point.normal_from(&rect);
+    assert_eq!(normal.dir(), v2(1.0, 0.0));
+    assert_eq!(normal.len(), 1.0);
+    assert_eq!(point.contact_point(&rect), v2(1.5, 2.0));
+
+    let other = Shape::point().place(v2(1.0, 2.0));
+    assert!(point.overlaps(&other));
+    let other = Shape::point().place(v2(1.0, 3.0));
+    assert!(!point.overlaps(&other));
+}
+
+#[test]
+#[should_panic(expected = "point dims must be zero")]
+fn test_point_nonzero_dims() {
+    Shape::new(ShapeKind::Point, v2(1.0, 1.0));
+}
+
+#[test]
+fn test_distance() {
+    let circle = Shape::circle(2.0).place(v2(0.0, 0.0));
+    let other = Shape::circle(4.0).place(v2(6.0, 8.0));
+    assert_eq!(circle.distance(&other), 7.0);
+    assert_eq!(other.distance(&circle), 7.0);
+
+    let rect = Shape::rect(v2(4.0, 2.0)).place(v2(2.0, 0.0));
+    assert_eq!(circle.distance(&rect), -1.0);
+    let rect = Shape::rect(v2(4.0, 2.0)).place(v2(6.0, 0.0));
+    assert_eq!(rect.distance(&circle), 3.0);
+    let square = Shape::square(2.0).place(v2(6.0, 1.5));
+    assert_eq!(rect.distance(&square), -0.5);
+}
+
+#[test]
+fn test_area_perimeter() {
+    let pi = consts::PI;
+    let circle = Shape::circle(2.0);
+    assert_eq!(circle.area(), pi);
+    assert_eq!(circle.perimeter(), 2.0 * pi);
+    let rect = Shape::rect(v2(3.0, 2.0));
+    assert_eq!(rect.area(), 6.0);
+    assert_eq!(rect.perimeter(), 10.0);
+    let rect = Shape::oriented_rect(v2(3.0, 2.0), 0.5);
+    assert_eq!(rect.area(), 6.0);
+    assert_eq!(rect.perimeter(), 10.0);
+    let capsule = Shape::capsule(v2(2.0, 5.0));
+    assert_eq!(capsule.area(), pi + 6.0);
+    assert_eq!(capsule.perimeter(), 2.0 * pi + 6.0);
+    let triangle = Shape::triangle(v2(-3.0, 4.0));
+    assert_eq!(triangle.area(), 6.0);
+    assert_eq!(triangle.perimeter(), 12.0);
+    let segment = Shape::segment(v2(3.0, -4.0));
+    assert_eq!(segment.area(), 0.0);
+    assert_eq!(segment.perimeter(), 10.0);
+    assert_eq!(Shape::point().area(), 0.0);
+    assert_eq!(Shape::point().perimeter(), 0.0);
+}
+
+#[test]
+fn test_approx_eq() {
+    assert!(v2(1.0, 2.0).approx_eq(v2(1.0 + 1e-10, 2.0 - 1e-10), 1e-9));
+    assert!(!v2(1.0, 2.0).approx_eq(v2(1.0, 2.1), 1e-9));
+
+    let rect = Shape::oriented_rect(v2(2.0, 1.0), 0.5).place(v2(3.0, 4.0));
+    let rotated = Shape::oriented_rect(v2(2.0, 1.0), 0.5 + 1e-12).place(v2(3.0, 4.0 + 1e-12));
+    assert!(rect.approx_eq(&rotated, 1e-9));
+    assert!(!rect.approx_eq(
+        &Shape::oriented_rect(v2(2.0, 1.0), 0.6).place(v2(3.0, 4.0)),
+        1e-9
+    ));
+    assert!(!rect.approx_eq(&Shape::rect(v2(2.0, 1.0)).place(v2(3.0, 4.0)), 1e-9));
+    let square = Shape::square(2.0).place(v2(0.0, 0.0));
+    assert!(!square.approx_eq(&Shape::circle(2.0).place(v2(0.0, 0.0)), 1e-9));
+}
+
+#[test]
+fn test_rotate_about() {
+    let pivot = v2(1.0, 2.0);
+    let rotated = v2(3.0, 2.0).rotate_about(pivot, consts::FRAC_PI_2);
+    assert!(rotated.approx_eq(v2(1.0, 4.0), EPS));
+    let rotated = v2(3.0, 2.0).rotate_about(pivot, consts::PI);
+    assert!(rotated.approx_eq(v2(-1.0, 2.0), EPS));
+    assert_eq!(pivot.rotate_about(pivot, 1.0), pivot);
+    let origin = Vec2::zero();
+    assert!(v2(1.0, 0.5)
+        .rotate_about(origin, 0.3)
+        .approx_eq(v2(1.0, 0.5).rotate(0.3), EPS));
+}
+
+#[test]
+fn test_project_onto() {
+    let v = v2(3.0, 4.0);
+    assert_eq!(v.project_onto(v2(2.0, 0.0)), v2(3.0, 0.0));
+    assert_eq!(v.reject_from(v2(2.0, 0.0)), v2(0.0, 4.0));
+    assert_eq!(v.project_onto(v2(0.0, -1.0)), v2(0.0, 4.0));
+    assert!(v.project_onto(v2(1.0, 1.0)).approx_eq(v2(3.5, 3.5), 1e-9));
+    assert!(v.reject_from(v2(1.0, 1.0)).approx_eq(v2(-0.5, 0.5), 1e-9));
+    assert_eq!(v.project_onto(Vec2::zero()), Vec2::zero());
+    assert_eq!(v.reject_from(Vec2::zero()), v);
+}
+
+#[test]
+fn test_aabb() {
+    let aabb = Aabb::from_placed_shape(&Shape::circle(2.0).place(v2(1.0, 2.0)));
+    assert_eq!(aabb, Aabb::new(v2(0.0, 1.0), v2(2.0, 3.0)));
+    assert_eq!(aabb.center(), v2(1.0, 2.0));
+    assert_eq!(aabb.dims(), v2(2.0, 2.0));
+    assert_eq!(aabb.to_rect(), Shape::square(2.0).place(v2(1.0, 2.0)));
+
+    assert!(aabb.contains(v2(1.0, 2.0)));
+    assert!(aabb.contains(v2(2.0, 3.0)));
+    assert!(!aabb.contains(v2(2.5, 2.0)));
+
+    let other = Aabb::new(v2(2.0, -1.0), v2(4.0, 1.0));
+    assert!(aabb.intersects(&other));
+    assert!(other.intersects(&aabb));
+    assert!(!aabb.intersects(&Aabb::new(v2(2.5, 0.0), v2(3.0, 4.0))));
+    assert_eq!(aabb.union(&other), Aabb::new(v2(0.0, -1.0), v2(4.0, 3.0)));
+}
+
+#[test]
+#[should_panic]
+fn test_aabb_inverted() {
+    Aabb::new(v2(1.0, 0.0), v2(0.0, 1.0));
+}
+
+#[test]
+fn test_inverted_rect_normal() {
+    let arena = Shape::inverted_rect(v2(10.0, 6.0)).place(v2(0.0, 0.0));
+
+    let inside = Shape::square(2.0).place(v2(3.0, 0.0));
+    let normal = arena.normal_from(&inside);
+    assert_eq!((normal.dir(), normal.len()), (v2(1.0, 0.0), -1.0));
+    assert!(!arena.overlaps(&inside));
+    assert_eq!(inside.distance(&arena), 1.0);
+
+    let crossing = Shape::circle(2.0).place(v2(4.5, 0.0));
+    let normal = arena.normal_from(&crossing);
+    assert_eq!((normal.dir(), normal.len()), (v2(1.0, 0.0), 0.5));
+    assert_eq!(crossing.normal_from(&arena).dir(), v2(-1.0, 0.0));
+    assert_eq!(arena.contact_point(&crossing), v2(5.25, 0.0));
+
+    let corner = Shape::square(2.0).place(v2(-5.0, -3.0));
+    let normal = arena.normal_from(&corner);
+    assert!(normal
+        .dir()
+        .approx_eq(v2(-1.0, -1.0).normalize().unwrap(), 1e-12));
+    assert!((normal.len() - (2.0 as Float).sqrt()).abs() < 1e-12);
+
+    let other_arena = Shape::inverted_rect(v2(2.0, 2.0)).place(v2(20.0, 0.0));
+    assert!(arena.overlaps(&other_arena));
+}
+
+#[test]
+fn test_inverted_rect_point_queries() {
+    let arena = Shape::inverted_rect(v2(10.0, 6.0)).place(v2(0.0, 0.0));
+    assert!(!arena.contains_point(v2(1.0, 1.0)));
+    assert!(arena.contains_point(v2(5.0, 1.0)));
+    assert!(arena.contains_point(v2(20.0, 1.0)));
+    assert_eq!(arena.distance_to_point(v2(1.0, 1.0)), 2.0);
+    assert_eq!(arena.distance_to_point(v2(6.0, 0.0)), -1.0);
+
+    assert_eq!(arena.ray_dist(v2(1.0, 0.0), v2(1.0, 0.0), 10.0), Some(4.0));
+    assert_eq!(arena.ray_dist(v2(1.0, 0.0), v2(0.0, -2.0), 10.0), Some(3.0));
+    assert_eq!(arena.ray_dist(v2(1.0, 0.0), v2(1.0, 0.0), 3.0), None);
+    assert_eq!(arena.ray_dist(v2(8.0, 0.0), v2(-1.0, 0.0), 3.0), Some(0.0));
+    assert_eq!(Shape::inverted_rect(v2(10.0, 6.0)).area(), Float::INFINITY);
+}
@@ -12,22 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use float::Float;
 use geom::card::Card;
 use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
-/// A 2-D Cartesian vector using finite `f64` values.
+/// A 2-D Cartesian vector using finite `Float` values.
 #[derive(PartialEq, Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Vec2 {
     /// The x-coordinate.
-    pub x: f64,
+    pub x: Float,
     /// The y-coordinate.
-    pub y: f64,
+    pub y: Float,
 }
 
 impl Vec2 {
     /// Constructs a vector with the given `x` and `y` coordinates.
     #[inline]
-    pub fn new(x: f64, y: f64) -> Vec2 {
+    pub fn new(x: Float, y: Float) -> Vec2 {
         Vec2 { x, y }
     }
 
@@ -41,7 +43,7 @@ impl Vec2 {
     ///
     /// Due to underflow, this might be `0.0` even if `x` and `y` are non-zero
     /// but very small.
-    pub fn len_sq(&self) -> f64 {
+    pub fn len_sq(&self) -> Float {
         self.x * self.x + self.y * self.y
     }
 
@@ -49,7 +51,7 @@ impl Vec2 {
     ///
     /// Due to underflow, this might be `0.0` even if `x` and `y` are non-zero
     /// but very small.
-    pub fn len(&self) -> f64 {
+    pub fn len(&self) -> Float {
         self.len_sq().sqrt()
     }
 
@@ -66,12 +68,12 @@ impl Vec2 {
     }
 
     /// Computes the square of the Euclidean distance between two vectors.
-    pub fn dist_sq(&self, other: &Vec2) -> f64 {
+    pub fn dist_sq(&self, other: &Vec2) -> Float {
         (*self - *other).len_sq()
     }
 
     /// Computes the Euclidean distance between two vectors.
-    pub fn dist(&self, other: &Vec2) -> f64 {
+    pub fn dist(&self, other: &Vec2) -> Float {
         (*self - *other).len()
     }
 
@@ -80,43 +82,115 @@ impl Vec2 {
     /// Using `ratio = 0.0` will return `self`, and using `ratio = 1.0` will
     /// return `other`. Can also extrapolate using `ratio > 1.0` or
     /// `ratio < 0.0`.
-    pub fn lerp(&self, other: Vec2, ratio: f64) -> Vec2 {
+    pub fn lerp(&self, other: Vec2, ratio: Float) -> Vec2 {
         (1.0 - ratio) * *self + ratio * other
     }
 
+    /// Returns the component-wise minimum of `self` and `other`.
+    #[inline]
+    pub fn min(&self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x.min(other.x), self.y.min(other.y))
+    }
+
+    /// Returns the component-wise maximum of `self` and `other`.
+    #[inline]
+    pub fn max(&self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x.max(other.x), self.y.max(other.y))
+    }
+
+    /// Returns the vector with the absolute value of each component.
+    #[inline]
+    pub fn abs(&self) -> Vec2 {
+        Vec2::new(self.x.abs(), self.y.abs())
+    }
+
+    /// Clamps each component of the vector between the corresponding
+    /// components of `lo` and `hi`.
+    ///
+    /// If a component of `lo` is greater than that of `hi`, then the component
+    /// of `hi` is used.
+    #[inline]
+    pub fn clamp(&self, lo: Vec2, hi: Vec2) -> Vec2 {
+        self.max(lo).min(hi)
+    }
+
+    /// Returns a copy of the vector with the x-coordinate replaced by `x`.
+    #[inline]
+    pub fn with_x(&self, x: Float) -> Vec2 {
+        Vec2::new(x, self.y)
+    }
+
+    /// Returns a copy of the vector with the y-coordinate replaced by `y`.
+    #[inline]
+    pub fn with_y(&self, y: Float) -> Vec2 {
+        Vec2::new(self.x, y)
+    }
+
+    /// Returns true if each component of `self` is within `eps` of the
+    /// corresponding component of `other`.
+    ///
+    /// This is useful for comparing the results of computations that are
+    /// subject to rounding error.
+    pub fn approx_eq(&self, other: Vec2, eps: Float) -> bool {
+        (self.x - other.x).abs() <= eps && (self.y - other.y).abs() <= eps
+    }
+
     /// Rotates the vector by `angle` radians counter-clockwise (assuming +x is
     /// right and +y is up).
-    pub fn rotate(&self, angle: f64) -> Vec2 {
+    pub fn rotate(&self, angle: Float) -> Vec2 {
         let sin = angle.sin();
         let cos = angle.cos();
         Vec2::new(cos * self.x - sin * self.y, sin * self.x + cos * self.y)
     }
+
+    /// Rotates the vector by `angle` radians counter-clockwise about `pivot`
+    /// (assuming +x is right and +y is up).
+    pub fn rotate_about(&self, pivot: Vec2, angle: Float) -> Vec2 {
+        pivot + (*self - pivot).rotate(angle)
+    }
+
+    /// Returns the component of the vector that is parallel to `axis`, or
+    /// zero if `axis` has zero length.
+    pub fn project_onto(&self, axis: Vec2) -> Vec2 {
+        let len_sq = axis.len_sq();
+        if len_sq == 0.0 {
+            Vec2::zero()
+        } else {
+            axis * ((*self * axis) / len_sq)
+        }
+    }
+
+    /// Returns the component of the vector that is perpendicular to `axis`,
+    /// which is `self - self.project_onto(axis)`.
+    pub fn reject_from(&self, axis: Vec2) -> Vec2 {
+        *self - self.project_onto(axis)
+    }
 }
 
-impl Mul<Vec2> for f64 {
+impl Mul<Vec2> for Float {
     type Output = Vec2;
     fn mul(self, rhs: Vec2) -> Vec2 {
         Vec2::new(self * rhs.x, self * rhs.y)
     }
 }
 
-impl Mul<f64> for Vec2 {
+impl Mul<Float> for Vec2 {
     type Output = Vec2;
-    fn mul(self, rhs: f64) -> Vec2 {
+    fn mul(self, rhs: Float) -> Vec2 {
         Vec2::new(self.x * rhs, self.y * rhs)
     }
 }
 
-impl MulAssign<f64> for Vec2 {
-    fn mul_assign(&mut self, rhs: f64) {
+impl MulAssign<Float> for Vec2 {
+    fn mul_assign(&mut self, rhs: Float) {
         self.x *= rhs;
         self.y *= rhs;
     }
 }
 
 impl Mul<Vec2> for Vec2 {
-    type Output = f64;
-    fn mul(self, rhs: Vec2) -> f64 {
+    type Output = Float;
+    fn mul(self, rhs: Vec2) -> Float {
         self.x * rhs.x + self.y * rhs.y
     }
 }
@@ -169,7 +243,7 @@ impl From<Card> for Vec2 {
 
 /// Shorthand for invoking the `Vec2` constructor.
 #[inline]
-pub fn v2(x: f64, y: f64) -> Vec2 {
+pub fn v2(x: Float, y: Float) -> Vec2 {
     Vec2::new(x, y)
 }
 
@@ -183,14 +257,14 @@ pub fn v2(x: f64, y: f64) -> Vec2 {
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub struct DirVec2 {
     dir: Vec2,
-    len: f64,
+    len: Float,
 }
 
 impl DirVec2 {
     /// Constructs a vector with the given direction `dir` and length `len`.
     ///
     /// `dir` is normalized before being set.
-    pub fn new(dir: Vec2, len: f64) -> DirVec2 {
+    pub fn new(dir: Vec2, len: Float) -> DirVec2 {
         DirVec2 {
             dir: dir.normalize().unwrap(),
             len,
@@ -205,10 +279,27 @@ impl DirVec2 {
 
     /// Returns the length of the vector.  May be positive or negative.
     #[inline]
-    pub fn len(&self) -> f64 {
+    pub fn len(&self) -> Float {
         self.len
     }
 
+    /// Returns the x component of the vector, which is `dir().x * len()`.
+    #[inline]
+    pub fn x(&self) -> Float {
+        self.dir.x * self.len
+    }
+
+    /// Returns the y component of the vector, which is `dir().y * len()`.
+    #[inline]
+    pub fn y(&self) -> Float {
+        self.dir.y * self.len
+    }
+
+    /// Returns a new vector with the same `dir` but the given `len`.
+    pub fn with_len(&self, len: Float) -> DirVec2 {
+        DirVec2 { dir: self.dir, len }
+    }
+
     /// Returns a new vector with the same `len` but reversed `dir`.
     pub fn flip(&self) -> DirVec2 {
         DirVec2 {
@@ -216,13 +307,30 @@ impl DirVec2 {
             len: self.len,
         }
     }
+
+    /// Returns the cardinal direction that is closest to `dir`.
+    ///
+    /// This is useful for classifying a collision normal as a floor, wall, or
+    /// ceiling contact. Normals between two `Rect` shapes are already
+    /// axis-aligned, so the result is exact in that case. If `dir` is exactly
+    /// diagonal, the x direction is chosen.
+    pub fn nearest_card(&self) -> Card {
+        if self.dir.x.abs() >= self.dir.y.abs() {
+            if self.dir.x >= 0.0 {
+                Card::PlusX
+            } else {
+                Card::MinusX
+            }
+        } else if self.dir.y >= 0.0 {
+            Card::PlusY
+        } else {
+            Card::MinusY
+        }
+    }
 }
 
 impl From<DirVec2> for Vec2 {
     fn from(dir_vec: DirVec2) -> Vec2 {
-        Vec2::new(
-            dir_vec.dir().x * dir_vec.len(),
-            dir_vec.dir().y * dir_vec.len(),
-        )
+        Vec2::new(dir_vec.x(), dir_vec.y())
     }
 }
@@ -30,6 +30,48 @@ impl IndexRect {
         IndexRect { start, end }
     }
 
+    pub fn start(self) -> (i32, i32) {
+        self.start
+    }
+
+    // returns the smallest rect containing both rects
+    pub fn union(self, other: IndexRect) -> IndexRect {
+        IndexRect {
+            start: (
+                self.start.0.min(other.start.0),
+                self.start.1.min(other.start.1),
+            ),
+            end: (self.end.0.max(other.end.0), self.end.1.max(other.end.1)),
+        }
+    }
+
+    // returns the largest distance along either axis from `val` to an element
+    // of the rect
+    pub fn max_axis_dist(self, val: (i32, i32)) -> i64 {
+        let dist = |start: i32, end: i32, val: i32| {
+            (val as i64 - start as i64)
+                .abs()
+                .max((end as i64 - 1 - val as i64).abs())
+        };
+        dist(self.start.0, self.end.0, val.0).max(dist(self.start.1, self.end.1, val.1))
+    }
+
+    pub fn end(self) -> (i32, i32) {
+        self.end
+    }
+
+    // returns the rect with one element removed from each side, or `None` if
+    // no elements remain
+    pub fn shrunk(self) -> Option<IndexRect> {
+        let start = (self.start.0 + 1, self.start.1 + 1);
+        let end = (self.end.0 - 1, self.end.1 - 1);
+        if start.0 < end.0 && start.1 < end.1 {
+            Some(IndexRect { start, end })
+        } else {
+            None
+        }
+    }
+
     pub fn iter(self) -> Iter {
         Iter::new(self)
     }
@@ -37,6 +79,15 @@ impl IndexRect {
     pub fn contains(self, val: (i32, i32)) -> bool {
         val.0 >= self.start.0 && val.0 < self.end.0 && val.1 >= self.start.1 && val.1 < self.end.1
     }
+
+    // returns the first index in the intersection of two rects, assuming that
+    // they intersect
+    pub fn shared_start(self, other: IndexRect) -> (i32, i32) {
+        (
+            self.start.0.max(other.start.0),
+            self.start.1.max(other.start.1),
+        )
+    }
 }
 
 pub struct Iter {
@@ -99,6 +150,13 @@ mod tests {
         assert!(!rect.contains((4, 7)));
     }
 
+    #[test]
+    fn test_shared_start() {
+        let rect = IndexRect::new((2, 3), (5, 7));
+        assert_eq!(rect.shared_start(IndexRect::new((3, 1), (9, 4))), (3, 3));
+        assert_eq!(rect.shared_start(IndexRect::new((0, 0), (3, 9))), (2, 3));
+    }
+
     #[test]
     #[should_panic]
     fn test_new_bad_x() {
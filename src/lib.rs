@@ -20,10 +20,11 @@
 //! [Continuous collision detection](https://en.wikipedia.org/wiki/Collision_detection#A_posteriori_.28discrete.29_versus_a_priori_.28continuous.29),
 //! on the other hand, means that the time of collision is determined very
 //! precisely, and the user is not restricted to a fixed time-stepping method.
-//! There are currently two kinds of shapes supported by Collider: circles and
-//! rectangles. The user specifies the positions and velocities of these shapes,
-//! which they can update at any time, and Collider will solve for the precise
-//! times of collision and separation.
+//! There are currently eight kinds of shapes supported by Collider: circles,
+//! rectangles, rotated rectangles, capsules, right triangles, line segments,
+//! points, and inverted rectangles. The user specifies the positions and
+//! velocities of these shapes, which they can update at any time, and Collider
+//! will solve for the precise times of collision and separation.
 //!
 //! There are certain advantages that continuous collision detection holds over
 //! the traditional approach. In a game engine, the position of a sprite may be
@@ -86,6 +87,9 @@
 //! ```
 
 extern crate fnv;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
 
 mod core;
 mod float;
@@ -96,3 +100,4 @@ mod tests;
 mod util;
 
 pub use core::*;
+pub use float::Float;
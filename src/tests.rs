@@ -12,9 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::{Collider, HbEvent, HbId, HbProfile, HbVel};
-use geom::{v2, Shape};
-use std::f64;
+use super::{
+    Collider, ColliderBuilder, ColliderError, DetectionMode, EventDescription, GridStats, HbEvent,
+    HbGroup, HbId, HbProfile, HbVel, Hitbox, InvalidationReason,
+};
+use float::{consts, Float, EPS};
+use geom::{v2, Aabb, Card, CardMask, Shape, ShapeKind, Vec2};
+use std::cell::Cell;
+use std::sync::{Arc, Mutex};
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 struct TestHbProfile {
@@ -36,12 +41,69 @@ impl HbProfile for TestHbProfile {
     }
 }
 
-fn advance_to_event(collider: &mut Collider<TestHbProfile>, time: f64) {
+// a profile for tests of groups and teams, where hitboxes can interact if
+// either one interacts with the group of the other
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct GroupHbProfile {
+    id: HbId,
+    group: HbGroup,
+    interact_groups: &'static [HbGroup],
+    interact_mask: Option<u64>,
+    team: Option<u32>,
+    one_way: Option<Card>,
+}
+
+impl GroupHbProfile {
+    fn new(id: HbId, group: HbGroup) -> GroupHbProfile {
+        GroupHbProfile {
+            id,
+            group,
+            interact_groups: &[0, 1],
+            interact_mask: None,
+            team: None,
+            one_way: None,
+        }
+    }
+
+    fn interacts_with_group(&self, group: HbGroup) -> bool {
+        match self.interact_mask {
+            Some(mask) => mask & (1 << group) != 0,
+            None => self.interact_groups.contains(&group),
+        }
+    }
+}
+
+impl HbProfile for GroupHbProfile {
+    fn id(&self) -> HbId {
+        self.id
+    }
+    fn can_interact(&self, other: &GroupHbProfile) -> bool {
+        assert!(self.team.is_none() || self.team != other.team);
+        self.interacts_with_group(other.group) || other.interacts_with_group(self.group)
+    }
+    fn group(&self) -> Option<HbGroup> {
+        Some(self.group)
+    }
+    fn interact_groups(&self) -> &'static [HbGroup] {
+        self.interact_groups
+    }
+    fn interact_mask(&self) -> Option<u64> {
+        self.interact_mask
+    }
+    fn team(&self) -> Option<u32> {
+        self.team
+    }
+    fn one_way(&self) -> Option<Card> {
+        self.one_way
+    }
+}
+
+fn advance_to_event<P: HbProfile>(collider: &mut Collider<P>, time: Float) {
     advance(collider, time);
     assert_eq!(collider.next_time(), collider.time());
 }
 
-fn advance(collider: &mut Collider<TestHbProfile>, time: f64) {
+fn advance<P: HbProfile>(collider: &mut Collider<P>, time: Float) {
     while collider.time() < time {
         assert!(collider.next().is_none());
         let new_time = collider.next_time().min(time);
@@ -50,7 +112,7 @@ fn advance(collider: &mut Collider<TestHbProfile>, time: f64) {
     assert_eq!(collider.time(), time);
 }
 
-fn advance_through_events(collider: &mut Collider<TestHbProfile>, time: f64) {
+fn advance_through_events<P: HbProfile>(collider: &mut Collider<P>, time: Float) {
     while collider.time() < time {
         collider.next();
         let new_time = collider.next_time().min(time);
@@ -59,6 +121,18 @@ fn advance_through_events(collider: &mut Collider<TestHbProfile>, time: f64) {
     assert_eq!(collider.time(), time);
 }
 
+// advances to the next event, which must occur before the end of time
+fn next_event<P: HbProfile>(collider: &mut Collider<P>) -> (HbEvent, P, P) {
+    loop {
+        if let Some(event) = collider.next() {
+            return event;
+        }
+        let time = collider.next_time();
+        assert!(time < Float::INFINITY);
+        collider.set_time(time);
+    }
+}
+
 fn sort(mut vector: Vec<TestHbProfile>) -> Vec<TestHbProfile> {
     vector.sort();
     vector
@@ -111,7 +185,7 @@ fn test_hitbox_updates() {
     assert_eq!(hitbox.value, Shape::square(2.0).place(v2(1.0, 0.0)));
     assert_eq!(hitbox.vel.value, v2(1.0, 0.0));
     assert_eq!(hitbox.vel.resize, v2(0.0, 0.0));
-    assert_eq!(hitbox.vel.end_time, f64::INFINITY);
+    assert_eq!(hitbox.vel.end_time, Float::INFINITY);
     hitbox.value.pos = v2(0.0, 2.0);
     hitbox.vel.value = v2(0.0, -1.0);
     let overlaps = collider.remove_hitbox(0);
@@ -125,7 +199,7 @@ fn test_hitbox_updates() {
     assert_eq!(hitbox.value, Shape::circle(2.0).place(v2(24.0, 0.0)));
     assert_eq!(hitbox.vel.value, v2(1.0, 0.0));
     assert_eq!(hitbox.vel.resize, v2(0.0, 0.0));
-    assert_eq!(hitbox.vel.end_time, f64::INFINITY);
+    assert_eq!(hitbox.vel.end_time, Float::INFINITY);
     hitbox.value.pos = v2(0.0, -8.0);
     hitbox.vel.value = v2(0.0, 0.0);
     let overlaps = collider.remove_hitbox(1);
@@ -143,7 +217,7 @@ fn test_hitbox_updates() {
     assert_eq!(hitbox.value, Shape::square(2.0).place(v2(0.0, -6.0)));
     assert_eq!(hitbox.vel.value, v2(0.0, -1.0));
     assert_eq!(hitbox.vel.resize, v2(0.0, 0.0));
-    assert_eq!(hitbox.vel.end_time, f64::INFINITY);
+    assert_eq!(hitbox.vel.end_time, Float::INFINITY);
     hitbox.vel.value = v2(0.0, 0.0);
     collider.set_hitbox_vel(0, hitbox.vel);
 
@@ -151,7 +225,7 @@ fn test_hitbox_updates() {
     assert_eq!(hitbox.value, Shape::circle(2.0).place(v2(0.0, -8.0)));
     assert_eq!(hitbox.vel.value, v2(0.0, 0.0));
     assert_eq!(hitbox.vel.resize, v2(0.0, 0.0));
-    assert_eq!(hitbox.vel.end_time, f64::INFINITY);
+    assert_eq!(hitbox.vel.end_time, Float::INFINITY);
     hitbox.vel.value = v2(0.0, 2.0);
     collider.set_hitbox_vel(1, hitbox.vel);
 
@@ -176,6 +250,49 @@ fn test_hitbox_updates() {
     advance(&mut collider, 37.125);
 }
 
+#[test]
+fn test_translate_world() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0)
+            .place(v2(-10.0, 0.0))
+            .moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(
+        1.into(),
+        Shape::circle(2.0)
+            .place(v2(10.0, 0.0))
+            .moving(v2(-1.0, 0.0)),
+    );
+    collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(0.0, 5.0)).still());
+
+    advance(&mut collider, 5.0);
+    let events = collider.scheduled_events();
+    collider.translate_world(v2(100.0, -50.0));
+    assert_eq!(collider.scheduled_events(), events);
+    assert_eq!(
+        collider.get_hitbox(0).value,
+        Shape::square(2.0).place(v2(95.0, -50.0))
+    );
+    assert_eq!(
+        collider.query_overlaps(&Shape::square(2.0).place(v2(100.0, -45.0)), &5.into()),
+        vec![2.into()]
+    );
+    assert_eq!(collider.hitboxes_in_cell((0, 1)), vec![]);
+
+    advance_to_event(&mut collider, 9.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+    assert_eq!(
+        collider.get_hitbox(1).value,
+        Shape::circle(2.0).place(v2(101.0, -50.0))
+    );
+}
+
 #[test]
 fn test_get_overlaps() {
     let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
@@ -201,6 +318,8 @@ fn test_get_overlaps() {
     assert!(!collider.is_overlapping(0, 2));
     assert!(!collider.is_overlapping(1, 2));
     assert!(!collider.is_overlapping(1, 0));
+    assert_eq!(collider.overlap_count(0), 0);
+    assert!(!collider.has_any_overlap(2));
 
     advance_through_events(&mut collider, 10.0);
 
@@ -211,6 +330,8 @@ fn test_get_overlaps() {
     assert!(collider.is_overlapping(0, 2));
     assert!(collider.is_overlapping(1, 2));
     assert!(collider.is_overlapping(1, 0));
+    assert_eq!(collider.overlap_count(0), 2);
+    assert!(collider.has_any_overlap(2));
 
     collider.set_hitbox_vel(1, HbVel::moving(v2(1.0, 0.0)));
     advance_through_events(&mut collider, 20.0);
@@ -229,6 +350,45 @@ fn test_get_overlaps() {
 
     collider.remove_hitbox(1);
     assert_eq!(collider.get_overlaps(0), vec![]);
+    assert_eq!(collider.overlap_count(0), 0);
+    assert!(!collider.has_any_overlap(0));
+}
+
+#[test]
+fn test_persistent_overlaps() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0).place(v2(-5.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(0.5, 1.5)).still());
+    assert_eq!(collider.overlap_start_time(1, 2), Some(0.0));
+    assert_eq!(collider.overlap_start_time(0, 1), None);
+
+    advance_through_events(&mut collider, 4.0);
+    assert_eq!(collider.overlap_start_time(1, 0), Some(3.0));
+    collider.set_hitbox_vel(0, HbVel::still());
+    assert_eq!(
+        collider.persistent_overlaps(0.25, 0.5),
+        vec![(0.into(), 1.into(), 1.0), (1.into(), 2.into(), 0.5)]
+    );
+    assert_eq!(
+        collider.persistent_overlaps(0.75, 0.5),
+        vec![(0.into(), 1.into(), 1.0)]
+    );
+    assert_eq!(
+        collider.persistent_overlaps(0.25, 2.0),
+        vec![(1.into(), 2.into(), 0.5)]
+    );
+
+    collider.remove_hitbox(1);
+    assert_eq!(collider.overlap_start_time(0, 1), None);
+    assert_eq!(
+        collider.persistent_overlaps(0.0, 0.0),
+        vec![(0.into(), 2.into(), 0.5)]
+    );
 }
 
 #[test]
@@ -260,6 +420,122 @@ fn test_query_overlaps() {
     );
 }
 
+#[test]
+fn test_separation_margin() {
+    let mut collider: Collider<TestHbProfile> = ColliderBuilder::new()
+        .cell_width(4.0)
+        .padding(0.25)
+        .separation_margin(1.0)
+        .build();
+
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(0.5).place(v2(0.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::square(0.5).place(v2(2.0, 0.0)).still());
+
+    advance_to_event(&mut collider, 1.5);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+    advance_to_event(&mut collider, 3.5);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Separate, 0.into(), 1.into()))
+    );
+}
+
+#[test]
+fn test_circle_padding_scale() {
+    let mut collider: Collider<TestHbProfile> = ColliderBuilder::new()
+        .cell_width(4.0)
+        .padding(0.25)
+        .circle_padding_scale(2.0)
+        .build();
+
+    collider.add_hitbox(
+        0.into(),
+        Shape::circle(1.0).place(v2(0.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::square(1.0).place(v2(2.0, 0.0)).still());
+    collider.add_hitbox(
+        2.into(),
+        Shape::square(1.0).place(v2(0.0, 5.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(3.into(), Shape::square(1.0).place(v2(2.0, 5.0)).still());
+
+    advance_to_event(&mut collider, 1.0);
+    assert!(collider.next().is_some());
+    assert!(collider.next().is_some());
+    advance_to_event(&mut collider, 3.25);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Separate, 2.into(), 3.into()))
+    );
+    advance_to_event(&mut collider, 3.5);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Separate, 0.into(), 1.into()))
+    );
+}
+
+#[test]
+fn test_capsule_padding_scale() {
+    let mut collider: Collider<TestHbProfile> = ColliderBuilder::new()
+        .cell_width(4.0)
+        .padding(0.25)
+        .circle_padding_scale(2.0)
+        .build();
+
+    collider.add_hitbox(
+        0.into(),
+        Shape::capsule(v2(1.0, 2.0))
+            .place(v2(0.0, 0.0))
+            .moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::square(1.0).place(v2(2.0, 0.0)).still());
+
+    assert_eq!(
+        next_event(&mut collider),
+        (HbEvent::Collide, 0.into(), 1.into())
+    );
+    assert!((collider.time() - 1.0).abs() < EPS);
+    assert_eq!(
+        next_event(&mut collider),
+        (HbEvent::Separate, 0.into(), 1.into())
+    );
+    assert!((collider.time() - 3.5).abs() < EPS);
+}
+
+#[test]
+fn test_add_tilemap() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+
+    let occupancy: [&[bool]; 3] = [
+        &[true, true, false, true],
+        &[true, true, false],
+        &[false, true, true, true],
+    ];
+    let profiles = collider.add_tilemap(&occupancy, 2.0, v2(10.0, 0.0), |x, y| {
+        ((y * 10 + x) as HbId).into()
+    });
+    assert_eq!(profiles, vec![0.into(), 3.into(), 21.into()]);
+
+    assert_eq!(
+        collider.get_hitbox(0).value,
+        Shape::square(4.0).place(v2(12.0, 2.0))
+    );
+    assert_eq!(
+        collider.get_hitbox(3).value,
+        Shape::square(2.0).place(v2(17.0, 1.0))
+    );
+    assert_eq!(
+        collider.get_hitbox(21).value,
+        Shape::rect(v2(6.0, 2.0)).place(v2(15.0, 5.0))
+    );
+}
+
 #[test]
 fn test_separate_initial_overlap() {
     let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
@@ -281,4 +557,2879 @@ fn test_separate_initial_overlap() {
     advance(&mut collider, 1.5);
 }
 
+#[test]
+fn test_coincident_hitboxes() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+
+    let hitbox = Shape::circle(2.0).place(v2(1.0, 1.0)).moving(v2(1.0, 0.0));
+    assert_eq!(collider.add_hitbox(0.into(), hitbox.clone()), vec![]);
+    assert_eq!(collider.add_hitbox(1.into(), hitbox), vec![0.into()]);
+    assert_eq!(collider.next_time(), 4.0);
+
+    advance(&mut collider, 3.0);
+    collider.set_hitbox_vel(1, HbVel::moving(v2(-1.0, 0.0)));
+    advance_to_event(&mut collider, 4.125);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Separate, 0.into(), 1.into()))
+    );
+}
+
+#[test]
+fn test_on_collide() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0).place(v2(-5.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(
+        2.into(),
+        Shape::square(2.0).place(v2(5.0, 0.0)).moving(v2(-2.0, 0.0)),
+    );
+
+    let hits = Arc::new(Mutex::new(Vec::new()));
+    let callback_hits = hits.clone();
+    collider.on_collide(
+        1,
+        Box::new(move |profile, other| callback_hits.lock().unwrap().push((profile, other))),
+    );
+
+    advance_to_event(&mut collider, 1.5);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 1.into(), 2.into()))
+    );
+    assert_eq!(*hits.lock().unwrap(), vec![(1.into(), 2.into())]);
+
+    collider.remove_hitbox(2);
+    advance_to_event(&mut collider, 3.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+    assert_eq!(
+        *hits.lock().unwrap(),
+        vec![(1.into(), 2.into()), (1.into(), 0.into())]
+    );
+
+    collider.remove_hitbox(1);
+    assert_eq!(Arc::strong_count(&hits), 1);
+}
+
+#[test]
+fn test_collider_is_send() {
+    fn assert_send<T: Send>() {}
+    assert_send::<Collider<TestHbProfile>>();
+}
+
+#[test]
+fn test_scheduled_events() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 5.0)).still());
+    assert_eq!(collider.scheduled_events(), vec![]);
+
+    collider.set_hitbox_vel(0, HbVel::moving(v2(0.0, -1.0)));
+    let events = collider.scheduled_events();
+    assert!(events.contains(&(3.0, EventDescription::Collide(0, 1))));
+    assert!(events.contains(&(4.0, EventDescription::Reiterate(0))));
+    assert!(events.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+
+    advance_to_event(&mut collider, 3.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+    advance(&mut collider, 5.0);
+    assert!(collider
+        .scheduled_events()
+        .contains(&(7.25, EventDescription::Separate(0, 1))));
+}
+
+#[test]
+fn test_resolve_overlap_masked() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.5, 0.75)).still());
+    collider.add_hitbox(
+        1.into(),
+        Shape::rect(v2(4.0, 1.0)).place(v2(-2.0, 0.0)).still(),
+    );
+
+    assert_eq!(
+        collider.resolve_overlap_masked(0, 1, CardMask::full()),
+        Some(v2(0.5, 0.0))
+    );
+    assert_eq!(
+        collider.resolve_overlap_masked(0, 1, Card::PlusY.into()),
+        Some(v2(0.0, 0.75))
+    );
+    assert_eq!(
+        collider.resolve_overlap_masked(0, 1, CardMask::empty()),
+        None
+    );
+
+    collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(0.0, 3.0)).still());
+    assert_eq!(
+        collider.resolve_overlap_masked(2, 1, Card::PlusY.into()),
+        None
+    );
+}
+
+#[test]
+fn test_hitboxes_in_cell() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(2.0, 2.0)).still());
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(4.0, 2.0)).still());
+    collider.add_hitbox(
+        2.into(),
+        Shape::square(2.0).place(v2(-2.0, 2.0)).moving(v2(1.0, 0.0)),
+    );
+
+    assert_eq!(collider.hitboxes_in_cell((0, 0)), vec![0, 1, 2]);
+    assert_eq!(collider.hitboxes_in_cell((1, 0)), vec![1]);
+    assert_eq!(collider.hitboxes_in_cell((-1, 0)), vec![2]);
+    assert_eq!(collider.hitboxes_in_cell((0, 1)), vec![]);
+
+    collider.remove_hitbox(0);
+    assert_eq!(collider.hitboxes_in_cell((0, 0)), vec![1, 2]);
+}
+
+#[test]
+fn test_take_cell_changes() {
+    let mut collider = Collider::<GroupHbProfile>::new(4.0, 0.25);
+
+    // changes are only tracked once enabled
+    collider.add_hitbox(
+        GroupHbProfile::new(3, 0),
+        Shape::square(2.0).place(v2(-6.0, 2.0)).still(),
+    );
+    assert_eq!(collider.take_cell_changes(), vec![]);
+    collider.set_cell_change_tracking(true);
+
+    assert_eq!(collider.take_cell_changes(), vec![]);
+    collider.add_hitbox(
+        GroupHbProfile::new(0, 0),
+        Shape::square(2.0).place(v2(2.0, 2.0)).still(),
+    );
+    collider.add_hitbox(
+        GroupHbProfile::new(1, 0),
+        Shape::square(2.0).place(v2(4.0, 2.0)).still(),
+    );
+    assert_eq!(
+        collider.take_cell_changes(),
+        vec![(0, 0, 0, true), (0, 1, 0, true)]
+    );
+    assert_eq!(collider.take_cell_changes(), vec![]);
+
+    collider.remove_hitbox(1);
+    collider.add_hitbox(
+        GroupHbProfile::new(2, 0),
+        Shape::square(2.0).place(v2(-2.0, 2.0)).still(),
+    );
+    collider.remove_hitbox(2);
+    assert_eq!(collider.take_cell_changes(), vec![(0, 1, 0, false)]);
+
+    // each group has its own cells, even at the same coordinates
+    collider.add_hitbox(
+        GroupHbProfile::new(4, 1),
+        Shape::square(2.0).place(v2(2.0, 2.0)).still(),
+    );
+    assert_eq!(collider.take_cell_changes(), vec![(1, 0, 0, true)]);
+
+    collider.set_cell_change_tracking(false);
+    collider.remove_hitbox(4);
+    collider.set_cell_change_tracking(true);
+    assert_eq!(collider.take_cell_changes(), vec![]);
+}
+
+#[test]
+fn test_query_overlaps_inflated() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(
+        1.into(),
+        Shape::rect(v2(8.0, 2.0)).place(v2(0.0, -2.5)).still(),
+    );
+    collider.add_hitbox(2.into(), Shape::circle(2.0).place(v2(3.0, 0.0)).still());
+
+    assert_eq!(collider.query_overlaps_inflated(0, 0.0), vec![]);
+    assert_eq!(collider.query_overlaps_inflated(0, 0.5), vec![1.into()]);
+    assert_eq!(
+        sort(collider.query_overlaps_inflated(0, 1.0)),
+        vec![1.into(), 2.into()]
+    );
+    assert_eq!(collider.query_overlaps_inflated(2, 0.25), vec![]);
+}
+
+#[test]
+fn test_projected_stop() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+
+    collider.add_hitbox(
+        0.into(),
+        Shape::circle(2.0).place(v2(0.0, 0.0)).moving(v2(2.0, 0.0)),
+    );
+    collider.add_hitbox(
+        1.into(),
+        Shape::rect(v2(2.0, 10.0)).place(v2(100.0, 0.0)).still(),
+    );
+    collider.add_hitbox(
+        2.into(),
+        Shape::rect(v2(2.0, 10.0)).place(v2(200.0, 0.0)).still(),
+    );
+    collider.add_hitbox(3.into(), Shape::square(2.0).place(v2(50.0, 5.0)).still());
+
+    assert_eq!(
+        collider.projected_stop(0),
+        Some((1.into(), v2(98.0, 0.0), 49.0))
+    );
+    assert_eq!(
+        collider.projected_stop(1),
+        Some((0.into(), v2(100.0, 0.0), 49.0))
+    );
+    assert_eq!(collider.projected_stop(3), None);
+
+    advance(&mut collider, 10.0);
+    collider.set_hitbox_vel(0, HbVel::moving(v2(-1.0, 0.0)));
+    assert_eq!(collider.projected_stop(0), None);
+
+    // a hit that is too far in the future is ignored
+    collider.set_hitbox_vel(0, HbVel::moving(v2(1e-60, 1.0)));
+    collider.add_hitbox(
+        4.into(),
+        Shape::circle(2.0).place(v2(60.0, 0.0)).moving(v2(0.0, 1.0)),
+    );
+    assert_eq!(collider.projected_stop(0), None);
+}
+
+#[test]
+fn test_collisions_within() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0).place(v2(0.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(4.0, 0.0)).still());
+    collider.add_hitbox(2.into(), Shape::circle(2.0).place(v2(3.0, 0.0)).still());
+    collider.add_hitbox(3.into(), Shape::square(2.0).place(v2(0.0, 1.0)).still());
+
+    assert_eq!(collider.collisions_within(0, 0.5), vec![]);
+    assert_eq!(
+        collider.collisions_within(0, 2.5),
+        vec![(2.into(), 1.0), (1.into(), 2.0)]
+    );
+    assert_eq!(collider.collisions_within(3, 2.5), vec![]);
+
+    let scheduled = collider.scheduled_events();
+    advance(&mut collider, 0.5);
+    assert_eq!(collider.collisions_within(0, 1.0), vec![(2.into(), 1.0)]);
+    assert_eq!(collider.scheduled_events(), scheduled);
+}
+
+#[test]
+fn test_find_free_position() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+
+    collider.add_hitbox(0.into(), Shape::square(4.0).place(v2(0.0, 0.0)).still());
+
+    let shape = Shape::square(2.0);
+    assert_eq!(
+        collider.find_free_position(shape, v2(10.0, 0.0), &5.into(), 5.0),
+        Some(v2(10.0, 0.0))
+    );
+    assert_eq!(
+        collider.find_free_position(shape, v2(0.0, 0.0), &5.into(), 2.0),
+        None
+    );
+    let pos = collider
+        .find_free_position(shape, v2(0.0, 0.0), &5.into(), 10.0)
+        .unwrap();
+    assert!(pos.len() <= 10.0);
+    assert!(collider
+        .query_overlaps(&shape.place(pos), &5.into())
+        .is_empty());
+}
+
+#[test]
+#[should_panic(expected = "max_radius must be non-negative and finite")]
+fn test_find_free_position_infinite_radius() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::square(4.0).place(v2(0.0, 0.0)).still());
+    collider.find_free_position(Shape::square(2.0), v2(0.0, 0.0), &5.into(), Float::INFINITY);
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct TriggerHbProfile {
+    id: HbId,
+    trigger: bool,
+}
+
+impl HbProfile for TriggerHbProfile {
+    fn id(&self) -> HbId {
+        self.id
+    }
+    fn can_interact(&self, _other: &TriggerHbProfile) -> bool {
+        true
+    }
+    fn is_solid(&self, other: &TriggerHbProfile) -> bool {
+        !other.trigger
+    }
+}
+
+#[test]
+fn test_resolve_overlap_trigger() {
+    let mut collider = Collider::<TriggerHbProfile>::new(4.0, 0.25);
+
+    let player = TriggerHbProfile {
+        id: 0,
+        trigger: false,
+    };
+    let wall = TriggerHbProfile {
+        id: 1,
+        trigger: false,
+    };
+    let trigger = TriggerHbProfile {
+        id: 2,
+        trigger: true,
+    };
+    collider.add_hitbox(player, Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    assert_eq!(
+        collider.add_hitbox(wall, Shape::square(2.0).place(v2(1.5, 0.0)).still()),
+        vec![player]
+    );
+    assert_eq!(
+        collider.add_hitbox(trigger, Shape::square(2.0).place(v2(-1.5, 0.0)).still()),
+        vec![player]
+    );
+
+    assert_eq!(
+        collider.resolve_overlap_masked(0, 1, CardMask::full()),
+        Some(v2(-0.5, 0.0))
+    );
+    assert_eq!(
+        collider.resolve_overlap_masked(0, 2, CardMask::full()),
+        None
+    );
+    assert_eq!(
+        collider.resolve_overlap_masked(2, 0, CardMask::full()),
+        Some(v2(-0.5, 0.0))
+    );
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct DiscreteHbProfile {
+    id: HbId,
+    discrete: bool,
+}
+
+impl HbProfile for DiscreteHbProfile {
+    fn id(&self) -> HbId {
+        self.id
+    }
+    fn can_interact(&self, _other: &DiscreteHbProfile) -> bool {
+        true
+    }
+    fn detection_mode(&self) -> DetectionMode {
+        if self.discrete {
+            DetectionMode::Discrete
+        } else {
+            DetectionMode::Continuous
+        }
+    }
+}
+
+fn next_event_time(collider: &mut Collider<DiscreteHbProfile>) -> (Float, HbEvent) {
+    loop {
+        let time = collider.next_time();
+        assert!(time < 100.0);
+        collider.set_time(time);
+        if let Some((event, _, _)) = collider.next() {
+            return (collider.time(), event);
+        }
+    }
+}
+
+#[test]
+fn test_detection_mode() {
+    for &discrete in &[false, true] {
+        let mut collider = Collider::<DiscreteHbProfile>::new(4.0, 0.25);
+        let player = DiscreteHbProfile {
+            id: 0,
+            discrete: false,
+        };
+        let pickup = DiscreteHbProfile { id: 1, discrete };
+        let mut hitbox = Shape::square(2.0).place(v2(-9.5, 0.0)).moving(v2(1.0, 0.0));
+        hitbox.vel.end_time = 100.0;
+        collider.add_hitbox(player, hitbox);
+        collider.add_hitbox(pickup, Shape::square(2.0).place(v2(0.0, 0.0)).still());
+        if discrete {
+            assert!(collider
+                .scheduled_events()
+                .iter()
+                .all(|&(_, event)| matches!(event, EventDescription::Reiterate(_))));
+            assert_eq!(next_event_time(&mut collider), (8.0, HbEvent::Collide));
+            assert_eq!(next_event_time(&mut collider), (12.0, HbEvent::Separate));
+        } else {
+            assert_eq!(next_event_time(&mut collider), (7.5, HbEvent::Collide));
+            assert_eq!(next_event_time(&mut collider), (11.75, HbEvent::Separate));
+        }
+    }
+}
+
+#[test]
+fn test_group_bounds() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    assert_eq!(collider.group_bounds(0), None);
+    collider.add_hitbox(
+        0.into(),
+        Shape::circle(2.0).place(v2(-3.0, 1.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(
+        1.into(),
+        Shape::rect(v2(2.0, 4.0)).place(v2(5.0, 0.0)).still(),
+    );
+    advance(&mut collider, 2.0);
+    assert_eq!(
+        collider.group_bounds(0),
+        Some(Shape::rect(v2(8.0, 4.0)).place(v2(2.0, 0.0)))
+    );
+    assert_eq!(collider.group_bounds(1), None);
+}
+
+#[test]
+fn test_rewind_history() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.enable_history(2);
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0).place(v2(-5.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    assert_eq!(collider.history_len(), 0);
+
+    collider.set_time(1.0);
+    collider.set_time(2.0);
+    advance_to_event(&mut collider, 3.0);
+    assert_eq!(collider.history_len(), 2);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+
+    collider.rewind_to(1);
+    assert_eq!(collider.time(), 1.0);
+    assert_eq!(collider.history_len(), 0);
+    assert_eq!(collider.get_hitbox(0).value.pos, v2(-4.0, 0.0));
+    assert!(!collider.is_overlapping(0, 1));
+    advance_to_event(&mut collider, 3.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+
+    // settings are rewound along with the hitboxes
+    let bounds = Shape::rect(v2(20.0, 20.0)).place(v2(0.0, 0.0));
+    collider.set_bounds(Some(bounds));
+    advance(&mut collider, 3.5);
+    collider.set_bounds(None);
+    collider.rewind_to(0);
+    assert_eq!(collider.bounds(), Some(bounds));
+
+    collider.enable_history(0);
+    collider.set_time(4.0);
+    assert_eq!(collider.history_len(), 0);
+}
+
+#[test]
+fn test_resize_separation() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let mut hitbox = Shape::rect(v2(6.0, 4.0)).place(v2(0.0, 0.0)).still();
+    hitbox.vel.resize = v2(-1.0, 0.0);
+    collider.add_hitbox(0.into(), hitbox);
+    assert_eq!(
+        collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(3.0, 0.0)).still()),
+        vec![0.into()]
+    );
+    advance_to_event(&mut collider, 2.5);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Separate, 0.into(), 1.into()))
+    );
+    advance(&mut collider, 4.0);
+}
+
+#[test]
+fn test_event_time_histogram() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(
+        1.into(),
+        Shape::square(2.0).place(v2(-3.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(
+        2.into(),
+        Shape::square(2.0).place(v2(0.0, 5.5)).moving(v2(0.0, -1.0)),
+    );
+    collider.add_hitbox(
+        3.into(),
+        Shape::square(2.0)
+            .place(v2(10.0, 0.0))
+            .moving(v2(-1.0, 0.0)),
+    );
+    assert_eq!(collider.event_time_histogram(2.0, 5.0), vec![1, 2, 0]);
+    assert_eq!(collider.event_time_histogram(10.0, 10.0), vec![3]);
+    advance(&mut collider, 0.5);
+    assert_eq!(collider.event_time_histogram(1.0, 3.0), vec![1, 0, 0]);
+    assert_eq!(collider.event_time_histogram(1.0, 0.0), vec![]);
+}
+
+#[test]
+fn test_rotating_rect() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let hitbox = Shape::square(2.0).place(v2(-5.0, 0.0)).moving(v2(1.0, 0.0));
+    let mut rotating_hitbox = hitbox.clone();
+    rotating_hitbox.vel = HbVel::rotating(v2(1.0, 0.0), 0.5);
+    collider.add_hitbox(0.into(), rotating_hitbox.clone());
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    assert_eq!(collider.get_hitbox(0), rotating_hitbox);
+
+    advance(&mut collider, 2.0);
+    let shape = collider.get_hitbox(0).value;
+    assert_eq!(shape.kind(), ShapeKind::OrientedRect);
+    assert!((shape.pos - v2(-3.0, 0.0)).len() < EPS);
+    assert!((shape.shape.orientation() - v2((1 as Float).cos(), (1 as Float).sin())).len() < EPS);
+
+    // the corner of the rotating square reaches the still square when
+    // t + cos(t / 2) + sin(t / 2) = 4, at about t = 2.878, and the collision
+    // may be found early by the enlargement of the rotating square, but later
+    // than with its circumscribing circle at t = 4 - sqrt(2)
+    assert_eq!(
+        next_event(&mut collider),
+        (HbEvent::Collide, 0.into(), 1.into())
+    );
+    let time = collider.time();
+    assert!(time > 2.65 && time < 2.88);
+    let shape = collider.get_hitbox(0).value;
+    assert!(time + (time * 0.5).cos() + (time * 0.5).sin() <= 4.0);
+    assert_eq!(shape.kind(), ShapeKind::OrientedRect);
+
+    // once it stops rotating, the square is solved exactly as an oriented rect
+    collider.set_hitbox_vel(0, HbVel::moving(v2(0.0, 1.0)));
+    assert_eq!(
+        next_event(&mut collider),
+        (HbEvent::Separate, 0.into(), 1.into())
+    );
+    assert!(collider.time() > time);
+}
+
+#[test]
+fn test_invalid_rotation() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let mut hitbox = Shape::capsule(v2(2.0, 4.0)).place(v2(0.0, 0.0)).still();
+    hitbox.vel.angular = 1.0;
+    assert_eq!(
+        collider.try_add_hitbox(0.into(), hitbox),
+        Err(ColliderError::InvalidRotation)
+    );
+    let mut hitbox = Shape::square(2.0).place(v2(0.0, 0.0)).still();
+    hitbox.vel = HbVel::rotating(v2(0.0, 0.0), 1.0);
+    hitbox.vel.resize = v2(1.0, 1.0);
+    assert_eq!(
+        collider.try_add_hitbox(0.into(), hitbox.clone()),
+        Err(ColliderError::InvalidRotation)
+    );
+    hitbox.vel.resize = Vec2::zero();
+    assert_eq!(collider.try_add_hitbox(0.into(), hitbox), Ok(vec![]));
+}
+
+#[test]
+fn test_next_detailed() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0).place(v2(5.0, 0.5)).moving(v2(-1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    advance_to_event(&mut collider, 3.0);
+    let event = collider.next_detailed().unwrap();
+    assert_eq!(event.event, HbEvent::Collide);
+    assert_eq!(event.profile_1, 0.into());
+    assert_eq!(event.profile_2, 1.into());
+    assert_eq!(event.normal.dir(), v2(1.0, 0.0));
+    assert_eq!(event.normal.len(), 0.0);
+    assert_eq!(event.contact, v2(1.0, 0.25));
+    assert!(collider.next_detailed().is_none());
+
+    collider.set_hitbox_vel(0, HbVel::moving(v2(1.0, 0.0)));
+    advance_to_event(&mut collider, 3.25);
+    let event = collider.next_detailed().unwrap();
+    assert_eq!(event.event, HbEvent::Separate);
+    assert_eq!(event.normal.dir(), v2(1.0, 0.0));
+    assert_eq!(event.normal.len(), -0.25);
+}
+
+#[test]
+fn test_accelerating_hitbox() {
+    let sqrt8 = (8 as Float).sqrt();
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let mut hitbox = Shape::square(2.0).place(v2(0.0, 10.0)).still();
+    hitbox.vel = HbVel::accelerating(Vec2::zero(), v2(0.0, -2.0));
+    collider.add_hitbox(0.into(), hitbox);
+    collider.add_hitbox(
+        1.into(),
+        Shape::rect(v2(10.0, 2.0)).place(v2(0.0, 0.0)).still(),
+    );
+    let event = loop {
+        let time = collider.next_time();
+        assert!(time < 10.0);
+        collider.set_time(time);
+        if let Some(event) = collider.next() {
+            break event;
+        }
+    };
+    assert_eq!(event, (HbEvent::Collide, 0.into(), 1.into()));
+    assert!((collider.time() - sqrt8).abs() < EPS);
+    let hitbox = collider.get_hitbox(0);
+    assert!((hitbox.value.pos.y - 2.0).abs() < EPS);
+    assert!((hitbox.vel.value.y + 2.0 * sqrt8).abs() < EPS);
+    assert_eq!(hitbox.vel.accel, v2(0.0, -2.0));
+}
+
+#[test]
+fn test_segment_wall() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::segment(v2(10.0, 0.0)).place(v2(0.0, 0.0)).still(),
+    );
+    collider.add_hitbox(
+        1.into(),
+        Shape::circle(2.0).place(v2(3.0, 5.0)).moving(v2(0.0, -1.0)),
+    );
+    advance_to_event(&mut collider, 4.0);
+    let event = collider.next_detailed().unwrap();
+    assert_eq!(event.event, HbEvent::Collide);
+    assert_eq!(event.normal.dir(), v2(0.0, -1.0));
+    assert!(event.normal.len().abs() < 1e-9);
+    assert!((event.contact - v2(3.0, 0.0)).len() < 1e-9);
+
+    collider.set_hitbox_vel(1, HbVel::moving(v2(0.0, 1.0)));
+    advance_to_event(&mut collider, 4.25);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Separate, 0.into(), 1.into()))
+    );
+}
+
+#[test]
+fn test_segment_crossing() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::segment(v2(10.0, 0.0)).place(v2(0.0, 0.0)).still(),
+    );
+    collider.add_hitbox(
+        1.into(),
+        Shape::segment(v2(0.0, 4.0))
+            .place(v2(2.0, 5.0))
+            .moving(v2(0.0, -1.0)),
+    );
+    advance_to_event(&mut collider, 3.0);
+    let event = collider.next_detailed().unwrap();
+    assert_eq!(event.event, HbEvent::Collide);
+    assert!(event.contact.approx_eq(v2(2.0, 0.0), 1e-9));
+
+    advance_to_event(&mut collider, 7.25);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Separate, 0.into(), 1.into()))
+    );
+}
+
+#[test]
+fn test_snapshot_restore() {
+    fn record(
+        collider: &mut Collider<TestHbProfile>,
+        time: Float,
+    ) -> Vec<(Float, HbEvent, HbId, HbId)> {
+        let mut events = Vec::new();
+        while collider.time() < time {
+            while let Some((event, a, b)) = collider.next() {
+                events.push((collider.time(), event, a.id, b.id));
+            }
+            let new_time = collider.next_time().min(time);
+            collider.set_time(new_time);
+        }
+        events
+    }
+
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0)
+            .place(v2(-10.0, 0.0))
+            .moving(v2(2.0, 0.0)),
+    );
+    collider.add_hitbox(
+        1.into(),
+        Shape::circle(2.0)
+            .place(v2(10.0, 0.5))
+            .moving(v2(-1.0, 0.0)),
+    );
+    collider.add_hitbox(
+        2.into(),
+        Shape::rect(v2(1.0, 6.0))
+            .place(v2(0.0, 10.0))
+            .moving(v2(0.0, -1.5)),
+    );
+    collider.add_hitbox(3.into(), Shape::square(3.0).place(v2(1.0, -1.0)).still());
+
+    record(&mut collider, 2.5);
+    let state = collider.snapshot();
+    let expected = record(&mut collider, 20.0);
+    assert!(expected.len() >= 4);
+
+    collider.restore(state.clone());
+    assert_eq!(collider.time(), 2.5);
+    assert_eq!(collider.get_hitbox(0).value.pos, v2(-5.0, 0.0));
+    assert_eq!(record(&mut collider, 20.0), expected);
+
+    collider.remove_hitbox(1);
+    collider.restore(state);
+    assert_eq!(record(&mut collider, 20.0), expected);
+
+    // settings and compounds are part of the state
+    let bounds = Shape::rect(v2(100.0, 100.0)).place(v2(0.0, 0.0));
+    collider.set_bounds(Some(bounds));
+    collider.set_frozen(true);
+    let state = collider.snapshot();
+    collider.set_bounds(None);
+    collider.set_frozen(false);
+    collider.add_compound(
+        4.into(),
+        vec![(v2(-60.0, 0.0), Shape::square(1.0))],
+        HbVel::still(),
+    );
+    collider.restore(state);
+    assert_eq!(collider.bounds(), Some(bounds));
+    assert!(collider.is_frozen());
+    collider.add_compound(
+        4.into(),
+        vec![(v2(-60.0, 0.0), Shape::square(1.0))],
+        HbVel::still(),
+    );
+    assert_eq!(collider.out_of_bounds(), vec![4.into()]);
+}
+
+#[test]
+fn test_raycast() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::circle(2.0).place(v2(5.0, 0.0)).still());
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(10.0, 0.0)).still());
+    collider.add_hitbox(
+        2.into(),
+        Shape::square(2.0)
+            .place(v2(-6.0, -6.0))
+            .moving(v2(1.0, 1.0)),
+    );
+
+    assert_eq!(
+        collider.raycast(v2(0.0, 0.0), v2(1.0, 0.0), 20.0, &5.into()),
+        Some((0.into(), 4.0, v2(4.0, 0.0)))
+    );
+    assert_eq!(
+        collider.raycast(v2(7.0, 0.0), v2(2.0, 0.0), 20.0, &5.into()),
+        Some((1.into(), 2.0, v2(9.0, 0.0)))
+    );
+    assert_eq!(
+        collider.raycast(v2(20.0, 0.0), v2(-1.0, 0.0), 20.0, &5.into()),
+        Some((1.into(), 9.0, v2(11.0, 0.0)))
+    );
+    assert_eq!(
+        collider.raycast(v2(20.0, 0.0), v2(-1.0, 0.0), 8.0, &5.into()),
+        None
+    );
+    assert_eq!(
+        collider.raycast(v2(5.0, 0.5), v2(0.0, 1.0), 1.0, &5.into()),
+        Some((0.into(), 0.0, v2(5.0, 0.5)))
+    );
+
+    let (profile, dist, point) = collider
+        .raycast(v2(0.0, 0.0), v2(-1.0, -1.0), 20.0, &5.into())
+        .unwrap();
+    assert_eq!(profile, 2.into());
+    assert!((dist - 5.0 * (2 as Float).sqrt()).abs() < EPS);
+    assert!((point - v2(-5.0, -5.0)).len() < EPS);
+
+    advance(&mut collider, 2.0);
+    let (_, dist, _) = collider
+        .raycast(v2(0.0, 0.0), v2(-1.0, -1.0), 20.0, &5.into())
+        .unwrap();
+    assert!((dist - 3.0 * (2 as Float).sqrt()).abs() < EPS);
+}
+
+#[test]
+fn test_query_point() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::square(4.0).place(v2(2.0, 2.0)).still());
+    collider.add_hitbox(1.into(), Shape::circle(2.0).place(v2(5.0, 2.0)).still());
+    collider.add_hitbox(
+        2.into(),
+        Shape::square(2.0).place(v2(-5.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+
+    assert_eq!(
+        collider.query_point(v2(1.0, 1.0), &5.into()),
+        vec![0.into()]
+    );
+    assert_eq!(
+        sort(collider.query_point(v2(4.0, 2.0), &5.into())),
+        vec![0.into(), 1.into()]
+    );
+    assert_eq!(
+        collider.query_point(v2(4.0, 4.0), &5.into()),
+        vec![0.into()]
+    );
+    assert_eq!(
+        collider.query_point(v2(0.0, 0.0), &5.into()),
+        vec![0.into()]
+    );
+    assert!(collider.query_point(v2(4.5, 3.5), &5.into()).is_empty());
+    assert!(collider.query_point(v2(-1.0, 0.0), &5.into()).is_empty());
+
+    advance(&mut collider, 3.0);
+    assert_eq!(
+        collider.query_point(v2(-1.0, 0.0), &5.into()),
+        vec![2.into()]
+    );
+}
+
+#[test]
+fn test_try_add_hitbox() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    assert_eq!(
+        collider.try_add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still()),
+        Ok(vec![])
+    );
+    assert_eq!(
+        collider.try_add_hitbox(0.into(), Shape::square(2.0).place(v2(5.0, 0.0)).still()),
+        Err(ColliderError::IdInUse)
+    );
+    assert_eq!(
+        collider.try_add_hitbox(1.into(), Shape::square(0.1).place(v2(5.0, 0.0)).still()),
+        Err(ColliderError::ShapeTooSmall)
+    );
+    assert_eq!(
+        collider.try_add_hitbox(
+            1.into(),
+            Shape::square(2.0).place(v2(Float::NAN, 0.0)).still()
+        ),
+        Err(ColliderError::NonFinite)
+    );
+    assert_eq!(
+        collider.try_add_hitbox(
+            1.into(),
+            Shape::circle(2.0).place(v2(5.0, 0.0)).still_until(-1.0)
+        ),
+        Err(ColliderError::EndTimeInPast)
+    );
+    let mut hitbox = Shape::circle(2.0).place(v2(5.0, 0.0)).still();
+    hitbox.vel.resize = v2(1.0, 0.0);
+    assert_eq!(
+        collider.try_add_hitbox(1.into(), hitbox),
+        Err(ColliderError::InvalidResize)
+    );
+    assert!(collider.query_point(v2(5.0, 0.0), &5.into()).is_empty());
+
+    assert_eq!(
+        collider.try_add_hitbox(1.into(), Shape::circle(2.0).place(v2(1.5, 0.0)).still()),
+        Ok(vec![0.into()])
+    );
+}
+
+#[test]
+fn test_try_set_hitbox_vel() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    assert_eq!(
+        collider.try_set_hitbox_vel(1, HbVel::moving(v2(1.0, 0.0))),
+        Err(ColliderError::IdNotFound)
+    );
+    assert_eq!(
+        collider.try_set_hitbox_vel(0, HbVel::moving(v2(Float::INFINITY, 0.0))),
+        Err(ColliderError::NonFinite)
+    );
+    let mut vel = HbVel::still();
+    vel.resize = v2(-1.0, 0.0);
+    vel.end_time = 2.5;
+    collider.set_time(1.0);
+    assert_eq!(
+        collider.try_set_hitbox_vel(0, HbVel::moving_until(v2(1.0, 0.0), 0.5)),
+        Err(ColliderError::EndTimeInPast)
+    );
+    assert_eq!(collider.get_hitbox(0).vel, HbVel::still());
+    assert_eq!(collider.try_set_hitbox_vel(0, vel.clone()), Ok(()));
+    assert_eq!(collider.get_hitbox(0).vel, vel);
+}
+
+#[test]
+#[should_panic(expected = "hitbox id 0: hitbox id is already in use")]
+fn test_add_hitbox_id_in_use() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(5.0, 0.0)).still());
+}
+
+#[test]
+fn test_iter_hitboxes() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    assert!(collider.is_empty());
+    assert_eq!(collider.iter().count(), 0);
+    collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(
+        0.into(),
+        Shape::circle(2.0).place(v2(-5.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(5.0, 0.0)).still());
+    collider.remove_hitbox(2);
+    assert!(!collider.is_empty());
+    assert_eq!(collider.len(), 2);
+
+    let mut ids: Vec<HbId> = collider.ids().collect();
+    ids.sort();
+    assert_eq!(ids, vec![0, 1]);
+    let mut entries: Vec<(HbId, TestHbProfile)> = collider.iter().collect();
+    entries.sort();
+    assert_eq!(entries, vec![(0, 0.into()), (1, 1.into())]);
+
+    let next_time = collider.next_time();
+    advance(&mut collider, 1.0);
+    assert_eq!(collider.iter().count(), 2);
+    assert_eq!(collider.next_time(), next_time);
+}
+
+#[test]
+fn test_predicted_times() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0).place(v2(-5.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(0.0, 5.0)).still());
+
+    assert_eq!(collider.predicted_collision_time(0, 1), Some(3.0));
+    assert_eq!(collider.predicted_collision_time(1, 0), Some(3.0));
+    assert_eq!(collider.predicted_collision_time(0, 2), None);
+    assert_eq!(collider.predicted_collision_time(1, 2), None);
+    assert_eq!(collider.predicted_separation_time(0, 1), Some(0.0));
+
+    advance_to_event(&mut collider, 3.0);
+    assert_eq!(collider.predicted_collision_time(0, 1), Some(3.0));
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+    assert_eq!(collider.predicted_separation_time(0, 1), Some(7.25));
+    advance_to_event(&mut collider, 7.25);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Separate, 0.into(), 1.into()))
+    );
+    collider.set_hitbox_vel(0, HbVel::moving(v2(-1.0, 0.0)));
+
+    assert_eq!(collider.predicted_collision_time(0, 1), Some(7.5));
+    advance_to_event(&mut collider, 7.5);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+    collider.set_hitbox_vel(0, HbVel::still());
+    assert_eq!(collider.predicted_separation_time(0, 1), None);
+}
+
+#[test]
+fn test_add_hitboxes() {
+    fn hitboxes() -> Vec<(TestHbProfile, Hitbox)> {
+        vec![
+            (0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still()),
+            (1.into(), Shape::circle(2.0).place(v2(1.5, 0.0)).still()),
+            (
+                2.into(),
+                Shape::square(2.0).place(v2(-5.0, 0.5)).moving(v2(1.0, 0.0)),
+            ),
+            (
+                3.into(),
+                Shape::rect(v2(1.0, 4.0)).place(v2(0.0, 2.5)).still(),
+            ),
+            (
+                4.into(),
+                Shape::circle(2.0).place(v2(8.0, 0.0)).moving(v2(-2.0, 0.0)),
+            ),
+        ]
+    }
+
+    let mut sequential = Collider::<TestHbProfile>::new(4.0, 0.25);
+    sequential.add_hitbox(5.into(), Shape::square(2.0).place(v2(3.0, 0.0)).still());
+    let mut batch = Collider::<TestHbProfile>::new(4.0, 0.25);
+    batch.add_hitbox(5.into(), Shape::square(2.0).place(v2(3.0, 0.0)).still());
+
+    let mut expected = Vec::new();
+    for (profile, hitbox) in hitboxes() {
+        for other in sequential.add_hitbox(profile, hitbox) {
+            expected.push((profile.min(other), profile.max(other)));
+        }
+    }
+    let mut pairs = batch.add_hitboxes(hitboxes());
+    pairs.sort();
+    expected.sort();
+    assert_eq!(
+        pairs,
+        vec![
+            (0.into(), 1.into()),
+            (0.into(), 3.into()),
+            (1.into(), 5.into())
+        ]
+    );
+    assert_eq!(pairs, expected);
+
+    assert_eq!(batch.len(), 6);
+    assert_eq!(sort(batch.get_overlaps(1)), vec![0.into(), 5.into()]);
+    let mut events = Vec::new();
+    while batch.time() < 10.0 {
+        while let Some(event) = batch.next() {
+            events.push((batch.time(), event));
+            assert_eq!(sequential.next(), Some(event));
+        }
+        assert_eq!(sequential.next(), None);
+        let time = batch.next_time().min(10.0);
+        batch.set_time(time);
+        sequential.set_time(time);
+    }
+    assert!(!events.is_empty());
+}
+
+#[test]
+fn test_add_hitboxes_groups() {
+    // group 1 uses smaller cells, and the large hitboxes span many cells
+    let hitboxes = vec![
+        (
+            GroupHbProfile::new(0, 0),
+            Shape::rect(v2(12.0, 2.0)).place(v2(0.0, 0.0)).still(),
+        ),
+        (
+            GroupHbProfile::new(1, 1),
+            Shape::square(0.5).place(v2(3.0, 1.0)).still(),
+        ),
+        (
+            GroupHbProfile::new(2, 1),
+            Shape::rect(v2(6.0, 3.0)).place(v2(-2.0, 1.0)).still(),
+        ),
+        (
+            GroupHbProfile::new(3, 0),
+            Shape::circle(3.0).place(v2(-4.0, 1.0)).still(),
+        ),
+        (
+            GroupHbProfile::new(4, 1),
+            Shape::square(1.0).place(v2(20.0, 0.0)).still(),
+        ),
+    ];
+    let mut sequential = Collider::<GroupHbProfile>::new(4.0, 0.25);
+    sequential.set_group_cell_width(1, 1.0);
+    let mut batch = Collider::<GroupHbProfile>::new(4.0, 0.25);
+    batch.set_group_cell_width(1, 1.0);
+    sequential.add_hitbox(
+        GroupHbProfile::new(5, 0),
+        Shape::square(2.0).place(v2(4.0, 2.0)).still(),
+    );
+    batch.add_hitbox(
+        GroupHbProfile::new(5, 0),
+        Shape::square(2.0).place(v2(4.0, 2.0)).still(),
+    );
+
+    let mut expected = Vec::new();
+    for &(profile, ref hitbox) in &hitboxes {
+        for other in sequential.add_hitbox(profile, hitbox.clone()) {
+            expected.push((profile.id.min(other.id), profile.id.max(other.id)));
+        }
+    }
+    let mut pairs: Vec<(HbId, HbId)> = batch
+        .add_hitboxes(hitboxes)
+        .into_iter()
+        .map(|(profile_1, profile_2)| (profile_1.id, profile_2.id))
+        .collect();
+    pairs.sort();
+    expected.sort();
+    assert_eq!(pairs, vec![(0, 1), (0, 2), (0, 3), (0, 5), (1, 5), (2, 3)]);
+    assert_eq!(pairs, expected);
+}
+
+#[test]
+#[should_panic(expected = "hitbox id 1: hitbox id is already in use")]
+fn test_add_hitboxes_duplicate_id() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitboxes(vec![
+        (1.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still()),
+        (1.into(), Shape::square(2.0).place(v2(5.0, 0.0)).still()),
+    ]);
+}
+
+#[test]
+fn test_grid_stats() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    assert_eq!(collider.grid_stats(), GridStats::default());
+
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(2.0, 2.0)).still());
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(4.0, 2.0)).still());
+    collider.add_hitbox(
+        2.into(),
+        Shape::square(2.0).place(v2(-2.0, 2.0)).moving(v2(1.0, 0.0)),
+    );
+    let stats = collider.grid_stats();
+    assert_eq!(stats.occupied_cells, 3);
+    assert_eq!(stats.max_hitboxes_per_cell, 3);
+    assert!((stats.mean_hitboxes_per_cell - 5.0 / 3.0).abs() < 1e-9);
+
+    collider.remove_hitbox(0);
+    let stats = collider.grid_stats();
+    assert_eq!(stats.occupied_cells, 3);
+    assert_eq!(stats.max_hitboxes_per_cell, 2);
+    assert!((stats.mean_hitboxes_per_cell - 4.0 / 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_capsule_corner() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::capsule(v2(2.0, 4.0))
+            .place(v2(0.0, 0.0))
+            .moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(4.0, 2.5)).still());
+
+    // a rect with the same bounds would collide at time 2.0
+    let collide_time = 3.0 - (0.75 as Float).sqrt();
+    advance(&mut collider, 2.0);
+    let event = loop {
+        if let Some(event) = collider.next_detailed() {
+            break event;
+        }
+        let time = collider.next_time();
+        collider.set_time(time);
+    };
+    assert!((collider.time() - collide_time).abs() < EPS);
+    assert_eq!(event.event, HbEvent::Collide);
+    assert!((event.normal.dir() - v2(-(0.75 as Float).sqrt(), -0.5)).len() < EPS);
+
+    let mut hitbox = Shape::capsule(v2(2.0, 4.0)).place(v2(10.0, 0.0)).still();
+    hitbox.vel.resize = v2(1.0, 1.0);
+    assert_eq!(
+        collider.try_add_hitbox(2.into(), hitbox),
+        Err(ColliderError::InvalidResize)
+    );
+}
+
+#[test]
+fn test_nearest() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    assert_eq!(collider.nearest(v2(0.0, 0.0), 100.0, &5.into()), None);
+    assert_eq!(collider.nearest(v2(0.0, 0.0), 1e30, &5.into()), None);
+
+    collider.add_hitbox(0.into(), Shape::circle(2.0).place(v2(30.0, 0.0)).still());
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(0.0, -25.0)).still());
+    collider.add_hitbox(
+        2.into(),
+        Shape::square(2.0)
+            .place(v2(-10.0, 10.0))
+            .moving(v2(0.0, -1.0)),
+    );
+
+    let (profile, dist) = collider.nearest(v2(0.0, 0.0), 100.0, &5.into()).unwrap();
+    assert_eq!(profile, 2.into());
+    assert!((dist - 9.0 * (2 as Float).sqrt()).abs() < EPS);
+    assert_eq!(
+        collider.nearest(v2(20.0, 0.0), 100.0, &5.into()),
+        Some((0.into(), 9.0))
+    );
+    assert_eq!(collider.nearest(v2(20.0, 0.0), 8.0, &5.into()), None);
+    assert_eq!(
+        collider.nearest(v2(0.0, -24.5), 1.0, &5.into()),
+        Some((1.into(), -0.5))
+    );
+
+    advance(&mut collider, 10.0);
+    assert_eq!(
+        collider.nearest(v2(0.0, 0.0), 100.0, &5.into()),
+        Some((2.into(), 9.0))
+    );
+    collider.add_hitbox(3.into(), Shape::square(2.0).place(v2(10.0, 0.0)).still());
+    assert_eq!(
+        collider.nearest(v2(0.0, 0.0), 100.0, &5.into()),
+        Some((2.into(), 9.0))
+    );
+    collider.remove_hitbox(2);
+    assert_eq!(
+        collider.nearest(v2(0.0, 0.0), 100.0, &5.into()),
+        Some((3.into(), 9.0))
+    );
+    assert_eq!(
+        collider.nearest(v2(0.0, 0.0), 1e30, &5.into()),
+        Some((3.into(), 9.0))
+    );
+    collider.remove_hitbox(0);
+    collider.remove_hitbox(1);
+    collider.remove_hitbox(3);
+    assert_eq!(collider.nearest(v2(0.0, 0.0), 1e30, &5.into()), None);
+}
+
+#[test]
+fn test_peek_event() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    assert_eq!(collider.peek_event(), None);
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0).place(v2(-2.5, 0.0)).moving(v2(1.0, 0.0)),
+    );
+
+    assert_eq!(collider.peek_event(), Some((HbEvent::Collide, 0, 1, 0.5)));
+    assert_eq!(collider.next_time(), 0.5);
+    let scheduled = collider.scheduled_events();
+    assert_eq!(collider.peek_event(), Some((HbEvent::Collide, 0, 1, 0.5)));
+    assert_eq!(collider.scheduled_events(), scheduled);
+
+    collider.set_time(0.5);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+    assert_eq!(collider.next(), None);
+
+    // an internal event is next
+    assert_eq!(collider.next_time(), 4.0);
+    assert_eq!(collider.peek_event(), None);
+    advance(&mut collider, 4.0);
+    assert_eq!(collider.next(), None);
+    assert_eq!(collider.peek_event(), Some((HbEvent::Separate, 0, 1, 4.75)));
+
+    collider.set_hitbox_vel(0, HbVel::still());
+    assert_eq!(collider.peek_event(), None);
+}
+
+#[test]
+fn test_group_cell_width() {
+    let terrain = GroupHbProfile::new(0, 0);
+    let bullet = GroupHbProfile::new(1, 1);
+    let mut collider = Collider::<GroupHbProfile>::new(10.0, 0.25);
+    collider.add_hitbox(terrain, Shape::square(8.0).place(v2(20.0, 5.0)).still());
+    collider.add_hitbox(
+        bullet,
+        Shape::square(0.5).place(v2(0.5, 5.0)).moving(v2(4.0, 0.0)),
+    );
+    assert_eq!(collider.hitboxes_in_cell((0, 0)), vec![1]);
+    assert_eq!(collider.hitboxes_in_cell((1, 0)), vec![0, 1]);
+
+    // moves the existing bullet into the smaller cells
+    collider.set_group_cell_width(1, 1.0);
+    assert_eq!(collider.hitboxes_in_cell((0, 0)), vec![]);
+    assert_eq!(collider.hitboxes_in_cell((0, 5)), vec![1]);
+    assert_eq!(collider.hitboxes_in_cell((1, 0)), vec![0]);
+
+    // the bullet still finds the terrain in the larger cells
+    let (event, profile_1, profile_2) = next_event(&mut collider);
+    assert!((collider.time() - 15.25 / 4.0).abs() < 1e-9);
+    assert_eq!(event, HbEvent::Collide);
+    assert_eq!(profile_1.id.min(profile_2.id), 0);
+    assert_eq!(profile_1.id.max(profile_2.id), 1);
+}
+
+#[test]
+fn test_hitbox_time_remaining() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    let mut hitbox = Shape::square(2.0).place(v2(10.0, 0.0)).moving(v2(1.0, 0.0));
+    hitbox.vel.end_time = 3.0;
+    collider.add_hitbox(1.into(), hitbox);
+    assert_eq!(collider.hitbox_time_remaining(0), Float::INFINITY);
+    assert_eq!(collider.hitbox_time_remaining(1), 3.0);
+
+    advance(&mut collider, 1.25);
+    assert_eq!(collider.hitbox_time_remaining(1), 1.75);
+}
+
+#[test]
+fn test_set_hitbox_enabled() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(1.0, 0.0)).still());
+    collider.add_hitbox(
+        2.into(),
+        Shape::square(2.0).place(v2(-5.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+
+    assert_eq!(collider.set_hitbox_enabled(0, false), vec![1.into()]);
+    assert!(collider.set_hitbox_enabled(0, false).is_empty());
+    assert!(collider.get_overlaps(1).is_empty());
+
+    // hitbox 2 passes through the disabled hitbox 0 before reaching hitbox 1
+    advance(&mut collider, 3.0);
+    assert!(collider.get_overlaps(0).is_empty());
+    assert_eq!(collider.get_hitbox(0).value.pos, v2(0.0, 0.0));
+
+    assert_eq!(
+        sort(collider.set_hitbox_enabled(0, true)),
+        vec![1.into(), 2.into()]
+    );
+    assert!(collider.is_overlapping(0, 2));
+    advance_to_event(&mut collider, 4.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 1.into(), 2.into()))
+    );
+}
+
+#[test]
+fn test_overlap_normal() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(1.5, 0.0)).still());
+    collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(10.0, 0.0)).still());
+
+    let normal = collider.overlap_normal(1, 0).unwrap();
+    assert_eq!(normal.dir(), v2(1.0, 0.0));
+    assert_eq!(normal.len(), 0.5);
+    let normal = collider.overlap_normal(0, 1).unwrap();
+    assert_eq!(normal.dir(), v2(-1.0, 0.0));
+    assert_eq!(collider.overlap_normal(0, 2), None);
+}
+
+#[test]
+fn test_triangle_slope() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::triangle(v2(8.0, 8.0)).place(v2(0.0, 0.0)).still(),
+    );
+    let diag = v2(1.0, 1.0) * (0.5 as Float).sqrt();
+    collider.add_hitbox(
+        1.into(),
+        Shape::circle(2.0)
+            .place(v2(-2.0, 3.0) + diag)
+            .moving(v2(0.0, -1.0)),
+    );
+
+    assert_eq!(
+        next_event(&mut collider),
+        (HbEvent::Collide, 0.into(), 1.into())
+    );
+    assert!((collider.time() - 1.0).abs() < EPS);
+    collider.set_hitbox_vel(1, HbVel::moving(v2(1.0, -1.0)));
+
+    // the circle slides down the hypotenuse without separating until it
+    // passes the bottom corner of the triangle
+    advance(&mut collider, 7.0);
+    let time = collider.next_time();
+    assert!(time > 7.0 && time < 8.0);
+    advance_to_event(&mut collider, time);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Separate, 0.into(), 1.into()))
+    );
+}
+
+#[test]
+fn test_next_bounded() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    for id in 0..3 {
+        collider.add_hitbox(
+            id.into(),
+            Shape::square(2.0)
+                .place(v2(id as Float * 10.0, 0.0))
+                .moving(v2(1.0, 0.0)),
+        );
+    }
+    collider.add_hitbox(3.into(), Shape::square(2.0).place(v2(7.0, 0.0)).still());
+
+    // all three hitboxes move to new grid cells at time 4.0
+    collider.set_time(4.0);
+    assert_eq!(collider.next_bounded(0), None);
+    assert_eq!(collider.next_bounded(2), None);
+    assert_eq!(collider.next_time(), 4.0);
+    assert_eq!(collider.next_bounded(1), None);
+    assert!(collider.next_time() > 4.0);
+
+    // user events are returned regardless of the limit
+    collider.set_time(5.0);
+    assert_eq!(
+        collider.next_bounded(0),
+        Some((HbEvent::Collide, 0.into(), 3.into()))
+    );
+
+    // a second part of a compound colliding with the same hitbox is counted as
+    // an internal event
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let parts = vec![
+        (v2(0.0, -1.0), Shape::square(1.0)),
+        (v2(0.0, 1.0), Shape::square(1.0)),
+    ];
+    collider.add_compound(0.into(), parts, HbVel::moving(v2(1.0, 0.0)));
+    collider.add_hitbox(
+        1.into(),
+        Shape::rect(v2(1.0, 4.0)).place(v2(2.5, 0.0)).still(),
+    );
+    collider.set_time(1.5);
+    assert_eq!(
+        collider.next_bounded(0),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+    assert!(collider.next_is_internal());
+    assert_eq!(collider.next_bounded(0), None);
+    assert_eq!(collider.next_time(), 1.5);
+    assert_eq!(collider.next_bounded(1), None);
+    assert!(collider.next_time() > 1.5);
+}
+
+#[test]
+fn test_all_overlaps() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    assert!(collider.all_overlaps().is_empty());
+    collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(1.0, 0.0)).still());
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(-1.0, 0.0)).still());
+    collider.add_hitbox(3.into(), Shape::square(2.0).place(v2(10.0, 0.0)).still());
+    assert_eq!(
+        collider.all_overlaps(),
+        vec![(0.into(), 2.into()), (1.into(), 2.into())]
+    );
+}
+
+#[test]
+fn test_resize_anchor() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let mut wall = Shape::rect(v2(2.0, 2.0)).place(v2(0.0, 0.0)).still();
+    wall.vel.resize = v2(1.0, 0.0);
+    wall.vel.resize_anchor = Some(Card::MinusX);
+    collider.add_hitbox(0.into(), wall);
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(-5.0, 0.0)).still());
+    collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(5.0, 0.0)).still());
+
+    // the left edge of the wall stays in place while the right edge moves at the full resize speed
+    advance_to_event(&mut collider, 3.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 2.into()))
+    );
+    let hitbox = collider.get_hitbox(0);
+    assert_eq!(hitbox.value, Shape::rect(v2(5.0, 2.0)).place(v2(1.5, 0.0)));
+    assert!(collider.get_overlaps(1).is_empty());
+}
+
+#[test]
+fn test_closest_approach() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::circle(1.0).place(v2(0.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(
+        1.into(),
+        Shape::circle(1.0)
+            .place(v2(20.0, 4.0))
+            .moving(v2(-1.0, 0.0)),
+    );
+    advance(&mut collider, 2.0);
+    assert_eq!(collider.closest_approach(0, 1), (10.0, 4.0));
+    assert_eq!(collider.closest_approach(1, 0), (10.0, 4.0));
+
+    advance(&mut collider, 12.0);
+    assert_eq!(
+        collider.closest_approach(0, 1),
+        (12.0, (32 as Float).sqrt())
+    );
+}
+
+#[test]
+fn test_interact_key() {
+    thread_local! {
+        static INTERACT_CALLS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    #[derive(Copy, Clone, Debug)]
+    struct KeyedProfile {
+        id: HbId,
+        key: u64,
+    }
+
+    impl HbProfile for KeyedProfile {
+        fn id(&self) -> HbId {
+            self.id
+        }
+        fn can_interact(&self, _other: &KeyedProfile) -> bool {
+            INTERACT_CALLS.with(|calls| calls.set(calls.get() + 1));
+            true
+        }
+        fn interact_key(&self) -> u64 {
+            self.key
+        }
+    }
+
+    let mut collider = Collider::<KeyedProfile>::new(4.0, 0.25);
+    for id in 0..4 {
+        let overlaps = collider.add_hitbox(
+            KeyedProfile { id, key: 7 },
+            Shape::square(2.0).place(v2(id as Float * 0.5, 0.0)).still(),
+        );
+        assert_eq!(overlaps.len(), id as usize);
+    }
+    assert_eq!(INTERACT_CALLS.with(|calls| calls.get()), 1);
+
+    let overlaps = collider.add_hitbox(
+        KeyedProfile { id: 4, key: 0 },
+        Shape::square(2.0).place(v2(0.0, 0.0)).still(),
+    );
+    assert_eq!(overlaps.len(), 4);
+    assert_eq!(INTERACT_CALLS.with(|calls| calls.get()), 5);
+}
+
+#[test]
+fn test_remove_group() {
+    let terrain = GroupHbProfile::new(0, 0);
+    let enemies = [
+        GroupHbProfile::new(1, 1),
+        GroupHbProfile::new(2, 1),
+        GroupHbProfile::new(3, 1),
+    ];
+    let mut collider = Collider::<GroupHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(terrain, Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(enemies[1], Shape::square(2.0).place(v2(1.0, 0.0)).still());
+    collider.add_hitbox(enemies[0], Shape::square(2.0).place(v2(1.5, 0.0)).still());
+    collider.add_hitbox(
+        enemies[2],
+        Shape::square(2.0)
+            .place(v2(-10.0, 0.0))
+            .moving(v2(1.0, 0.0)),
+    );
+    let swarm = GroupHbProfile::new(4, 1);
+    let parts = vec![
+        (v2(0.0, -1.0), Shape::square(1.0)),
+        (v2(0.0, 1.0), Shape::square(1.0)),
+    ];
+    collider.add_compound(swarm, parts.clone(), HbVel::still());
+    assert_eq!(collider.get_overlaps(0).len(), 3);
+    assert!(collider.next_time() < Float::INFINITY);
+
+    let mut removed = enemies.to_vec();
+    removed.push(swarm);
+    assert_eq!(collider.remove_group(1), removed);
+    assert!(collider.remove_group(1).is_empty());
+    assert_eq!(collider.len(), 1);
+    assert!(collider.get_overlaps(0).is_empty());
+    assert_eq!(collider.next_time(), Float::INFINITY);
+    assert!(collider.all_overlaps().is_empty());
+    assert_eq!(
+        collider.add_compound(swarm, parts, HbVel::still()),
+        vec![terrain]
+    );
+}
+
+#[test]
+fn test_hitbox_at() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let mut hitbox = Shape::square(2.0).place(v2(0.0, 0.0)).moving(v2(1.0, 2.0));
+    hitbox.vel.end_time = 5.0;
+    collider.add_hitbox(0.into(), hitbox);
+    advance(&mut collider, 1.0);
+
+    let future = collider.hitbox_at(0, 3.0);
+    assert_eq!(future.value, Shape::square(2.0).place(v2(3.0, 6.0)));
+    assert_eq!(future.vel.value, v2(1.0, 2.0));
+    assert_eq!(future.vel.end_time, 5.0);
+    assert_eq!(
+        collider.hitbox_at(0, 1.0).value,
+        collider.get_hitbox(0).value
+    );
+    assert_eq!(collider.get_hitbox(0).value.pos, v2(1.0, 2.0));
+}
+
+#[test]
+#[should_panic]
+fn test_hitbox_at_past_end_time() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let mut hitbox = Shape::square(2.0).place(v2(0.0, 0.0)).moving(v2(1.0, 2.0));
+    hitbox.vel.end_time = 5.0;
+    collider.add_hitbox(0.into(), hitbox);
+    collider.hitbox_at(0, 6.0);
+}
+
+#[test]
+fn test_next_is_internal() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    assert!(!collider.next_is_internal());
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    assert!(!collider.next_is_internal());
+    collider.add_hitbox(
+        1.into(),
+        Shape::square(2.0)
+            .place(v2(-20.0, 0.0))
+            .moving(v2(1.0, 0.0)),
+    );
+    assert!(collider.next_is_internal());
+    let time = collider.next_time();
+    collider.set_time(time);
+    assert!(collider.next().is_none());
+
+    advance_to_event(&mut collider, 18.0);
+    assert!(!collider.next_is_internal());
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+}
+
+#[test]
+fn test_query_sweep() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(5.0, 0.0)).still());
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(2.0, 0.0)).still());
+    collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(3.0, 5.0)).still());
+    collider.add_hitbox(
+        3.into(),
+        Shape::square(2.0).place(v2(3.0, -5.0)).moving(v2(0.0, 2.0)),
+    );
+
+    let sword = Shape::rect(v2(1.0, 0.5)).place(v2(-1.0, 0.0));
+    assert_eq!(
+        collider.query_sweep(&sword, v2(2.0, 0.0), 1.5, &4.into()),
+        vec![1.into()]
+    );
+    assert_eq!(
+        collider.query_sweep(&sword, v2(2.0, 0.0), 0.5, &4.into()),
+        Vec::<TestHbProfile>::new()
+    );
+    // hitbox 3 moves up into the path of the sweep
+    assert_eq!(
+        collider.query_sweep(&sword, v2(2.0, 0.0), 3.0, &4.into()),
+        vec![1.into(), 3.into(), 0.into()]
+    );
+    assert_eq!(collider.len(), 4);
+}
+
+#[test]
+fn test_set_group_padding() {
+    let mut collider = Collider::<GroupHbProfile>::new(4.0, 0.25);
+    collider.set_group_padding(0, 0.125);
+    collider.set_group_padding(1, 1.0);
+    let wall = GroupHbProfile::new(0, 0);
+    let boulder = GroupHbProfile::new(1, 1);
+    let bullet = GroupHbProfile::new(2, 0);
+    collider.add_hitbox(wall, Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(
+        boulder,
+        Shape::square(2.0).place(v2(1.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(
+        bullet,
+        Shape::square(2.0)
+            .place(v2(-1.0, 0.0))
+            .moving(v2(-1.0, 0.0)),
+    );
+
+    // the bullet and the wall both use the smaller group 0 padding
+    advance_to_event(&mut collider, 1.125);
+    let (event, profile_1, profile_2) = collider.next().unwrap();
+    assert_eq!(
+        (event, profile_1.id, profile_2.id),
+        (HbEvent::Separate, 0, 2)
+    );
+
+    // the boulder and the wall use the larger group 1 padding
+    advance_to_event(&mut collider, 2.0);
+    let (event, profile_1, profile_2) = collider.next().unwrap();
+    assert_eq!(
+        (event, profile_1.id, profile_2.id),
+        (HbEvent::Separate, 0, 1)
+    );
+}
+
+#[test]
+fn test_oriented_rect_ramp() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let ramp = Shape::oriented_rect(v2(8.0, 1.0), consts::FRAC_PI_6);
+    collider.add_hitbox(0.into(), ramp.place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(
+        1.into(),
+        Shape::circle(1.0).place(v2(0.0, 5.0)).moving(v2(0.0, -1.0)),
+    );
+
+    assert_eq!(
+        next_event(&mut collider),
+        (HbEvent::Collide, 0.into(), 1.into())
+    );
+    // the center of the circle is 1.0 from the center of the ramp along the
+    // normal of its top face at the time of contact
+    let contact_height = 1.0 / consts::FRAC_PI_6.cos();
+    assert!((collider.time() - (5.0 - contact_height)).abs() < EPS);
+    let normal = collider.overlap_normal(1, 0).unwrap();
+    assert!((normal.dir() - v2(-0.5, (0.75 as Float).sqrt())).len() < EPS);
+}
+
+#[test]
+fn test_expiry_events() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0)
+            .place(v2(0.0, 0.0))
+            .moving_until(v2(1.0, 0.0), 3.0),
+    );
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(10.0, 0.0)).still());
+    collider.set_expiry_events(true);
+
+    advance_to_event(&mut collider, 3.0);
+    assert_eq!(collider.peek_event(), Some((HbEvent::Expire, 0, 0, 3.0)));
+    assert_eq!(collider.next(), Some((HbEvent::Expire, 0.into(), 0.into())));
+    let hitbox = collider.get_hitbox(0);
+    assert_eq!(hitbox.value.pos, v2(3.0, 0.0));
+    assert_eq!(hitbox.vel.value, v2(0.0, 0.0));
+    assert_eq!(hitbox.vel.end_time, Float::INFINITY);
+    assert!(collider.next().is_none());
+
+    collider.set_time(20.0);
+    assert!(collider.next().is_none());
+    assert_eq!(collider.get_hitbox(0).value.pos, v2(3.0, 0.0));
+}
+
+#[test]
+fn test_bounds() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.set_bounds(Some(Shape::rect(v2(20.0, 20.0)).place(v2(0.0, 0.0))));
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0).place(v2(0.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    let mut hitbox = Shape::square(2.0).place(v2(-5.0, 5.0)).still();
+    hitbox.vel.accel = v2(0.0, -2.0);
+    collider.add_hitbox(1.into(), hitbox);
+    collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(20.0, 15.0)).still());
+    assert_eq!(collider.out_of_bounds(), vec![2.into()]);
+
+    loop {
+        let time = collider.next_time();
+        collider.set_time(time);
+        if !collider.next_is_internal() {
+            break;
+        }
+        assert!(collider.next().is_none());
+    }
+    let (event, id_1, id_2, time) = collider.peek_event().unwrap();
+    assert_eq!((event, id_1, id_2), (HbEvent::ExitBounds, 1, 1));
+    assert!((time - (14 as Float).sqrt()).abs() < EPS);
+    advance_to_event(&mut collider, time);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::ExitBounds, 1.into(), 1.into()))
+    );
+    assert!(collider.next().is_none());
+
+    advance_to_event(&mut collider, 9.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::ExitBounds, 0.into(), 0.into()))
+    );
+    assert!(collider.next().is_none());
+    assert_eq!(collider.out_of_bounds(), vec![1.into(), 2.into()]);
+
+    collider.set_hitbox_vel(0, HbVel::moving(v2(-1.0, 0.0)));
+    advance(&mut collider, 15.0);
+    collider.set_hitbox_vel(0, HbVel::moving(v2(1.0, 0.0)));
+    advance_to_event(&mut collider, 21.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::ExitBounds, 0.into(), 0.into()))
+    );
+
+    collider.set_bounds(None);
+    assert!(collider.out_of_bounds().is_empty());
+    advance(&mut collider, 100.0);
+}
+
+#[test]
+fn test_query_region() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::circle(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(7.0, 0.0)).still());
+    collider.add_hitbox(
+        2.into(),
+        Shape::square(2.0).place(v2(-9.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+
+    let region = Shape::square(2.0).place(v2(1.8, 1.8));
+    assert!(collider.query_overlaps(&region, &5.into()).is_empty());
+    let region = Aabb::from_placed_shape(&region);
+    assert_eq!(collider.query_region(&region, &5.into()), vec![0.into()]);
+
+    let region = Aabb::new(v2(-6.0, -1.0), v2(6.0, 1.0));
+    assert_eq!(
+        sort(collider.query_region(&region, &5.into())),
+        vec![0.into(), 1.into()]
+    );
+    advance(&mut collider, 2.0);
+    assert_eq!(
+        sort(collider.query_region(&region, &5.into())),
+        vec![0.into(), 1.into(), 2.into()]
+    );
+}
+
+#[test]
+fn test_hitbox_velocity_at() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0).place(v2(0.0, 0.0)).moving(v2(1.0, 2.0)),
+    );
+    let mut hitbox = Shape::square(2.0).place(v2(10.0, 0.0)).moving(v2(0.0, 3.0));
+    hitbox.vel.accel = v2(0.0, -1.0);
+    collider.add_hitbox(1.into(), hitbox);
+
+    assert_eq!(collider.hitbox_velocity_at(0, 5.0), v2(1.0, 2.0));
+    assert_eq!(collider.hitbox_velocity_at(1, 0.0), v2(0.0, 3.0));
+    assert_eq!(collider.hitbox_velocity_at(1, 2.0), v2(0.0, 1.0));
+    advance(&mut collider, 1.0);
+    assert_eq!(collider.hitbox_velocity_at(1, 1.0), v2(0.0, 2.0));
+    assert_eq!(collider.hitbox_velocity_at(1, 4.0), v2(0.0, -1.0));
+}
+
+#[test]
+#[should_panic]
+fn test_hitbox_velocity_at_past() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0).place(v2(0.0, 0.0)).moving(v2(1.0, 2.0)),
+    );
+    advance(&mut collider, 1.0);
+    collider.hitbox_velocity_at(0, 0.5);
+}
+
+#[test]
+fn test_sorted_overlaps() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    for id in (1..20).rev() {
+        let pos = v2(id as Float * 0.1, 0.0);
+        collider.add_hitbox(id.into(), Shape::square(2.0).place(pos).still());
+    }
+    let expected: Vec<TestHbProfile> = (1..20).map(|id| id.into()).collect();
+    assert_eq!(collider.get_overlaps(10).len(), 18);
+    let shape = Shape::circle(1.0).place(v2(1.0, 0.0));
+    assert_eq!(collider.query_overlaps(&shape, &0.into()), expected);
+    let region = Aabb::from_placed_shape(&shape);
+    assert_eq!(collider.query_region(&region, &0.into()), expected);
+
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(1.0, 0.0)).still());
+    assert_eq!(collider.get_overlaps(0), expected);
+    assert_eq!(collider.remove_hitbox(0), expected);
+}
+
+#[test]
+fn test_separation_normal() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(
+        1.into(),
+        Shape::square(2.0).place(v2(0.0, 1.0)).moving(v2(0.0, 1.0)),
+    );
+
+    advance_to_event(&mut collider, 1.25);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Separate, 0.into(), 1.into()))
+    );
+    let normal = collider.separation_normal(1, 0);
+    assert_eq!(normal.dir(), v2(0.0, 1.0));
+    assert!((normal.len() + 0.25).abs() < 1e-9);
+    let normal = collider.separation_normal(0, 1);
+    assert_eq!(normal.dir(), v2(0.0, -1.0));
+}
+
+#[test]
+fn test_compound() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let parts = vec![
+        (v2(0.0, 0.0), Shape::circle(2.0)),
+        (v2(-2.0, 0.0), Shape::rect(v2(2.0, 1.0))),
+        (v2(2.0, 0.0), Shape::rect(v2(2.0, 1.0))),
+    ];
+    assert!(collider
+        .add_compound(0.into(), parts, HbVel::still())
+        .is_empty());
+    assert_eq!(collider.get_compound(0).len(), 3);
+    assert_eq!(
+        collider.add_hitbox(2.into(), Shape::square(3.0).place(v2(0.0, 0.0)).still()),
+        vec![0.into()]
+    );
+    assert_eq!(collider.get_overlaps(2), vec![0.into()]);
+    assert_eq!(collider.remove_hitbox(2), vec![0.into()]);
+
+    collider.add_hitbox(
+        1.into(),
+        Shape::rect(v2(1.0, 10.0))
+            .place(v2(10.0, 0.0))
+            .moving(v2(-1.0, 0.0)),
+    );
+    let mut events = Vec::new();
+    while collider.time() < 20.0 {
+        while let Some(event) = collider.next_detailed() {
+            events.push((
+                event.event,
+                event.profile_1,
+                event.profile_2,
+                collider.time(),
+            ));
+            if collider.time() == 10.0 {
+                assert_eq!(collider.get_overlaps(1), vec![0.into()]);
+            }
+        }
+        let time = collider.next_time().min(20.0);
+        collider.set_time(time);
+    }
+    assert_eq!(
+        events,
+        vec![
+            (HbEvent::Collide, 0.into(), 1.into(), 6.5),
+            (HbEvent::Separate, 0.into(), 1.into(), 13.75),
+        ]
+    );
+
+    collider.set_compound_vel(0, HbVel::moving(v2(-2.0, 0.0)));
+    assert_eq!(collider.get_compound(0)[1].vel.value, v2(-2.0, 0.0));
+    advance_to_event(&mut collider, 26.5);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+    assert_eq!(collider.remove_compound(0), vec![1.into()]);
+    assert!(collider.get_overlaps(1).is_empty());
+    advance(&mut collider, 30.0);
+}
+
+#[test]
+fn test_compound_exit_bounds() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.set_bounds(Some(Shape::rect(v2(20.0, 20.0)).place(v2(0.0, 0.0))));
+    let parts = vec![
+        (v2(0.0, 0.0), Shape::square(2.0)),
+        (v2(4.0, 0.0), Shape::square(2.0)),
+    ];
+    collider.add_compound(0.into(), parts, HbVel::moving(v2(1.0, 0.0)));
+    advance_to_event(&mut collider, 5.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::ExitBounds, 0.into(), 0.into()))
+    );
+    advance(&mut collider, 20.0);
+    assert_eq!(collider.out_of_bounds(), vec![0.into()]);
+}
+
+#[test]
+fn test_compound_query_point() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let parts = vec![
+        (v2(0.0, 0.0), Shape::square(2.0)),
+        (v2(1.0, 0.0), Shape::square(2.0)),
+    ];
+    collider.add_compound(1.into(), parts, HbVel::still());
+    assert_eq!(
+        collider.query_point(v2(0.5, 0.0), &0.into()),
+        vec![1.into()]
+    );
+}
+
+#[test]
+fn test_compound_query_sweep() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let parts = vec![
+        (v2(0.0, 0.0), Shape::square(2.0)),
+        (v2(3.0, 0.0), Shape::square(2.0)),
+    ];
+    collider.add_compound(1.into(), parts, HbVel::still());
+    let shape = Shape::square(1.0).place(v2(-5.0, 0.0));
+    assert_eq!(
+        collider.query_sweep(&shape, v2(1.0, 0.0), 10.0, &0.into()),
+        vec![1.into()]
+    );
+}
+
+#[test]
+fn test_compound_collisions_within() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let parts = vec![
+        (v2(0.0, 0.0), Shape::square(2.0)),
+        (v2(3.0, 0.0), Shape::square(2.0)),
+    ];
+    collider.add_compound(1.into(), parts, HbVel::still());
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(1.0).place(v2(-5.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    assert_eq!(collider.collisions_within(0, 10.0), vec![(1.into(), 3.5)]);
+}
+
+#[test]
+fn test_compound_persistent_overlaps() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let parts = vec![
+        (v2(0.0, 0.0), Shape::square(2.0)),
+        (v2(3.0, 0.0), Shape::square(2.0)),
+    ];
+    collider.add_compound(0.into(), parts, HbVel::still());
+    collider.add_hitbox(1.into(), Shape::square(4.0).place(v2(1.5, 0.0)).still());
+    advance(&mut collider, 2.0);
+    assert_eq!(
+        collider.persistent_overlaps(0.0, 1.0),
+        vec![(0.into(), 1.into(), 1.5)]
+    );
+}
+
+#[test]
+fn test_still_hitboxes_schedule_no_events() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    for i in 0..10 {
+        let pos = v2(i as Float * 1.5, 0.0);
+        collider.add_hitbox(i.into(), Shape::square(2.0).place(pos).still());
+    }
+    assert_eq!(
+        collider.add_hitbox(10.into(), Shape::circle(1.0).place(v2(3.0, 1.2)).still()),
+        vec![2.into()]
+    );
+    assert_eq!(collider.pending_event_count(), 0);
+    assert_eq!(collider.next_time(), Float::INFINITY);
+
+    collider.set_hitbox_vel(10, HbVel::moving(v2(0.0, 1.0)));
+    assert!(collider.pending_event_count() > 0);
+    collider.set_hitbox_vel(10, HbVel::still());
+    assert_eq!(collider.pending_event_count(), 0);
+}
+
+#[test]
+fn test_contains() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    let hitbox = Shape::square(2.0).place(v2(1.0, 2.0)).still();
+    assert!(!collider.contains(0));
+    assert!(collider.try_get_hitbox(0).is_none());
+    collider.add_hitbox(0.into(), hitbox.clone());
+    assert!(collider.contains(0));
+    assert_eq!(collider.try_get_hitbox(0), Some(hitbox));
+    collider.add_compound(
+        1.into(),
+        vec![(v2(10.0, 0.0), Shape::circle(2.0))],
+        HbVel::still(),
+    );
+    assert!(collider.contains(1));
+    collider.remove_hitbox(0);
+    collider.remove_compound(1);
+    assert!(!collider.contains(0));
+    assert!(!collider.contains(1));
+    assert!(collider.try_get_hitbox(0).is_none());
+}
+
+#[test]
+fn test_set_frozen() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0).place(v2(0.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(10.0, 0.0)).still());
+    collider.add_hitbox(
+        2.into(),
+        Shape::square(2.0)
+            .place(v2(0.0, 20.0))
+            .moving_until(v2(0.0, 1.0), 5.0),
+    );
+
+    advance(&mut collider, 3.0);
+    collider.set_frozen(true);
+    assert!(collider.is_frozen());
+    collider.set_time(6.0);
+    collider.set_time(10.0);
+    assert_eq!(collider.time(), 10.0);
+    assert!(collider.next().is_none());
+    assert_eq!(collider.get_hitbox(0).value.pos, v2(3.0, 0.0));
+    assert_eq!(collider.get_hitbox(2).value.pos, v2(0.0, 23.0));
+    assert_eq!(collider.get_hitbox(2).vel.end_time, 12.0);
+    assert!(collider.next_time() > 10.0);
+    assert_eq!(collider.scheduled_events()[0].0, collider.next_time());
+    collider.set_hitbox_vel(0, HbVel::moving_until(v2(1.0, 0.0), 30.0));
+    assert_eq!(collider.get_hitbox(0).vel.end_time, 30.0);
+
+    collider.set_frozen(false);
+    assert_eq!(collider.time(), 10.0);
+    assert_eq!(collider.get_hitbox(0).vel.end_time, 30.0);
+    collider.set_hitbox_vel(2, HbVel::still());
+    advance_to_event(&mut collider, 15.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+    assert_eq!(collider.get_hitbox(0).value.pos, v2(8.0, 0.0));
+}
+
+#[test]
+fn test_invalidated_too_small() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.set_invalidation_events(true);
+    let mut hitbox = Shape::rect(v2(2.0, 1.0)).place(v2(0.0, 0.0)).still();
+    hitbox.vel.resize = v2(-1.0, 0.0);
+    collider.add_hitbox(0.into(), hitbox);
+
+    while collider.next_is_internal() {
+        collider.set_time(collider.next_time());
+        assert!(collider.next().is_none());
+    }
+    let (event, id_1, id_2, time) = collider.peek_event().unwrap();
+    assert_eq!(
+        (event, id_1, id_2),
+        (HbEvent::Invalidated(InvalidationReason::TooSmall), 0, 0)
+    );
+    assert!((time - 1.775).abs() < 1e-9);
+    assert!(collider.scheduled_events().contains(&(
+        time,
+        EventDescription::Invalidated(0, InvalidationReason::TooSmall)
+    )));
+    collider.set_time(time);
+    assert_eq!(
+        collider.next(),
+        Some((
+            HbEvent::Invalidated(InvalidationReason::TooSmall),
+            0.into(),
+            0.into()
+        ))
+    );
+    let dims = collider.get_hitbox(0).value.dims();
+    assert_eq!(collider.get_hitbox(0).vel.resize, v2(0.0, 0.0));
+    assert_eq!(collider.next_time(), Float::INFINITY);
+    collider.set_time(10.0);
+    assert_eq!(collider.get_hitbox(0).value.dims(), dims);
+
+    // the hitbox may keep moving as long as it does not shrink further
+    collider.set_hitbox_vel(0, HbVel::moving(v2(1.0, 0.0)));
+    let mut shrinking = HbVel::still();
+    shrinking.resize = v2(-1.0, 0.0);
+    assert_eq!(
+        collider.try_set_hitbox_vel(0, shrinking),
+        Err(ColliderError::ShapeTooSmall)
+    );
+    advance(&mut collider, 12.0);
+    assert_eq!(collider.get_hitbox(0).value.pos.x, 2.0);
+}
+
+#[test]
+fn test_invalidated_duration_passed() {
+    let mut collider = ColliderBuilder::new()
+        .cell_width(4.0)
+        .padding(0.25)
+        .invalidation_events(true)
+        .build::<TestHbProfile>();
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0)
+            .place(v2(0.0, 0.0))
+            .moving_until(v2(1.0, 0.0), 3.0),
+    );
+
+    advance_to_event(&mut collider, 3.0);
+    let event = HbEvent::Invalidated(InvalidationReason::DurationPassed);
+    assert_eq!(collider.peek_event(), Some((event, 0, 0, 3.0)));
+    assert_eq!(collider.next(), Some((event, 0.into(), 0.into())));
+    let hitbox = collider.get_hitbox(0);
+    assert_eq!(hitbox.value.pos, v2(3.0, 0.0));
+    assert_eq!(hitbox.vel.end_time, Float::INFINITY);
+    assert!(collider.next().is_none());
+
+    collider.set_time(20.0);
+    assert_eq!(collider.get_hitbox(0).value.pos, v2(3.0, 0.0));
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic]
+fn test_duration_passed_without_invalidation_events() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.set_invalidation_events(true);
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0).place(v2(0.0, 0.0)).still_until(3.0),
+    );
+    collider.set_invalidation_events(false);
+    collider.set_time(3.0);
+    collider.next();
+}
+
+#[test]
+fn test_events_within() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0).place(v2(0.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(10.0, 0.0)).still());
+    collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(0.0, 10.0)).still());
+    let next_time = collider.next_time();
+
+    assert!(collider.events_within(7.0).is_empty());
+    assert_eq!(
+        collider.events_within(20.0),
+        vec![
+            (HbEvent::Collide, 0, 1, 8.0),
+            (HbEvent::Separate, 0, 1, 12.25)
+        ]
+    );
+    assert_eq!(collider.time(), 0.0);
+    assert_eq!(collider.next_time(), next_time);
+    assert_eq!(collider.get_hitbox(0).value.pos, v2(0.0, 0.0));
+
+    advance_to_event(&mut collider, 8.0);
+    assert_eq!(
+        collider.events_within(0.0),
+        vec![(HbEvent::Collide, 0, 1, 8.0)]
+    );
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+}
+
+#[test]
+fn test_events_within_stops_at_invalid_hitbox() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(2.0)
+            .place(v2(0.0, 0.0))
+            .moving_until(v2(1.0, 0.0), 10.0),
+    );
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(10.0, 0.0)).still());
+    assert_eq!(
+        collider.events_within(20.0),
+        vec![(HbEvent::Collide, 0, 1, 8.0)]
+    );
+
+    collider.set_invalidation_events(true);
+    let invalidated = HbEvent::Invalidated(InvalidationReason::DurationPassed);
+    assert_eq!(
+        collider.events_within(20.0),
+        vec![(HbEvent::Collide, 0, 1, 8.0), (invalidated, 0, 0, 10.0)]
+    );
+}
+
+#[test]
+fn test_rect_cells() {
+    let mut collider = ColliderBuilder::new()
+        .cell_dims(v2(16.0, 2.0))
+        .padding(0.25)
+        .build::<TestHbProfile>();
+    assert_eq!(collider.cell_dims(), v2(16.0, 2.0));
+    assert_eq!(collider.cell_of(v2(17.0, 3.0)), (1, 1));
+    assert_eq!(collider.cell_of(v2(-1.0, -0.5)), (-1, -1));
+
+    collider.add_hitbox(
+        0.into(),
+        Shape::square(1.0).place(v2(0.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(
+        1.into(),
+        Shape::square(1.0)
+            .place(v2(-20.0, 10.0))
+            .moving(v2(1.0, -1.0)),
+    );
+    collider.add_hitbox(2.into(), Shape::square(1.0).place(v2(30.0, 0.0)).still());
+    let events = collider.scheduled_events();
+    assert!(events.contains(&(16.0, EventDescription::Reiterate(0))));
+    assert!(events.contains(&(2.0, EventDescription::Reiterate(1))));
+    assert_eq!(collider.hitboxes_in_cell((1, 0)), vec![0, 2]);
+
+    advance_to_event(&mut collider, 29.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 2.into()))
+    );
+}
+
+#[test]
+fn test_inverted_rect_arena() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::inverted_rect(v2(20.0, 10.0))
+            .place(v2(0.0, 0.0))
+            .still(),
+    );
+    assert!(collider
+        .add_hitbox(
+            1.into(),
+            Shape::square(2.0).place(v2(0.0, 0.0)).moving(v2(1.0, 0.0))
+        )
+        .is_empty());
+
+    advance_to_event(&mut collider, 9.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+    collider.set_hitbox_vel(1, HbVel::moving(v2(-1.0, 0.0)));
+    advance_to_event(&mut collider, 9.25);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Separate, 0.into(), 1.into()))
+    );
+    assert!(collider.get_overlaps(1).is_empty());
+    advance(&mut collider, 20.0);
+
+    // far outside the rectangle, where the inverted rect occupies no cells
+    assert_eq!(
+        collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(50.0, 0.0)).still()),
+        vec![0.into()]
+    );
+    assert_eq!(
+        collider.query_point(v2(-50.0, 40.0), &5.into()),
+        vec![0.into()]
+    );
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(1.into(), Shape::square(2.0).place(v2(0.0, 40.0)).still());
+    assert_eq!(
+        collider.add_hitbox(
+            0.into(),
+            Shape::inverted_rect(v2(20.0, 10.0))
+                .place(v2(0.0, 0.0))
+                .still()
+        ),
+        vec![1.into()]
+    );
+}
+
+fn simultaneous_collision_order(deterministic_ties: bool) -> Vec<HbId> {
+    let mut collider: Collider<TestHbProfile> = ColliderBuilder::new()
+        .cell_width(4.0)
+        .padding(0.25)
+        .deterministic_ties(deterministic_ties)
+        .build();
+    let wall = Shape::rect(v2(2.0, 40.0)).place(v2(0.0, 0.0)).still();
+    collider.add_hitbox(0.into(), wall);
+    for id in (1..6).rev() {
+        let mut hitbox = Shape::square(2.0)
+            .place(v2(-10.0, (id as Float - 3.0) * 4.0))
+            .still();
+        hitbox.vel.value = v2(1.0, 0.0);
+        collider.add_hitbox(id.into(), hitbox);
+    }
+    advance_to_event(&mut collider, 8.0);
+    let mut ids = Vec::new();
+    while let Some((event, profile_1, profile_2)) = collider.next() {
+        assert_eq!(event, HbEvent::Collide);
+        assert_eq!(profile_1.id.min(profile_2.id), 0);
+        ids.push(profile_1.id.max(profile_2.id));
+    }
+    ids
+}
+
+#[test]
+fn test_deterministic_ties() {
+    assert_eq!(simultaneous_collision_order(true), vec![1, 2, 3, 4, 5]);
+    assert_eq!(simultaneous_collision_order(false), vec![5, 4, 3, 2, 1]);
+}
+
+#[test]
+fn test_predicted_contact() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::circle(2.0).place(v2(-5.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    collider.add_hitbox(1.into(), Shape::circle(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(0.0, 5.0)).still());
+    collider.add_hitbox(3.into(), Shape::square(2.0).place(v2(0.0, 5.5)).still());
+
+    assert_eq!(collider.predicted_contact(0, 1), Some((3.0, v2(-1.0, 0.0))));
+    assert_eq!(collider.predicted_contact(1, 0), Some((3.0, v2(-1.0, 0.0))));
+    assert_eq!(collider.predicted_contact(0, 2), None);
+    assert_eq!(collider.predicted_contact(2, 3), Some((0.0, v2(0.0, 5.25))));
+
+    advance(&mut collider, 1.0);
+    assert_eq!(collider.predicted_contact(0, 1), Some((3.0, v2(-1.0, 0.0))));
+
+    // a contact that is too far in the future is ignored
+    collider.set_hitbox_vel(0, HbVel::moving(v2(1e-60, 1.0)));
+    collider.set_hitbox_vel(1, HbVel::moving(v2(0.0, 1.0)));
+    assert_eq!(collider.predicted_contact(0, 1), None);
+}
+
+#[test]
+fn test_for_each_overlap() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        0.into(),
+        Shape::rect(v2(20.0, 12.0)).place(v2(0.0, 0.0)).still(),
+    );
+    collider.add_hitbox(
+        1.into(),
+        Shape::circle(2.0)
+            .place(v2(-6.0, 3.0))
+            .moving(v2(5.0, -2.0)),
+    );
+    collider.add_hitbox(2.into(), Shape::square(2.0).place(v2(30.0, 0.0)).still());
+
+    let mut overlaps = Vec::new();
+    collider.for_each_overlap(0, |profile| overlaps.push(profile));
+    assert_eq!(overlaps, vec![1.into()]);
+    overlaps.clear();
+    collider.for_each_overlap(2, |profile| overlaps.push(profile));
+    assert_eq!(overlaps, vec![]);
+
+    // the query shape shares many grid cells with hitboxes 0 and 1, but each
+    // hitbox is only reported once
+    let test_shape = Shape::rect(v2(16.0, 8.0)).place(v2(-2.0, 1.0));
+    let mut overlaps = Vec::new();
+    collider.for_each_query_overlap(&test_shape, &5.into(), |profile| overlaps.push(profile));
+    assert_eq!(sort(overlaps), vec![0.into(), 1.into()]);
+    assert_eq!(
+        collider.query_overlaps(&test_shape, &5.into()),
+        vec![0.into(), 1.into()]
+    );
+
+    advance(&mut collider, 1.0);
+    let test_shape = Shape::circle(2.0).place(v2(30.0, 2.0));
+    let mut overlaps = Vec::new();
+    collider.for_each_query_overlap(&test_shape, &5.into(), |profile| overlaps.push(profile));
+    assert_eq!(overlaps, vec![2.into()]);
+
+    // a compound is reported once, although several of its parts overlap
+    let parts = vec![
+        (v2(29.0, -1.0), Shape::square(1.0)),
+        (v2(31.0, -1.0), Shape::square(1.0)),
+        (v2(30.0, 1.0), Shape::square(1.0)),
+    ];
+    collider.add_compound(3.into(), parts, HbVel::still());
+    assert_eq!(collider.get_overlaps(2), vec![3.into()]);
+    let mut overlaps = Vec::new();
+    collider.for_each_overlap(2, |profile| overlaps.push(profile));
+    assert_eq!(overlaps, vec![3.into()]);
+    let mut overlaps = Vec::new();
+    collider.for_each_query_overlap(&test_shape, &5.into(), |profile| overlaps.push(profile));
+    assert_eq!(sort(overlaps), vec![2.into(), 3.into()]);
+    assert_eq!(
+        collider.query_overlaps(&test_shape, &5.into()),
+        vec![2.into(), 3.into()]
+    );
+}
+
+#[test]
+fn test_one_way_platform() {
+    let platform = GroupHbProfile {
+        one_way: Some(Card::PlusY),
+        ..GroupHbProfile::new(0, 0)
+    };
+    let player = GroupHbProfile::new(1, 0);
+    let mut collider = Collider::<GroupHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(
+        platform,
+        Shape::rect(v2(10.0, 1.0)).place(v2(0.0, 0.0)).still(),
+    );
+
+    // jumping up through the platform does not collide
+    collider.add_hitbox(
+        player,
+        Shape::square(1.0).place(v2(0.0, -5.0)).moving(v2(0.0, 2.0)),
+    );
+    assert_eq!(collider.collisions_within(1, 10.0), vec![]);
+    assert_eq!(collider.projected_stop(1), None);
+    let sweep = Shape::square(1.0).place(v2(2.0, -5.0));
+    assert_eq!(
+        collider.query_sweep(&sweep, v2(0.0, 2.0), 5.0, &player),
+        vec![]
+    );
+    advance(&mut collider, 5.0);
+
+    // falling onto the platform collides
+    collider.set_hitbox_vel(1, HbVel::moving(v2(0.0, -2.0)));
+    assert_eq!(collider.collisions_within(1, 10.0), vec![(platform, 7.0)]);
+    assert_eq!(
+        collider.projected_stop(1),
+        Some((platform, v2(0.0, 1.0), 7.0))
+    );
+    let sweep = Shape::square(1.0).place(v2(2.0, 5.0));
+    assert_eq!(
+        collider.query_sweep(&sweep, v2(0.0, -2.0), 5.0, &player),
+        vec![platform]
+    );
+    advance_to_event(&mut collider, 7.0);
+    assert_eq!(collider.next(), Some((HbEvent::Collide, platform, player)));
+
+    // walking off the side of the platform separates
+    collider.set_hitbox_vel(1, HbVel::moving(v2(2.0, 0.0)));
+    advance_to_event(&mut collider, 9.875);
+    assert_eq!(collider.next(), Some((HbEvent::Separate, platform, player)));
+}
+
+#[test]
+fn test_cell_of() {
+    let collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    assert_eq!(collider.cell_width(), 4.0);
+    assert_eq!(collider.cell_of(v2(0.0, 0.0)), (0, 0));
+    assert_eq!(collider.cell_of(v2(3.9, 4.0)), (0, 1));
+    assert_eq!(collider.cell_of(v2(-0.1, -8.0)), (-1, -2));
+    assert_eq!(collider.cell_of(v2(10.0, -7.9)), (2, -2));
+}
+
+#[test]
+fn test_builder_expiry_events() {
+    let mut collider: Collider<TestHbProfile> = ColliderBuilder::new()
+        .cell_width(4.0)
+        .padding(0.25)
+        .expiry_events(true)
+        .build();
+    collider.add_hitbox(
+        0.into(),
+        Shape::circle(2.0)
+            .place(v2(0.0, 0.0))
+            .moving_until(v2(0.0, 1.0), 2.5),
+    );
+    advance_to_event(&mut collider, 2.5);
+    assert_eq!(collider.next(), Some((HbEvent::Expire, 0.into(), 0.into())));
+    assert_eq!(collider.get_hitbox(0).value.pos, v2(0.0, 2.5));
+}
+
+#[test]
+#[should_panic(expected = "requires cell_width > padding")]
+fn test_builder_small_cell_width() {
+    let _: Collider<TestHbProfile> = ColliderBuilder::new()
+        .cell_width(0.25)
+        .padding(0.25)
+        .build();
+}
+
+#[test]
+fn test_point_marker() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::point().place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(
+        1.into(),
+        Shape::circle(2.0).place(v2(-5.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    advance_to_event(&mut collider, 4.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+    advance_to_event(&mut collider, 6.25);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Separate, 0.into(), 1.into()))
+    );
+
+    let hitbox = Shape::point().place(v2(0.0, 0.0)).moving(v2(0.0, 0.0));
+    let mut resizing = hitbox.clone();
+    resizing.vel.resize = v2(1.0, 1.0);
+    assert_eq!(
+        collider.try_add_hitbox(2.into(), resizing),
+        Err(ColliderError::InvalidResize)
+    );
+}
+
+#[test]
+fn test_rebase_time() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(
+        1.into(),
+        Shape::square(2.0)
+            .place(v2(-20.0, 0.0))
+            .moving_until(v2(1.0, 0.0), 40.0),
+    );
+    collider.add_hitbox(
+        2.into(),
+        Shape::square(2.0).place(v2(0.5, 0.0)).moving(v2(0.0, 0.0)),
+    );
+    advance(&mut collider, 10.0);
+    collider.rebase_time();
+    assert_eq!(collider.time(), 0.0);
+    assert_eq!(collider.overlap_start_time(0, 2), Some(-10.0));
+    let hitbox = collider.get_hitbox(1);
+    assert_eq!(hitbox.value.pos, v2(-10.0, 0.0));
+    assert_eq!(hitbox.vel.end_time, 30.0);
+
+    advance_to_event(&mut collider, 8.0);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 0.into(), 1.into()))
+    );
+    assert_eq!(collider.next(), None);
+    advance_to_event(&mut collider, 8.5);
+    assert_eq!(
+        collider.next(),
+        Some((HbEvent::Collide, 1.into(), 2.into()))
+    );
+}
+
+#[test]
+fn test_distance() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(
+        1.into(),
+        Shape::circle(2.0)
+            .place(v2(-10.0, 0.0))
+            .moving(v2(1.0, 0.0)),
+    );
+    assert_eq!(collider.distance(0, 1), 8.0);
+    advance(&mut collider, 5.0);
+    assert_eq!(collider.distance(1, 0), 3.0);
+    advance_through_events(&mut collider, 9.5);
+    assert_eq!(collider.distance(0, 1), -1.5);
+}
+
+#[test]
+fn test_pending_event_count() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    assert_eq!(collider.pending_event_count(), 0);
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    assert_eq!(collider.pending_event_count(), 0);
+    assert_eq!(collider.hitbox_event_count(0), 0);
+    collider.add_hitbox(
+        1.into(),
+        Shape::square(2.0).place(v2(-3.0, 0.0)).moving(v2(1.0, 0.0)),
+    );
+    // a collide event and a reiterate event
+    assert_eq!(collider.pending_event_count(), 2);
+    assert_eq!(collider.hitbox_event_count(0), 1);
+    assert_eq!(collider.hitbox_event_count(1), 2);
+    assert_eq!(
+        collider.pending_event_count(),
+        collider.scheduled_events().len()
+    );
+    collider.remove_hitbox(1);
+    assert_eq!(collider.pending_event_count(), 0);
+    assert_eq!(collider.hitbox_event_count(0), 0);
+}
+
+#[test]
+fn test_team() {
+    let a = GroupHbProfile {
+        team: Some(1),
+        ..GroupHbProfile::new(0, 0)
+    };
+    let b = GroupHbProfile {
+        team: Some(1),
+        ..GroupHbProfile::new(1, 0)
+    };
+    let c = GroupHbProfile {
+        team: Some(2),
+        ..GroupHbProfile::new(2, 0)
+    };
+    let d = GroupHbProfile::new(3, 0);
+    let mut collider = Collider::<GroupHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(a, Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    assert_eq!(
+        collider.add_hitbox(b, Shape::square(2.0).place(v2(1.0, 0.0)).still()),
+        vec![]
+    );
+    let mut overlaps = collider.add_hitbox(c, Shape::square(2.0).place(v2(0.0, 1.0)).still());
+    overlaps.sort_by_key(|profile| profile.id);
+    assert_eq!(overlaps, vec![a, b]);
+    let mut overlaps = collider.add_hitbox(d, Shape::square(2.0).place(v2(0.5, 0.5)).still());
+    overlaps.sort_by_key(|profile| profile.id);
+    assert_eq!(overlaps, vec![a, b, c]);
+    assert!(!collider.is_overlapping(0, 1));
+}
+
+#[test]
+fn test_advance_to() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(
+        1.into(),
+        Shape::square(2.0)
+            .place(v2(-20.0, 0.0))
+            .moving(v2(2.0, 0.0)),
+    );
+    let mut events = Vec::new();
+    collider.advance_to(9.0, |event, profile_1, profile_2| {
+        events.push((event, profile_1.id, profile_2.id))
+    });
+    assert_eq!(collider.time(), 9.0);
+    assert_eq!(events, vec![(HbEvent::Collide, 0, 1)]);
+
+    events.clear();
+    collider.advance_to(11.125, |event, profile_1, profile_2| {
+        events.push((event, profile_1.id, profile_2.id))
+    });
+    assert_eq!(collider.time(), 11.125);
+    assert_eq!(events, vec![(HbEvent::Separate, 0, 1)]);
+    assert!(collider.next().is_none());
+}
+
+#[test]
+fn test_interact_mask() {
+    let wall = GroupHbProfile {
+        interact_groups: &[],
+        ..GroupHbProfile::new(0, 3)
+    };
+    let pickup = GroupHbProfile {
+        interact_groups: &[],
+        ..GroupHbProfile::new(1, 5)
+    };
+    let player = GroupHbProfile {
+        interact_groups: &[],
+        interact_mask: Some(1 << 3),
+        ..GroupHbProfile::new(2, 1)
+    };
+    let mut collider = Collider::<GroupHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(wall, Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(pickup, Shape::square(2.0).place(v2(1.0, 0.0)).still());
+    let overlaps = collider.add_hitbox(player, Shape::square(2.0).place(v2(0.5, 0.0)).still());
+    assert_eq!(overlaps, vec![wall]);
+    assert_eq!(collider.query_point(v2(0.5, 0.0), &player), vec![wall]);
+
+    let player = GroupHbProfile {
+        interact_groups: &[],
+        interact_mask: Some((1 << 3) | (1 << 5)),
+        ..GroupHbProfile::new(3, 1)
+    };
+    let mut overlaps = collider.add_hitbox(player, Shape::square(2.0).place(v2(0.5, 0.0)).still());
+    overlaps.sort_by_key(|profile| profile.id);
+    assert_eq!(overlaps, vec![wall, pickup]);
+
+    // an empty mask takes precedence over `interact_groups`
+    let ghost = GroupHbProfile {
+        interact_groups: &[3, 5],
+        interact_mask: Some(0),
+        ..GroupHbProfile::new(4, 1)
+    };
+    assert!(collider
+        .add_hitbox(ghost, Shape::square(2.0).place(v2(0.5, 0.0)).still())
+        .is_empty());
+}
+
+#[test]
+fn test_set_hitbox_profile() {
+    // ghosts in group 1 only interact with group 0
+    let ghost_profile = |id| GroupHbProfile {
+        interact_groups: &[0],
+        ..GroupHbProfile::new(id, 1)
+    };
+    let wall = GroupHbProfile::new(0, 0);
+    let ghost = ghost_profile(1);
+    let player = ghost_profile(2);
+    let mut collider = Collider::<GroupHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(wall, Shape::square(2.0).place(v2(0.0, 0.0)).still());
+    collider.add_hitbox(ghost, Shape::square(2.0).place(v2(1.0, 0.0)).still());
+    assert_eq!(
+        collider.add_hitbox(
+            player,
+            Shape::square(2.0).place(v2(1.5, 0.0)).moving(v2(1.0, 0.0))
+        ),
+        vec![wall]
+    );
+
+    // as a member of group 0, the player now also interacts with the ghost
+    let powered = GroupHbProfile::new(2, 0);
+    assert_eq!(collider.set_hitbox_profile(2, powered), vec![]);
+    assert!(collider.is_overlapping(0, 2));
+    assert_eq!(collider.next(), Some((HbEvent::Collide, ghost, powered)));
+    advance_to_event(&mut collider, 0.75);
+    assert_eq!(collider.next(), Some((HbEvent::Separate, wall, powered)));
+
+    // ghosts do not interact with each other
+    let ghostly = ghost_profile(2);
+    assert_eq!(collider.set_hitbox_profile(2, ghostly), vec![ghost]);
+    assert!(!collider.is_overlapping(1, 2));
+    advance(&mut collider, 10.0);
+}
+
+#[test]
+fn test_swept_bounds() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.add_hitbox(0.into(), Shape::circle(2.0).place(v2(1.0, 2.0)).still());
+    let mut hitbox = Shape::rect(v2(2.0, 4.0))
+        .place(v2(0.0, 0.0))
+        .moving_until(v2(1.0, -1.0), 3.0);
+    hitbox.vel.resize = v2(2.0, 0.0);
+    collider.add_hitbox(1.into(), hitbox);
+
+    assert_eq!(
+        collider.swept_bounds(0, 10.0),
+        Shape::rect(v2(2.0, 2.0)).place(v2(1.0, 2.0))
+    );
+    // limited by the end time of the velocity
+    let bounds = collider.swept_bounds(1, 10.0);
+    assert_eq!((bounds.min_x(), bounds.max_x()), (-1.0, 7.0));
+    assert_eq!((bounds.min_y(), bounds.max_y()), (-5.0, 2.0));
+    let bounds = collider.swept_bounds(1, 1.0);
+    assert_eq!((bounds.min_x(), bounds.max_x()), (-1.0, 3.0));
+    assert_eq!((bounds.min_y(), bounds.max_y()), (-3.0, 2.0));
+    assert_eq!(
+        collider.swept_bounds(1, 0.0),
+        Shape::rect(v2(2.0, 4.0)).place(v2(0.0, 0.0))
+    );
+}
+
+#[test]
+fn test_reserve() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.reserve(100);
+    for i in 0..100 {
+        let pos = v2((i % 10) as Float * 3.0, (i / 10) as Float * 3.0);
+        assert_eq!(
+            collider.add_hitbox(i.into(), Shape::square(2.0).place(pos).still()),
+            vec![]
+        );
+    }
+    assert_eq!(
+        collider.query_point(v2(3.0, 6.0), &0.into()),
+        vec![21.into()]
+    );
+}
+
+#[test]
+fn test_hitbox_collide_time() {
+    let a = Shape::square(2.0)
+        .place(v2(0.0, 0.0))
+        .moving_until(v2(1.0, 0.0), 20.0);
+    let b = Shape::circle(2.0).place(v2(10.0, 0.0)).still();
+    assert_eq!(a.collide_time(&b, 5.0), 13.0);
+    assert_eq!(b.collide_time(&a, 5.0), 13.0);
+    let b = Shape::circle(2.0).place(v2(30.0, 0.0)).still();
+    assert_eq!(a.collide_time(&b, 5.0), Float::INFINITY);
+
+    let b = Shape::circle(2.0).place(v2(1.0, 0.0)).still();
+    assert_eq!(a.collide_time(&b, 5.0), 5.0);
+    assert_eq!(a.separate_time(&b, 5.0, 0.5), 8.5);
+    let b = Shape::circle(2.0).place(v2(-5.0, 0.0)).still();
+    assert_eq!(a.separate_time(&b, 5.0, 0.5), 5.0);
+}
+
+#[test]
+#[should_panic(expected = "time must not exceed 1")]
+fn test_set_time_past_high_time() {
+    let mut collider = Collider::<TestHbProfile>::new(4.0, 0.25);
+    collider.set_time(Float::MAX);
+}
+
+#[test]
+#[should_panic(expected = "requires now <= end_time")]
+fn test_hitbox_collide_time_after_end() {
+    let a = Shape::square(2.0)
+        .place(v2(0.0, 0.0))
+        .moving_until(v2(1.0, 0.0), 4.0);
+    let b = Shape::circle(2.0).place(v2(10.0, 0.0)).still();
+    a.collide_time(&b, 5.0);
+}
+
 //TODO test custom interactivities...
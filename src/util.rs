@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use float::Float;
 use fnv::FnvHashSet;
 use std::borrow::Borrow;
 use std::collections::{hash_set, HashSet};
@@ -19,10 +20,13 @@ use std::hash::Hash;
 
 pub use self::one_or_two::OneOrTwo;
 
-// returns the ascending root of a quadratic polynomial ax^2 + bx + c
-pub fn quad_root_ascending(a: f64, b: f64, c: f64) -> Option<f64> {
+// returns the ascending root of a quadratic polynomial ax^2 + bx + c; a
+// tangent double root is also returned, so that grazing contacts are found
+pub fn quad_root_ascending(a: Float, b: Float, c: Float) -> Option<Float> {
     let determinant = b * b - a * c * 4.0;
-    if determinant <= 0.0 {
+    if determinant == 0.0 && a != 0.0 {
+        Some(-b / (a * 2.0))
+    } else if determinant <= 0.0 {
         None
     } else if b >= 0.0 {
         Some((c * 2.0) / (-b - determinant.sqrt()))
@@ -31,6 +35,66 @@ pub fn quad_root_ascending(a: f64, b: f64, c: f64) -> Option<f64> {
     }
 }
 
+// evaluates the polynomial with the given coefficients (in increasing order of
+// degree) at x
+pub fn poly_eval(coeffs: &[Float], x: Float) -> Float {
+    coeffs.iter().rev().fold(0.0, |acc, &c| acc * x + c)
+}
+
+// returns the roots of the polynomial with the given coefficients (in
+// increasing order of degree) that lie strictly between 0 and max, in
+// ascending order; the roots of the derivative are used to split the range
+// into monotonic pieces, and each sign change is refined by bisection, so the
+// returned value is always on the non-negative side of the root
+pub fn poly_roots_within(coeffs: &[Float], max: Float) -> Vec<Float> {
+    let degree = match coeffs.iter().rposition(|&c| c != 0.0) {
+        Some(degree) if degree > 0 => degree,
+        _ => return Vec::new(),
+    };
+    let coeffs = &coeffs[..degree + 1];
+    // all roots are bounded in magnitude by the Cauchy bound
+    let bound = 1.0
+        + coeffs[..degree]
+            .iter()
+            .map(|&c| (c / coeffs[degree]).abs())
+            .fold(0.0, Float::max);
+    let max = max.min(bound);
+    let derivative: Vec<Float> = coeffs
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &c)| c * i as Float)
+        .collect();
+    let mut bounds = vec![0.0];
+    bounds.extend(poly_roots_within(&derivative, max));
+    bounds.push(max);
+    let mut result = Vec::new();
+    for pair in bounds.windows(2) {
+        let (mut lo, mut hi) = (pair[0], pair[1]);
+        let (lo_val, hi_val) = (poly_eval(coeffs, lo), poly_eval(coeffs, hi));
+        if (lo_val < 0.0 && hi_val > 0.0) || (lo_val > 0.0 && hi_val < 0.0) {
+            let rising = hi_val > 0.0;
+            for _ in 0..MAX_BISECTIONS {
+                let mid = (lo + hi) * 0.5;
+                if mid <= lo || mid >= hi {
+                    break;
+                }
+                if (poly_eval(coeffs, mid) < 0.0) == rising {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            result.push(if rising { hi } else { lo });
+        } else if hi_val == 0.0 && hi < max {
+            result.push(hi);
+        }
+    }
+    result
+}
+
+const MAX_BISECTIONS: usize = 100;
+
 const MIN_TIGHT_SET_CAPACITY: usize = 4;
 
 // a HashSet that will automatically shrink down in capacity to save space
@@ -81,6 +145,10 @@ impl<T: Hash + Eq> TightSet<T> {
         self.set.drain()
     }
 
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.set.is_empty()
     }
@@ -144,6 +212,7 @@ mod one_or_two {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use float::EPS;
 
     #[test]
     fn test_quad_root_ascending() {
@@ -154,4 +223,29 @@ mod tests {
         assert!(quad_root_ascending(-3.0, 0.0, -1.0).is_none());
         assert!(quad_root_ascending(1.0, 1.0, 1.0).is_none());
     }
+
+    #[test]
+    fn test_quad_root_ascending_tangent() {
+        assert_eq!(quad_root_ascending(1.0, -4.0, 4.0), Some(2.0));
+        assert_eq!(quad_root_ascending(-1.0, 4.0, -4.0), Some(2.0));
+        assert_eq!(quad_root_ascending(2.0, 0.0, 0.0), Some(0.0));
+        assert!(quad_root_ascending(0.0, 0.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_poly_roots_within() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        let roots = poly_roots_within(&[24.0, -50.0, 35.0, -10.0, 1.0], 3.5);
+        assert_eq!(roots.len(), 3);
+        for (root, expected) in roots.iter().zip(&[1.0, 2.0, 3.0]) {
+            assert!((root - expected).abs() < EPS);
+        }
+        assert!(poly_eval(&[24.0, -50.0, 35.0, -10.0, 1.0], roots[0]) >= 0.0);
+        assert!(poly_eval(&[24.0, -50.0, 35.0, -10.0, 1.0], roots[1]) >= 0.0);
+        assert_eq!(poly_roots_within(&[1.0, 0.0, 1.0], Float::INFINITY), vec![]);
+        assert_eq!(poly_roots_within(&[-1.0, 0.0, 0.0], 10.0), vec![]);
+        let roots = poly_roots_within(&[-2.0, 0.0, 0.5], Float::INFINITY);
+        assert_eq!(roots.len(), 1);
+        assert!((roots[0] - 2.0).abs() < EPS);
+    }
 }
@@ -0,0 +1,63 @@
+// Copyright 2016-2018 Matthew D. Michelotti
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "serde")]
+
+extern crate collider;
+extern crate serde_json;
+
+use collider::geom::{v2, Card, CardMask, Shape};
+use collider::Hitbox;
+
+#[test]
+fn test_serde_round_trip() {
+    let mut hitbox = Shape::circle(2.0)
+        .place(v2(1.5, -3.0))
+        .moving_until(v2(0.5, 1.0), 10.0);
+    hitbox.vel.resize = v2(0.25, 0.25);
+    hitbox.vel.accel = v2(0.0, -9.8);
+    let json = serde_json::to_string(&hitbox).unwrap();
+    assert_eq!(serde_json::from_str::<Hitbox>(&json).unwrap(), hitbox);
+
+    let shape = Shape::segment(v2(-4.0, 2.0));
+    let json = serde_json::to_string(&shape).unwrap();
+    assert_eq!(serde_json::from_str::<Shape>(&json).unwrap(), shape);
+
+    let shape = Shape::triangle(v2(-4.0, 2.0));
+    let json = serde_json::to_string(&shape).unwrap();
+    assert_eq!(serde_json::from_str::<Shape>(&json).unwrap(), shape);
+
+    let shape = Shape::oriented_rect(v2(4.0, 2.0), 0.5);
+    let json = serde_json::to_string(&shape).unwrap();
+    assert_eq!(serde_json::from_str::<Shape>(&json).unwrap(), shape);
+
+    let shape = Shape::point();
+    let json = serde_json::to_string(&shape).unwrap();
+    assert_eq!(serde_json::from_str::<Shape>(&json).unwrap(), shape);
+
+    let mut mask = CardMask::empty();
+    mask[Card::MinusY] = true;
+    let json = serde_json::to_string(&(mask, Card::PlusX)).unwrap();
+    assert!(serde_json::from_str::<(CardMask, Card)>(&json).unwrap() == (mask, Card::PlusX));
+
+    let bad_circle = r#"{"kind":"Circle","dims":{"x":2.0,"y":3.0}}"#;
+    assert!(serde_json::from_str::<Shape>(bad_circle).is_err());
+    let bad_rect = r#"{"kind":"Rect","dims":{"x":-2.0,"y":3.0}}"#;
+    assert!(serde_json::from_str::<Shape>(bad_rect).is_err());
+    let bad_oriented_rect =
+        r#"{"kind":"OrientedRect","dims":{"x":2.0,"y":3.0},"dir":{"x":0.0,"y":0.0}}"#;
+    assert!(serde_json::from_str::<Shape>(bad_oriented_rect).is_err());
+    let bad_point = r#"{"kind":"Point","dims":{"x":1.0,"y":1.0}}"#;
+    assert!(serde_json::from_str::<Shape>(bad_point).is_err());
+}